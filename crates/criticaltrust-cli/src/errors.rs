@@ -0,0 +1,34 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use criticaltrust::integrity::IntegrityError;
+use std::path::PathBuf;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error(transparent)]
+    Trust(#[from] criticaltrust::Error),
+    #[error(transparent)]
+    WalkDir(#[from] walkdir::Error),
+
+    #[error("Failed to read {}.", .0.display())]
+    Read(PathBuf, #[source] std::io::Error),
+    #[error("Failed to write {}.", .0.display())]
+    Write(PathBuf, #[source] std::io::Error),
+    #[error("Failed to parse {} as {}.", .0.display(), .1)]
+    Parse(PathBuf, &'static str, #[source] serde_json::Error),
+    #[error("Failed to write the output.")]
+    Output(#[source] std::io::Error),
+
+    #[error("The revocation information is not signed, and no --revocation-key was provided.")]
+    MissingRevocationKey,
+    #[error("A keys manifest (--keys) is required to verify {0}.")]
+    MissingKeysManifest(&'static str),
+    #[error("A keys manifest (--keys) cannot be used to verify {0}, only the trust root.")]
+    UnexpectedKeysManifest(&'static str),
+    #[error(
+        "The package tree did not pass the integrity checks. The following errors were found:\n\n{}",
+        .0.iter().map(|err| err.to_string()).collect::<Vec<_>>().join("\n")
+    )]
+    Integrity(Vec<IntegrityError>),
+}
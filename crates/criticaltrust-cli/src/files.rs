@@ -0,0 +1,104 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Helpers to read and write the files handled by the subcommands.
+
+use crate::errors::Error;
+use clap::Args;
+use criticaltrust::keys::{FileKeyPair, KeyPassphrase, KeyRole, PublicKey};
+use criticaltrust::manifests::KeysManifest;
+use criticaltrust::signatures::{Keychain, VerificationPolicy};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Options shared by the subcommands handling private keys.
+#[derive(Debug, Args)]
+pub(crate) struct PassphraseArgs {
+    /// Environment variable containing the passphrase of the private keys
+    #[arg(long, default_value = "CRITICALTRUST_PASSPHRASE")]
+    passphrase_env: String,
+}
+
+impl PassphraseArgs {
+    pub(crate) fn passphrase(&self) -> KeyPassphrase<'_> {
+        KeyPassphrase::EnvVar(&self.passphrase_env)
+    }
+
+    pub(crate) fn load_key(&self, path: &Path, role: KeyRole) -> Result<FileKeyPair, Error> {
+        Ok(FileKeyPair::load(path, role, self.passphrase())?)
+    }
+}
+
+/// Options shared by the subcommands verifying signatures.
+#[derive(Debug, Args)]
+pub(crate) struct TrustArgs {
+    /// Path to the JSON public key used as the root of trust
+    #[arg(long)]
+    trust_root: PathBuf,
+    /// Path to the keys manifest containing the keys trusted by the root
+    #[arg(long)]
+    keys: Option<PathBuf>,
+    /// Check the expiry of the revocation information as of the trusted timestamps of the
+    /// signatures rather than now, to re-audit archived manifests (revocations published after
+    /// the revocation information expired are not noticed)
+    #[arg(long)]
+    revocation_at_timestamp: bool,
+}
+
+impl TrustArgs {
+    /// Build a keychain trusting the root and, if provided, the keys manifest.
+    pub(crate) fn keychain(&self) -> Result<Keychain, Error> {
+        let trust_root: PublicKey = read_json(&self.trust_root, "a public key")?;
+        let mut keychain = Keychain::new(&trust_root)?;
+        if let Some(keys) = &self.keys {
+            keychain.load_all(&read_json::<KeysManifest>(keys, "a keys manifest")?)?;
+        }
+        Ok(keychain)
+    }
+
+    /// Policy the verified payloads must satisfy.
+    pub(crate) fn policy(&self) -> VerificationPolicy {
+        let policy = VerificationPolicy::new();
+        if self.revocation_at_timestamp {
+            policy.revocation_at_timestamp()
+        } else {
+            policy
+        }
+    }
+
+    /// Like [`keychain`](Self::keychain), but requires the keys manifest to be provided.
+    pub(crate) fn keychain_with_keys(&self, verifying: &'static str) -> Result<Keychain, Error> {
+        if self.keys.is_none() {
+            return Err(Error::MissingKeysManifest(verifying));
+        }
+        self.keychain()
+    }
+
+    /// Like [`keychain`](Self::keychain), but rejects the keys manifest, for payloads that are
+    /// verified against the trust root alone.
+    pub(crate) fn keychain_without_keys(&self, verifying: &'static str) -> Result<Keychain, Error> {
+        if self.keys.is_some() {
+            return Err(Error::UnexpectedKeysManifest(verifying));
+        }
+        self.keychain()
+    }
+}
+
+pub(crate) fn read_json<T: DeserializeOwned>(path: &Path, what: &'static str) -> Result<T, Error> {
+    let contents = std::fs::read(path).map_err(|e| Error::Read(path.into(), e))?;
+    serde_json::from_slice(&contents).map_err(|e| Error::Parse(path.into(), what, e))
+}
+
+/// Write the JSON representation of `value` to `path`, or to stdout if no path is provided.
+pub(crate) fn write_json<T: Serialize>(path: Option<&Path>, value: &T) -> Result<(), Error> {
+    let mut json = serde_json::to_string_pretty(value).expect("failed to serialize the output");
+    json.push('\n');
+    match path {
+        Some(path) => std::fs::write(path, json).map_err(|e| Error::Write(path.into(), e)),
+        None => std::io::stdout()
+            .write_all(json.as_bytes())
+            .map_err(Error::Output),
+    }
+}
@@ -0,0 +1,34 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+mod errors;
+mod files;
+mod subcommand;
+
+use clap::Parser;
+use subcommand::Subcommand;
+
+/// Sign and verify criticaltrust manifests
+#[derive(Parser, Debug)]
+#[command(name = "criticaltrust", version, about, long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Subcommand,
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+
+    if let Err(err) = cli.command.execute().await {
+        eprintln!("error: {err}");
+
+        let mut err = &err as &dyn std::error::Error;
+        while let Some(source) = err.source() {
+            err = source;
+            eprintln!("  caused by: {source}");
+        }
+
+        std::process::exit(1);
+    }
+}
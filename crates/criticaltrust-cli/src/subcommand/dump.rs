@@ -0,0 +1,44 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::errors::Error;
+use crate::files::{read_json, write_json};
+use clap::Parser;
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// Print the decoded contents of the signed payloads in a file, WITHOUT verifying them
+///
+/// Every signed payload found in the file (including the ones nested in keys manifests) has its
+/// `signed` field decoded from the JSON string it's stored as.
+#[derive(Debug, Parser)]
+pub(crate) struct Dump {
+    /// Path to the JSON file to dump
+    input: PathBuf,
+}
+
+impl Dump {
+    pub(crate) fn execute(self) -> Result<(), Error> {
+        let input: Value = read_json(&self.input, "JSON")?;
+        eprintln!("warning: the signatures were not verified, do not trust the contents");
+        write_json(None, &decode(input))
+    }
+}
+
+fn decode(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| match (key.as_str(), value) {
+                    ("signed", Value::String(signed)) => match serde_json::from_str(&signed) {
+                        Ok(parsed) => (key, decode(parsed)),
+                        Err(_) => (key, Value::String(signed)),
+                    },
+                    (_, value) => (key, decode(value)),
+                })
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(decode).collect()),
+        other => other,
+    }
+}
@@ -0,0 +1,80 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::errors::Error;
+use crate::files::{write_json, PassphraseArgs};
+use clap::{Parser, ValueEnum};
+use criticaltrust::keys::{FileKeyPair, KeyAlgorithm, KeyPair, KeyRole};
+use std::path::PathBuf;
+
+/// Generate a new key pair, storing the private key in an encrypted file
+#[derive(Debug, Parser)]
+pub(crate) struct Keygen {
+    /// Path of the encrypted private key to create
+    output: PathBuf,
+    /// Algorithm of the key pair
+    #[arg(long, value_enum)]
+    algorithm: Algorithm,
+    /// Role of the key pair
+    #[arg(long, value_enum)]
+    role: Role,
+    /// Path to write the JSON public key to, instead of stdout
+    #[arg(long)]
+    public_key: Option<PathBuf>,
+    #[command(flatten)]
+    passphrase: PassphraseArgs,
+}
+
+impl Keygen {
+    pub(crate) fn execute(self) -> Result<(), Error> {
+        let key = FileKeyPair::generate(
+            &self.output,
+            self.algorithm.into(),
+            self.role.into(),
+            self.passphrase.passphrase(),
+        )?;
+        eprintln!("Generated key {}", key.public().calculate_id());
+        write_json(self.public_key.as_deref(), key.public())
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Algorithm {
+    EcdsaP256,
+    Ed25519,
+    #[value(name = "ml-dsa-65")]
+    MlDsa65,
+}
+
+impl From<Algorithm> for KeyAlgorithm {
+    fn from(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::EcdsaP256 => KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer,
+            Algorithm::Ed25519 => KeyAlgorithm::Ed25519,
+            Algorithm::MlDsa65 => KeyAlgorithm::MlDsa65,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Role {
+    Root,
+    Releases,
+    Packages,
+    Redirects,
+    Revocation,
+    Timestamps,
+}
+
+impl From<Role> for KeyRole {
+    fn from(role: Role) -> Self {
+        match role {
+            Role::Root => KeyRole::Root,
+            Role::Releases => KeyRole::Releases,
+            Role::Packages => KeyRole::Packages,
+            Role::Redirects => KeyRole::Redirects,
+            Role::Revocation => KeyRole::Revocation,
+            Role::Timestamps => KeyRole::Timestamps,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+mod dump;
+mod keygen;
+mod sign;
+mod verify;
+mod verify_package;
+
+use crate::errors::Error;
+
+#[derive(clap::Subcommand, Debug)]
+pub(crate) enum Subcommand {
+    Keygen(keygen::Keygen),
+    Sign(sign::Sign),
+    Verify(verify::Verify),
+    Dump(dump::Dump),
+    VerifyPackage(verify_package::VerifyPackage),
+}
+
+impl Subcommand {
+    pub(crate) async fn execute(self) -> Result<(), Error> {
+        match self {
+            Subcommand::Keygen(keygen) => keygen.execute(),
+            Subcommand::Sign(sign) => sign.execute().await,
+            Subcommand::Verify(verify) => verify.execute(),
+            Subcommand::Dump(dump) => dump.execute(),
+            Subcommand::VerifyPackage(verify_package) => verify_package.execute(),
+        }
+    }
+}
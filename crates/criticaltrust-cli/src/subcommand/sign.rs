@@ -0,0 +1,215 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::errors::Error;
+use crate::files::{read_json, write_json, PassphraseArgs};
+use clap::{Parser, ValueEnum};
+use criticaltrust::keys::{FileKeyPair, KeyRole, PublicKey};
+use criticaltrust::manifests::{
+    KeysManifest, ManifestVersion, Package, PackageManifest, Redirect, RedirectManifest, Release,
+    ReleaseManifest, RootRotation,
+};
+use criticaltrust::revocation_info::RevocationInfo;
+use criticaltrust::signatures::{Signable, SignedPayload};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Sign a payload, or add signatures to an already signed one
+///
+/// The input can either contain the plain payload to sign (for example a public key or a release
+/// without signatures), or an already signed payload or manifest, in which case the signatures
+/// are added to the existing ones.
+#[derive(Debug, Parser)]
+pub(crate) struct Sign {
+    /// Kind of payload to sign
+    #[arg(value_enum)]
+    kind: Kind,
+    /// Path to the JSON payload to sign
+    input: PathBuf,
+    /// Path to the encrypted private key to sign with (can be repeated)
+    #[arg(long = "key", required = true)]
+    keys: Vec<PathBuf>,
+    /// Path to the encrypted revocation private key, used to sign the revocation information of
+    /// keys manifests
+    #[arg(long)]
+    revocation_key: Option<PathBuf>,
+    /// Path to the encrypted timestamps private key, used to countersign the signatures with the
+    /// current time, so they can still be verified after the signing keys expire
+    #[arg(long)]
+    timestamp_key: Option<PathBuf>,
+    /// Path to write the signed JSON to, instead of stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+    #[command(flatten)]
+    passphrase: PassphraseArgs,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Kind {
+    /// Public key, signed by root keys
+    Key,
+    /// Revocation information, signed by revocation keys
+    Revocations,
+    /// Rotation of the trust root, signed by the root being replaced
+    RootRotation,
+    /// Keys manifest, whose keys are signed by root keys
+    Keys,
+    /// Release manifest, signed by releases keys
+    Release,
+    /// Package manifest, signed by packages keys
+    Package,
+    /// Redirect manifest, signed by redirects keys
+    Redirect,
+}
+
+impl Sign {
+    pub(crate) async fn execute(self) -> Result<(), Error> {
+        let input: Value = read_json(&self.input, "JSON")?;
+        let output = self.output.as_deref();
+
+        match self.kind {
+            Kind::Key => write_json(
+                output,
+                &self.sign::<PublicKey>(input, "a public key").await?,
+            ),
+            Kind::Revocations => write_json(
+                output,
+                &self
+                    .sign::<RevocationInfo>(input, "revocation information")
+                    .await?,
+            ),
+            Kind::RootRotation => write_json(
+                output,
+                &self.sign::<RootRotation>(input, "a root rotation").await?,
+            ),
+            Kind::Keys => write_json(output, &self.sign_keys_manifest(input).await?),
+            Kind::Release => write_json(
+                output,
+                &ReleaseManifest {
+                    version: ManifestVersion,
+                    signed: self.sign::<Release>(input, "a release").await?,
+                },
+            ),
+            Kind::Package => write_json(
+                output,
+                &PackageManifest {
+                    version: ManifestVersion,
+                    signed: self.sign::<Package>(input, "a package").await?,
+                },
+            ),
+            Kind::Redirect => write_json(
+                output,
+                &RedirectManifest {
+                    version: ManifestVersion,
+                    payload: self.sign::<Redirect>(input, "a redirect").await?,
+                },
+            ),
+        }
+    }
+
+    async fn sign<T: Signable>(
+        &self,
+        input: Value,
+        what: &'static str,
+    ) -> Result<SignedPayload<T>, Error> {
+        let keys = self.load_keys(&self.keys, T::SIGNED_BY_ROLE)?;
+        let timestamp_key = self.load_timestamp_key()?;
+        sign_with(&self.input, input, what, &keys, timestamp_key.as_ref()).await
+    }
+
+    /// Keys manifests are not signed themselves: each of their keys is signed by the root keys,
+    /// and the revocation information is signed by the revocation key.
+    async fn sign_keys_manifest(&self, input: Value) -> Result<KeysManifest, Error> {
+        let parse_error = |e| Error::Parse(self.input.clone(), "a keys manifest", e);
+        let Value::Object(mut input) = input else {
+            return Err(parse_error(serde::de::Error::custom("expected an object")));
+        };
+
+        let root_keys = self.load_keys(&self.keys, KeyRole::Root)?;
+        let timestamp_key = self.load_timestamp_key()?;
+        let mut keys = Vec::new();
+        let entries = input.remove("keys").unwrap_or_default();
+        for entry in serde_json::from_value::<Vec<Value>>(entries).map_err(parse_error)? {
+            keys.push(
+                sign_with(
+                    &self.input,
+                    entry,
+                    "a public key",
+                    &root_keys,
+                    timestamp_key.as_ref(),
+                )
+                .await?,
+            );
+        }
+
+        let revocations = input.remove("revoked_signatures").unwrap_or_default();
+        let revocation_keys = match &self.revocation_key {
+            Some(path) => self.load_keys(std::slice::from_ref(path), KeyRole::Revocation)?,
+            None if is_signed(&revocations) => Vec::new(),
+            None => return Err(Error::MissingRevocationKey),
+        };
+        let revoked_signatures = sign_with(
+            &self.input,
+            revocations,
+            "revocation information",
+            &revocation_keys,
+            timestamp_key.as_ref(),
+        )
+        .await?;
+
+        let root_rotations = input
+            .remove("root_rotations")
+            .unwrap_or(Value::Array(Vec::new()));
+        Ok(KeysManifest {
+            version: ManifestVersion,
+            keys,
+            revoked_signatures,
+            root_rotations: serde_json::from_value(root_rotations).map_err(parse_error)?,
+        })
+    }
+
+    fn load_keys(&self, paths: &[PathBuf], role: KeyRole) -> Result<Vec<FileKeyPair>, Error> {
+        paths
+            .iter()
+            .map(|path| self.passphrase.load_key(path, role))
+            .collect()
+    }
+
+    fn load_timestamp_key(&self) -> Result<Option<FileKeyPair>, Error> {
+        self.timestamp_key
+            .as_deref()
+            .map(|path| self.passphrase.load_key(path, KeyRole::Timestamps))
+            .transpose()
+    }
+}
+
+/// Sign `input` with all the `keys`, either starting a new signed payload if `input` contains the
+/// plain payload, or adding signatures to it if it's already signed. Signatures without a
+/// timestamp are then countersigned with the `timestamp_key`, if any.
+async fn sign_with<T: Signable>(
+    path: &Path,
+    input: Value,
+    what: &'static str,
+    keys: &[FileKeyPair],
+    timestamp_key: Option<&FileKeyPair>,
+) -> Result<SignedPayload<T>, Error> {
+    let mut payload = if is_signed(&input) {
+        serde_json::from_value(input)
+            .map_err(|e| Error::Parse(path.into(), "a signed payload", e))?
+    } else {
+        SignedPayload::new(
+            &serde_json::from_value::<T>(input).map_err(|e| Error::Parse(path.into(), what, e))?,
+        )?
+    };
+    for key in keys {
+        payload.add_signature(key).await?;
+    }
+    if let Some(timestamp_key) = timestamp_key {
+        payload.add_timestamp(timestamp_key).await?;
+    }
+    Ok(payload)
+}
+
+fn is_signed(value: &Value) -> bool {
+    value.get("signed").is_some_and(Value::is_string)
+}
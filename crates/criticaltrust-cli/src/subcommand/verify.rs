@@ -0,0 +1,85 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::errors::Error;
+use crate::files::{read_json, write_json, TrustArgs};
+use clap::{Parser, ValueEnum};
+use criticaltrust::manifests::{KeysManifest, PackageManifest, RedirectManifest, ReleaseManifest};
+use std::path::PathBuf;
+
+/// Verify the signatures of a manifest, printing its verified contents
+#[derive(Debug, Parser)]
+pub(crate) struct Verify {
+    /// Kind of manifest to verify
+    #[arg(value_enum)]
+    kind: Kind,
+    /// Path to the JSON manifest to verify
+    input: PathBuf,
+    #[command(flatten)]
+    trust: TrustArgs,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Kind {
+    /// Keys manifest, printing the keys trusted by the root
+    Keys,
+    /// Release manifest
+    Release,
+    /// Package manifest
+    Package,
+    /// Redirect manifest
+    Redirect,
+}
+
+impl Verify {
+    pub(crate) fn execute(self) -> Result<(), Error> {
+        match self.kind {
+            Kind::Keys => {
+                let manifest: KeysManifest = read_json(&self.input, "a keys manifest")?;
+                // The keys are verified against the trust root: loading another keys manifest
+                // first would make the revocation information of the two conflict.
+                let mut keychain = self.trust.keychain_without_keys("keys manifests")?;
+                keychain.load_all(&manifest)?;
+
+                let mut keys = keychain.keys().iter().collect::<Vec<_>>();
+                keys.sort_by_key(|(id, _)| id.to_string());
+                eprintln!(
+                    "Verified keys manifest ({} keys trusted, {} keys in the manifest)",
+                    keys.len(),
+                    manifest.keys.len()
+                );
+                write_json(
+                    None,
+                    &keys.into_iter().map(|(_, key)| key).collect::<Vec<_>>(),
+                )
+            }
+            Kind::Release => {
+                let keychain = self.trust.keychain_with_keys("release manifests")?;
+                let manifest: ReleaseManifest = read_json(&self.input, "a release manifest")?;
+                let release = manifest
+                    .signed
+                    .into_verified_with_policy(&keychain, &self.trust.policy())?;
+                eprintln!("Verified release manifest");
+                write_json(None, &release)
+            }
+            Kind::Package => {
+                let keychain = self.trust.keychain_with_keys("package manifests")?;
+                let manifest: PackageManifest = read_json(&self.input, "a package manifest")?;
+                let package = manifest
+                    .signed
+                    .into_verified_with_policy(&keychain, &self.trust.policy())?;
+                eprintln!("Verified package manifest");
+                write_json(None, &package)
+            }
+            Kind::Redirect => {
+                let keychain = self.trust.keychain_with_keys("redirect manifests")?;
+                let manifest: RedirectManifest = read_json(&self.input, "a redirect manifest")?;
+                let redirect = manifest
+                    .payload
+                    .into_verified_with_policy(&keychain, &self.trust.policy())?;
+                eprintln!("Verified redirect manifest");
+                write_json(None, &redirect)
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::errors::Error;
+use crate::files::TrustArgs;
+use clap::Parser;
+use criticaltrust::integrity::IntegrityVerifier;
+#[cfg(not(windows))]
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+use walkdir::WalkDir;
+
+/// Verify the integrity of an extracted package tree against its package manifests
+#[derive(Debug, Parser)]
+pub(crate) struct VerifyPackage {
+    /// Path to the directory containing the extracted packages
+    path: PathBuf,
+    /// Allow files not referenced by any package manifest, outside of the managed prefixes
+    #[arg(long)]
+    allow_external_files: bool,
+    #[command(flatten)]
+    trust: TrustArgs,
+}
+
+impl VerifyPackage {
+    pub(crate) fn execute(self) -> Result<(), Error> {
+        let keychain = self.trust.keychain_with_keys("packages")?;
+        let mut verifier = IntegrityVerifier::new(&keychain);
+        verifier.allow_external_files(self.allow_external_files);
+        verifier.verification_policy(self.trust.policy());
+
+        for entry in WalkDir::new(&self.path) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            // Package manifests only record the permission bits, not the file type.
+            #[cfg(not(windows))]
+            let mode = entry.metadata()?.mode() & 0o7777;
+            // Windows does not have the same concept of permissions, we just no-op mode.
+            #[cfg(windows)]
+            let mode = 0;
+
+            let contents =
+                std::fs::read(entry.path()).map_err(|e| Error::Read(entry.path().into(), e))?;
+            let relative = entry
+                .path()
+                .strip_prefix(&self.path)
+                .expect("walked paths are inside the walked directory");
+            verifier.add(relative, mode, &contents);
+        }
+
+        for package in verifier.verify().map_err(Error::Integrity)? {
+            println!("Verified {}/{}", package.product, package.package);
+        }
+        Ok(())
+    }
+}
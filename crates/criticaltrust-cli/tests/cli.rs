@@ -0,0 +1,419 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use criticaltrust::manifests::{Package, PackageFile, Release};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+const PASSPHRASE: &str = "correct horse battery staple";
+
+struct Repository {
+    dir: TempDir,
+}
+
+impl Repository {
+    /// Create a repository with root, releases, packages, revocation and timestamps keys, and a
+    /// keys manifest trusting all of them.
+    fn new() -> Self {
+        let repo = Repository {
+            dir: TempDir::new().unwrap(),
+        };
+        for (name, algorithm) in [
+            ("root", "ed25519"),
+            ("releases", "ecdsa-p256"),
+            ("packages", "ed25519"),
+            ("revocation", "ml-dsa-65"),
+            ("timestamps", "ed25519"),
+        ] {
+            repo.run_ok(&[
+                "keygen",
+                &format!("{name}.pem"),
+                "--algorithm",
+                algorithm,
+                "--role",
+                name,
+                "--public-key",
+                &format!("{name}.json"),
+            ]);
+        }
+
+        let unsigned = serde_json::json!({
+            "version": 1,
+            "keys": [
+                repo.read_json("releases.json"),
+                repo.read_json("packages.json"),
+                repo.read_json("revocation.json"),
+                repo.read_json("timestamps.json"),
+            ],
+            "revoked_signatures": {
+                "revoked_content_sha256": [],
+                "expires_at": "2999-01-01T00:00:00Z",
+            },
+        });
+        repo.write("keys.unsigned.json", &unsigned.to_string());
+        repo.run_ok(&[
+            "sign",
+            "keys",
+            "keys.unsigned.json",
+            "--key",
+            "root.pem",
+            "--revocation-key",
+            "revocation.pem",
+            "--output",
+            "keys.json",
+        ]);
+
+        repo
+    }
+
+    fn command(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_criticaltrust"))
+            .args(args)
+            .current_dir(self.dir.path())
+            .env("CRITICALTRUST_PASSPHRASE", PASSPHRASE)
+            .output()
+            .unwrap()
+    }
+
+    fn run_ok(&self, args: &[&str]) -> String {
+        let output = self.command(args);
+        assert!(
+            output.status.success(),
+            "command {args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    fn run_err(&self, args: &[&str]) -> String {
+        let output = self.command(args);
+        assert!(!output.status.success(), "command {args:?} succeeded");
+        String::from_utf8(output.stderr).unwrap()
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.path().join(name)
+    }
+
+    fn write(&self, name: &str, contents: &str) {
+        let path = self.path(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    fn read_json(&self, name: &str) -> serde_json::Value {
+        serde_json::from_slice(&std::fs::read(self.path(name)).unwrap()).unwrap()
+    }
+}
+
+fn release() -> Release {
+    Release {
+        product: "ferrocene".into(),
+        release: "25.02.0".into(),
+        commit: "123abc".into(),
+        packages: Vec::new(),
+    }
+}
+
+#[test]
+fn test_verify_keys_manifest() {
+    let repo = Repository::new();
+    let trusted = repo.run_ok(&["verify", "keys", "keys.json", "--trust-root", "root.json"]);
+    let trusted: serde_json::Value = serde_json::from_str(&trusted).unwrap();
+
+    // The four keys in the manifest, plus the trust root itself.
+    assert_eq!(5, trusted.as_array().unwrap().len());
+}
+
+#[test]
+fn test_sign_and_verify_release() {
+    let repo = Repository::new();
+    repo.write("release.json", &serde_json::to_string(&release()).unwrap());
+    repo.run_ok(&[
+        "sign",
+        "release",
+        "release.json",
+        "--key",
+        "releases.pem",
+        "--output",
+        "manifest.json",
+    ]);
+    assert_eq!(1, repo.read_json("manifest.json")["version"]);
+
+    let verified = repo.run_ok(&[
+        "verify",
+        "release",
+        "manifest.json",
+        "--trust-root",
+        "root.json",
+        "--keys",
+        "keys.json",
+    ]);
+    let verified: Release = serde_json::from_str(&verified).unwrap();
+    assert_eq!("25.02.0", verified.release);
+
+    // Without the keys manifest the releases key is not trusted.
+    let err = repo.run_err(&[
+        "verify",
+        "release",
+        "manifest.json",
+        "--trust-root",
+        "root.json",
+    ]);
+    assert!(err.contains("--keys"), "{err}");
+}
+
+#[test]
+fn test_sign_release_with_timestamp() {
+    let repo = Repository::new();
+    repo.write("release.json", &serde_json::to_string(&release()).unwrap());
+    repo.run_ok(&[
+        "sign",
+        "release",
+        "release.json",
+        "--key",
+        "releases.pem",
+        "--timestamp-key",
+        "timestamps.pem",
+        "--output",
+        "manifest.json",
+    ]);
+    let manifest = repo.read_json("manifest.json");
+    assert!(manifest["signatures"][0]["timestamp"].is_object());
+
+    repo.run_ok(&[
+        "verify",
+        "release",
+        "manifest.json",
+        "--trust-root",
+        "root.json",
+        "--keys",
+        "keys.json",
+    ]);
+}
+
+#[test]
+fn test_revocation_at_timestamp_requires_an_earlier_timestamp() {
+    let repo = Repository::new();
+    let mut unsigned = repo.read_json("keys.unsigned.json");
+    unsigned["revoked_signatures"]["expires_at"] = "2000-01-01T00:00:00Z".into();
+    repo.write("keys.expired.unsigned.json", &unsigned.to_string());
+    repo.run_ok(&[
+        "sign",
+        "keys",
+        "keys.expired.unsigned.json",
+        "--key",
+        "root.pem",
+        "--revocation-key",
+        "revocation.pem",
+        "--output",
+        "keys.expired.json",
+    ]);
+
+    repo.write("release.json", &serde_json::to_string(&release()).unwrap());
+    repo.run_ok(&[
+        "sign",
+        "release",
+        "release.json",
+        "--key",
+        "releases.pem",
+        "--timestamp-key",
+        "timestamps.pem",
+        "--output",
+        "manifest.json",
+    ]);
+
+    // The signature was timestamped after the revocation information expired, so the
+    // revocation information is expired as of the timestamp too.
+    for extra in [&[][..], &["--revocation-at-timestamp"][..]] {
+        let mut args = vec![
+            "verify",
+            "release",
+            "manifest.json",
+            "--trust-root",
+            "root.json",
+            "--keys",
+            "keys.expired.json",
+        ];
+        args.extend_from_slice(extra);
+        let err = repo.run_err(&args);
+        assert!(err.contains("signatures expired on '2000-01-01"), "{err}");
+    }
+}
+
+#[test]
+fn test_tampered_release_is_rejected() {
+    let repo = Repository::new();
+    repo.write("release.json", &serde_json::to_string(&release()).unwrap());
+    let signed = repo.run_ok(&["sign", "release", "release.json", "--key", "releases.pem"]);
+    repo.write("manifest.json", &signed.replace("25.02.0", "25.02.1"));
+
+    let err = repo.run_err(&[
+        "verify",
+        "release",
+        "manifest.json",
+        "--trust-root",
+        "root.json",
+        "--keys",
+        "keys.json",
+    ]);
+    assert!(err.contains("Failed to verify signed data."), "{err}");
+}
+
+#[test]
+fn test_sign_with_wrong_role_is_rejected() {
+    let repo = Repository::new();
+    repo.write("release.json", &serde_json::to_string(&release()).unwrap());
+    let err = repo.run_err(&[
+        "sign",
+        "release",
+        "release.json",
+        "--key",
+        "packages.pem",
+        "--output",
+        "manifest.json",
+    ]);
+    assert!(
+        err.contains("contains a Packages key, but a Releases key is required"),
+        "{err}"
+    );
+    assert!(!repo.path("manifest.json").exists());
+}
+
+#[test]
+fn test_verify_keys_manifest_with_keys_is_rejected() {
+    let repo = Repository::new();
+    let err = repo.run_err(&[
+        "verify",
+        "keys",
+        "keys.json",
+        "--trust-root",
+        "root.json",
+        "--keys",
+        "keys.json",
+    ]);
+    assert!(err.contains("--keys"), "{err}");
+}
+
+#[test]
+fn test_add_signature_to_signed_payload() {
+    let repo = Repository::new();
+    repo.run_ok(&[
+        "keygen",
+        "root2.pem",
+        "--algorithm",
+        "ed25519",
+        "--role",
+        "root",
+    ]);
+    repo.run_ok(&[
+        "sign",
+        "key",
+        "releases.json",
+        "--key",
+        "root.pem",
+        "--output",
+        "releases.signed.json",
+    ]);
+    repo.run_ok(&[
+        "sign",
+        "key",
+        "releases.signed.json",
+        "--key",
+        "root2.pem",
+        "--output",
+        "releases.cosigned.json",
+    ]);
+
+    let cosigned = repo.read_json("releases.cosigned.json");
+    assert_eq!(2, cosigned["signatures"].as_array().unwrap().len());
+    assert_eq!(
+        repo.read_json("releases.signed.json")["signed"],
+        cosigned["signed"]
+    );
+}
+
+#[test]
+fn test_wrong_passphrase_is_rejected() {
+    let repo = Repository::new();
+    repo.write("release.json", &serde_json::to_string(&release()).unwrap());
+    let err = repo.run_err(&[
+        "sign",
+        "release",
+        "release.json",
+        "--key",
+        "releases.pem",
+        "--passphrase-env",
+        "CRITICALTRUST_TEST_MISSING_PASSPHRASE",
+    ]);
+    assert!(
+        err.contains("CRITICALTRUST_TEST_MISSING_PASSPHRASE"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_dump_decodes_nested_payloads() {
+    let repo = Repository::new();
+    let dumped: serde_json::Value =
+        serde_json::from_str(&repo.run_ok(&["dump", "keys.json"])).unwrap();
+
+    assert_eq!(repo.read_json("releases.json"), dumped["keys"][0]["signed"]);
+    assert_eq!(
+        "2999-01-01T00:00:00Z",
+        dumped["revoked_signatures"]["signed"]["expires_at"]
+    );
+}
+
+#[test]
+fn test_verify_package_tree() {
+    let repo = Repository::new();
+    let contents = b"#!/bin/sh\necho hello\n";
+    repo.write("tree/bin/hello", std::str::from_utf8(contents).unwrap());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let permissions = std::fs::Permissions::from_mode(0o755);
+        std::fs::set_permissions(repo.path("tree/bin/hello"), permissions).unwrap();
+    }
+
+    let package = Package {
+        product: "ferrocene".into(),
+        package: "hello".into(),
+        commit: "123abc".into(),
+        files: vec![PackageFile {
+            path: "bin/hello".into(),
+            posix_mode: 0o755,
+            sha256: Sha256::digest(contents).to_vec(),
+            needs_proxy: false,
+        }],
+        managed_prefixes: Vec::new(),
+    };
+    repo.write("package.json", &serde_json::to_string(&package).unwrap());
+    std::fs::create_dir_all(repo.path("tree/share/criticaltrust/ferrocene")).unwrap();
+    repo.run_ok(&[
+        "sign",
+        "package",
+        "package.json",
+        "--key",
+        "packages.pem",
+        "--output",
+        "tree/share/criticaltrust/ferrocene/hello.json",
+    ]);
+
+    let args = [
+        "verify-package",
+        "tree",
+        "--trust-root",
+        "root.json",
+        "--keys",
+        "keys.json",
+    ];
+    assert_eq!("Verified ferrocene/hello\n", repo.run_ok(&args));
+
+    repo.write("tree/bin/hello", "tampered");
+    let err = repo.run_err(&args);
+    assert!(err.contains("did not pass the integrity checks"), "{err}");
+}
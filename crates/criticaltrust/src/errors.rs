@@ -1,7 +1,8 @@
 // SPDX-FileCopyrightText: The Ferrocene Developers
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::keys::KeyRole;
+use crate::keys::{KeyId, KeyRole};
+use std::path::PathBuf;
 use std::string::FromUtf8Error;
 use thiserror::Error;
 use time::macros::format_description;
@@ -16,6 +17,35 @@ pub enum Error {
     SignatureFailed,
     #[error("Failed to verify signed data.")]
     VerificationFailed,
+    #[error(
+        "Failed to verify signed data: {required} signatures by {role:?} keys are required, \
+        but only {} were found (signed by: {}; missing: {}).",
+        signed_by.len(),
+        key_list(signed_by),
+        key_list(missing)
+    )]
+    NotEnoughSignatures {
+        role: KeyRole,
+        required: usize,
+        signed_by: Vec<KeyId>,
+        missing: Vec<KeyId>,
+    },
+    #[error(
+        "Failed to verify signed data: {role:?} keys require both a classical and a post-quantum \
+        signature, but no {} signature was found.",
+        if *missing_post_quantum { "post-quantum" } else { "classical" }
+    )]
+    MissingHybridSignature {
+        role: KeyRole,
+        missing_post_quantum: bool,
+    },
+    #[error(
+        "Failed to verify signed data: {role:?} signatures require a trusted timestamp, but none \
+        was found."
+    )]
+    MissingTimestamp { role: KeyRole },
+    #[error("Hybrid signatures require a classical and a post-quantum key.")]
+    InvalidHybridKeys,
     #[error("Failed to generate a local key.")]
     LocalKeyGenerationFailed,
     #[error("Wrong key role for the trust root key (expected Root, found {0:?}).")]
@@ -28,9 +58,28 @@ pub enum Error {
     InvalidKey(String),
     #[error("Unsupported key.")]
     UnsupportedKey,
+    #[error("Failed to read the key file {}.", .0.display())]
+    KeyFileReadFailed(PathBuf, #[source] std::io::Error),
+    #[error("Failed to write the key file {}.", .0.display())]
+    KeyFileWriteFailed(PathBuf, #[source] std::io::Error),
+    #[error(
+        "The key file {} contains a {found:?} key, but a {expected:?} key is required.",
+        path.display()
+    )]
+    KeyFileWrongRole {
+        path: PathBuf,
+        expected: KeyRole,
+        found: KeyRole,
+    },
+    #[error("Failed to decrypt the private key (is the passphrase correct?).")]
+    KeyDecryptionFailed,
+    #[error("The environment variable {0} containing the key passphrase is not set.")]
+    KeyPassphraseNotSet(String),
+    #[error("The key passphrase is empty.")]
+    KeyPassphraseEmpty,
     #[error("Verification failed because the signatures expired on '{}'.", format!("{}", .0.format(format_description!("[year]-[month]-[day] +[offset_hour]::[offset_minute]")).expect("formatting OffsetDatetime failed")))]
     RevocationSignatureExpired(OffsetDateTime),
-    #[error("Failed to verify signed package '{}' because content is revoked.", .0)]
+    #[error("Failed to verify signed content with SHA-256 '{}' because it was revoked.", .0)]
     ContentRevoked(String),
     #[error("Calling the method to load all keys and revocation info failed because revocation info already exists.")]
     RevocationInfoOverwriting,
@@ -52,4 +101,26 @@ pub enum Error {
             aws_smithy_runtime_api::client::orchestrator::HttpResponse,
         >,
     ),
+    #[cfg(feature = "pkcs11")]
+    #[error("Failed to interact with the PKCS#11 token.")]
+    Pkcs11Failed(#[from] cryptoki::error::Error),
+    #[cfg(feature = "pkcs11")]
+    #[error("No PKCS#11 token labelled '{0}' was found.")]
+    Pkcs11TokenNotFound(String),
+    #[cfg(feature = "pkcs11")]
+    #[error("No PKCS#11 key pair labelled '{0}' was found in the token.")]
+    Pkcs11KeyNotFound(String),
+    #[cfg(feature = "pkcs11")]
+    #[error("Multiple PKCS#11 keys labelled '{0}' were found in the token.")]
+    Pkcs11KeyAmbiguous(String),
+}
+
+fn key_list(keys: &[KeyId]) -> String {
+    if keys.is_empty() {
+        return "none".into();
+    }
+    keys.iter()
+        .map(|key| key.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
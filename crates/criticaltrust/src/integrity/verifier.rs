@@ -5,7 +5,7 @@ use crate::integrity::detect_manifest::{is_package_manifest, FoundPackageManifes
 use crate::integrity::IntegrityError;
 use crate::manifests::{PackageFile, PackageManifest};
 use crate::sha256::hash_sha256;
-use crate::signatures::Keychain;
+use crate::signatures::{Keychain, VerificationPolicy};
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
@@ -23,6 +23,7 @@ pub struct IntegrityVerifier<'a> {
     errors: Vec<IntegrityError>,
     verified_packages: Vec<VerifiedPackage>,
     allow_external_files: bool,
+    policy: VerificationPolicy,
 
     managed_prefixes: HashSet<PathBuf>,
     loaded_files: HashSet<PathBuf>,
@@ -39,6 +40,7 @@ impl<'a> IntegrityVerifier<'a> {
             errors: Vec::new(),
             verified_packages: Vec::new(),
             allow_external_files: false,
+            policy: VerificationPolicy::default(),
 
             managed_prefixes: HashSet::new(),
             loaded_files: HashSet::new(),
@@ -61,6 +63,17 @@ impl<'a> IntegrityVerifier<'a> {
         self.allow_external_files = allow;
     }
 
+    /// Set the [`VerificationPolicy`] package manifests must satisfy. By default a single trusted
+    /// signature is required.
+    ///
+    /// Signatures with a trusted [`Timestamp`](crate::signatures::Timestamp) are checked against
+    /// the expiry of their key as of the time in the timestamp, so installations remain verifiable
+    /// after the keys signing them expire. Package manifests are verified as soon as they are
+    /// [added](IntegrityVerifier::add), so the policy must be set before adding any file.
+    pub fn verification_policy(&mut self, policy: VerificationPolicy) {
+        self.policy = policy;
+    }
+
     /// Include the provided path and contents in the files pending verification.
     ///
     /// The order in which files are added does not matter, but the same file can't be added twice.
@@ -142,7 +155,7 @@ impl<'a> IntegrityVerifier<'a> {
                 inner: e,
             })?
             .signed
-            .into_verified(self.keychain)
+            .into_verified_with_policy(self.keychain, &self.policy)
             .map_err(|e| IntegrityError::PackageManifestVerification {
                 path: path.into(),
                 inner: e,
@@ -737,6 +750,25 @@ mod tests {
             .assert_verified(&[("a", "b")]);
     }
 
+    #[tokio::test]
+    async fn test_policy_requiring_timestamps() {
+        IntegrityTest::new()
+            .await
+            .manifest(ManifestBuilder::new("a", "b").file(&BIN_A))
+            .await
+            .file(&BIN_A)
+            .policy(VerificationPolicy::new().require_timestamp(KeyRole::Packages))
+            .assert_errors(errors![
+                IntegrityError::PackageManifestVerification {
+                    path,
+                    inner: Error::MissingTimestamp { role: KeyRole::Packages },
+                } if path == Path::new("share/criticaltrust/a/b.json"),
+                // The manifest is completely ignored, resulting in more errors.
+                IntegrityError::NoPackageManifestFound,
+                IntegrityError::UnexpectedFile { path } if path == Path::new("bin/a"),
+            ]);
+    }
+
     #[derive(Clone)]
     struct TestFile {
         path: PathBuf,
@@ -837,6 +869,7 @@ mod tests {
         env: TestEnvironment,
         key: EphemeralKeyPair,
         allow_external_files: bool,
+        policy: VerificationPolicy,
         files: Vec<TestFile>,
     }
 
@@ -848,6 +881,7 @@ mod tests {
                 env,
                 key,
                 allow_external_files: false,
+                policy: VerificationPolicy::default(),
                 files: Vec::new(),
             }
         }
@@ -857,6 +891,11 @@ mod tests {
             self
         }
 
+        fn policy(mut self, policy: VerificationPolicy) -> Self {
+            self.policy = policy;
+            self
+        }
+
         fn file(mut self, file: &TestFile) -> Self {
             self.files.push(file.clone());
             self
@@ -951,6 +990,7 @@ mod tests {
 
                     let mut verifier = IntegrityVerifier::new(self.env.keychain());
                     verifier.allow_external_files(self.allow_external_files);
+                    verifier.verification_policy(self.policy.clone());
                     for file in files {
                         verifier.add(&file.path, file.mode, &file.contents);
                     }
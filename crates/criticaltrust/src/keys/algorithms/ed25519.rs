@@ -0,0 +1,171 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::keys::algorithms::Algorithm;
+use crate::keys::newtypes::{PayloadBytes, PrivateKeyBytes, PublicKeyBytes, SignatureBytes};
+use crate::Error;
+use ed25519_dalek::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use rand::rand_core::UnwrapErr;
+
+pub(super) struct Ed25519;
+
+impl Algorithm for Ed25519 {
+    fn sign(
+        &self,
+        private_key: &PrivateKeyBytes<'_>,
+        payload: &PayloadBytes<'_>,
+    ) -> Result<SignatureBytes<'static>, Error> {
+        let key = SigningKey::from_pkcs8_der(private_key.as_bytes())
+            .map_err(|e| Error::InvalidKey(e.to_string()))?;
+
+        let signature: Signature = key.sign(payload.as_bytes());
+        Ok(SignatureBytes::owned(signature.to_bytes().to_vec()))
+    }
+
+    fn verify(
+        &self,
+        public_key: &PublicKeyBytes<'_>,
+        payload: &PayloadBytes<'_>,
+        signature: &SignatureBytes<'_>,
+    ) -> Result<(), Error> {
+        let key = VerifyingKey::from_public_key_der(public_key.as_bytes())
+            .map_err(|e| Error::InvalidKey(e.to_string()))?;
+
+        let signature =
+            Signature::from_slice(signature.as_bytes()).map_err(|_| Error::VerificationFailed)?;
+        // Strict verification rejects weak (small order) public keys and non-canonical
+        // signatures, which would otherwise allow a signature to be valid for any payload.
+        key.verify_strict(payload.as_bytes(), &signature)
+            .map_err(|_| Error::VerificationFailed)
+    }
+
+    fn generate_private_key(&self) -> Result<PrivateKeyBytes<'static>, Error> {
+        let key = SigningKey::generate(&mut UnwrapErr(rand::rngs::SysRng));
+        Ok(PrivateKeyBytes::owned(
+            key.to_pkcs8_der()
+                .expect("generated private key cannot be encoded")
+                .to_bytes()
+                .to_vec(),
+        ))
+    }
+
+    fn derive_public_key_from_private_key(
+        &self,
+        private_key: &PrivateKeyBytes<'_>,
+    ) -> Result<PublicKeyBytes<'static>, Error> {
+        let key = SigningKey::from_pkcs8_der(private_key.as_bytes())
+            .map_err(|e| Error::InvalidKey(e.to_string()))?;
+        Ok(PublicKeyBytes::owned(
+            key.verifying_key()
+                .to_public_key_der()
+                .map_err(|e| Error::InvalidKey(e.to_string()))?
+                .to_vec(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{base64_decode, base64_encode};
+
+    // Manually generated by invoking the methods.
+    const PRIVATE_KEY: &str = "MFECAQEwBQYDK2VwBCIEIK0UnZtqko4hpLGk1gGS3h2Nxr3fRMvPq6e6ofaiBe3CgSEAn4+syYCtGh3sGdktcYMVqEQKuh5EizNF55+KPRT1d0U=";
+    const PUBLIC_KEY: &str = "MCowBQYDK2VwAyEAn4+syYCtGh3sGdktcYMVqEQKuh5EizNF55+KPRT1d0U=";
+    const SIGNATURE: &str =
+        "5iCe/5IDxp7cfj+4FRAysXPDPvOvSnVXUAnHg3+rMbCtQ4/rp9syomjVIxLjPW+1KcpVr8Tel1CPVO5tpEVhDg==";
+    const PLAINTEXT: PayloadBytes<'static> = PayloadBytes::borrowed(b"Hello world");
+
+    #[test]
+    fn test_generated_keys_are_not_equal() -> Result<(), Error> {
+        let key_a = Ed25519.generate_private_key()?;
+        let key_b = Ed25519.generate_private_key()?;
+
+        assert_ne!(key_a, key_b);
+        Ok(())
+    }
+
+    #[test]
+    fn test_derive_public_key() -> Result<(), Error> {
+        assert_eq!(
+            PUBLIC_KEY,
+            base64_encode(
+                Ed25519
+                    .derive_public_key_from_private_key(&PrivateKeyBytes::owned(b64(PRIVATE_KEY)))?
+                    .as_bytes()
+            ),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify() {
+        assert!(Ed25519
+            .verify(
+                &PublicKeyBytes::owned(b64(PUBLIC_KEY)),
+                &PLAINTEXT,
+                &SignatureBytes::owned(b64(SIGNATURE))
+            )
+            .is_ok());
+
+        let mut broken_signature = b64(SIGNATURE);
+        broken_signature[0] = broken_signature[0].wrapping_add(1);
+        assert!(Ed25519
+            .verify(
+                &PublicKeyBytes::owned(b64(PUBLIC_KEY)),
+                &PLAINTEXT,
+                &SignatureBytes::owned(broken_signature)
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_verify_wrong_length_signature() {
+        assert!(matches!(
+            Ed25519.verify(
+                &PublicKeyBytes::owned(b64(PUBLIC_KEY)),
+                &PLAINTEXT,
+                &SignatureBytes::owned(b64(SIGNATURE)[1..].to_vec())
+            ),
+            Err(Error::VerificationFailed)
+        ));
+    }
+
+    #[test]
+    fn test_verify_rejects_weak_public_key() {
+        // The identity point as the public key, with R set to the identity and S set to zero,
+        // satisfies the non-strict verification equation for any payload.
+        let mut public_key = vec![
+            0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+        ];
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+        public_key.extend_from_slice(&identity);
+
+        let mut signature = identity.to_vec();
+        signature.extend_from_slice(&[0u8; 32]);
+
+        assert!(matches!(
+            Ed25519.verify(
+                &PublicKeyBytes::owned(public_key),
+                &PLAINTEXT,
+                &SignatureBytes::owned(signature)
+            ),
+            Err(Error::VerificationFailed)
+        ));
+    }
+
+    #[test]
+    fn test_sign() -> Result<(), Error> {
+        // Ed25519 signatures are deterministic.
+        let signature = Ed25519.sign(&PrivateKeyBytes::owned(b64(PRIVATE_KEY)), &PLAINTEXT)?;
+        assert_eq!(SIGNATURE, base64_encode(signature.as_bytes()));
+
+        Ok(())
+    }
+
+    fn b64(encoded: &str) -> Vec<u8> {
+        base64_decode(encoded).unwrap()
+    }
+}
@@ -0,0 +1,143 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::keys::algorithms::Algorithm;
+use crate::keys::newtypes::{PayloadBytes, PrivateKeyBytes, PublicKeyBytes, SignatureBytes};
+use crate::Error;
+use ml_dsa::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey};
+use ml_dsa::signature::{Keypair, Signer, Verifier};
+use ml_dsa::{Generate, Signature, SigningKey, VerifyingKey};
+use rand::rand_core::UnwrapErr;
+
+type Params = ml_dsa::MlDsa65;
+
+pub(super) struct MlDsa65;
+
+impl Algorithm for MlDsa65 {
+    fn sign(
+        &self,
+        private_key: &PrivateKeyBytes<'_>,
+        payload: &PayloadBytes<'_>,
+    ) -> Result<SignatureBytes<'static>, Error> {
+        let key = SigningKey::<Params>::from_pkcs8_der(private_key.as_bytes())
+            .map_err(|e| Error::InvalidKey(e.to_string()))?;
+
+        let signature: Signature<Params> = key.sign(payload.as_bytes());
+        Ok(SignatureBytes::owned(signature.encode().to_vec()))
+    }
+
+    fn verify(
+        &self,
+        public_key: &PublicKeyBytes<'_>,
+        payload: &PayloadBytes<'_>,
+        signature: &SignatureBytes<'_>,
+    ) -> Result<(), Error> {
+        let key = VerifyingKey::<Params>::from_public_key_der(public_key.as_bytes())
+            .map_err(|e| Error::InvalidKey(e.to_string()))?;
+
+        let signature = Signature::<Params>::try_from(signature.as_bytes())
+            .map_err(|_| Error::VerificationFailed)?;
+        key.verify(payload.as_bytes(), &signature)
+            .map_err(|_| Error::VerificationFailed)
+    }
+
+    fn generate_private_key(&self) -> Result<PrivateKeyBytes<'static>, Error> {
+        let key = SigningKey::<Params>::generate_from_rng(&mut UnwrapErr(rand::rngs::SysRng));
+        Ok(PrivateKeyBytes::owned(
+            key.to_pkcs8_der()
+                .expect("generated private key cannot be encoded")
+                .to_bytes()
+                .to_vec(),
+        ))
+    }
+
+    fn derive_public_key_from_private_key(
+        &self,
+        private_key: &PrivateKeyBytes<'_>,
+    ) -> Result<PublicKeyBytes<'static>, Error> {
+        let key = SigningKey::<Params>::from_pkcs8_der(private_key.as_bytes())
+            .map_err(|e| Error::InvalidKey(e.to_string()))?;
+        Ok(PublicKeyBytes::owned(
+            key.verifying_key()
+                .to_public_key_der()
+                .map_err(|e| Error::InvalidKey(e.to_string()))?
+                .to_vec(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAINTEXT: PayloadBytes<'static> = PayloadBytes::borrowed(b"Hello world");
+
+    // ML-DSA keys and signatures are several kilobytes long, so they are generated by the tests
+    // rather than embedded in them.
+    fn generate() -> (PrivateKeyBytes<'static>, PublicKeyBytes<'static>) {
+        let private = MlDsa65.generate_private_key().unwrap();
+        let public = MlDsa65
+            .derive_public_key_from_private_key(&private)
+            .unwrap();
+        (private, public)
+    }
+
+    #[test]
+    fn test_generated_keys_are_not_equal() -> Result<(), Error> {
+        let key_a = MlDsa65.generate_private_key()?;
+        let key_b = MlDsa65.generate_private_key()?;
+
+        assert_ne!(key_a, key_b);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_and_verify() -> Result<(), Error> {
+        let (private, public) = generate();
+        let signature = MlDsa65.sign(&private, &PLAINTEXT)?;
+        assert!(MlDsa65.verify(&public, &PLAINTEXT, &signature).is_ok());
+
+        let mut broken_signature = signature.as_bytes().to_vec();
+        broken_signature[0] = broken_signature[0].wrapping_add(1);
+        assert!(matches!(
+            MlDsa65.verify(
+                &public,
+                &PLAINTEXT,
+                &SignatureBytes::owned(broken_signature)
+            ),
+            Err(Error::VerificationFailed)
+        ));
+
+        let (_, other_public) = generate();
+        assert!(matches!(
+            MlDsa65.verify(&other_public, &PLAINTEXT, &signature),
+            Err(Error::VerificationFailed)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_wrong_length_signature() -> Result<(), Error> {
+        let (private, public) = generate();
+        let signature = MlDsa65.sign(&private, &PLAINTEXT)?;
+        assert!(matches!(
+            MlDsa65.verify(
+                &public,
+                &PLAINTEXT,
+                &SignatureBytes::owned(signature.as_bytes()[1..].to_vec())
+            ),
+            Err(Error::VerificationFailed)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_derive_public_key_is_stable() -> Result<(), Error> {
+        let (private, public) = generate();
+        assert_eq!(
+            public,
+            MlDsa65.derive_public_key_from_private_key(&private)?
+        );
+        Ok(())
+    }
+}
@@ -2,8 +2,12 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 mod ecdsa_p256_sha256_asn1_spki_der;
+mod ed25519;
+mod ml_dsa_65;
 
 use crate::keys::algorithms::ecdsa_p256_sha256_asn1_spki_der::EcdsaP256Sha256Asn1SpkiDer;
+use crate::keys::algorithms::ed25519::Ed25519;
+use crate::keys::algorithms::ml_dsa_65::MlDsa65;
 use crate::keys::newtypes::{PayloadBytes, PrivateKeyBytes, PublicKeyBytes, SignatureBytes};
 use crate::Error;
 use serde::{Deserialize, Serialize};
@@ -15,15 +19,29 @@ pub enum KeyAlgorithm {
     /// DER.
     #[serde(rename = "ecdsa-p256-sha256-asn1-spki-der")]
     EcdsaP256Sha256Asn1SpkiDer,
+    /// Ed25519 as defined in RFC 8032, with the public key encoded as SPKI with DER.
+    #[serde(rename = "ed25519")]
+    Ed25519,
+    /// ML-DSA-65 as defined in FIPS 204, with the public key encoded as SPKI with DER. This is a
+    /// post-quantum algorithm.
+    #[serde(rename = "ml-dsa-65")]
+    MlDsa65,
     #[serde(other)]
     #[doc(hidden)]
     Unknown,
 }
 
 impl KeyAlgorithm {
+    /// Whether the algorithm is expected to resist attacks by quantum computers.
+    pub fn is_post_quantum(&self) -> bool {
+        matches!(self, KeyAlgorithm::MlDsa65)
+    }
+
     pub(crate) fn methods(&self) -> &'static dyn Algorithm {
         match self {
             KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer => &EcdsaP256Sha256Asn1SpkiDer,
+            KeyAlgorithm::Ed25519 => &Ed25519,
+            KeyAlgorithm::MlDsa65 => &MlDsa65,
             KeyAlgorithm::Unknown => &UnknownAlgorithm,
         }
     }
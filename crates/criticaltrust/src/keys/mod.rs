@@ -13,6 +13,9 @@ mod pair;
 #[cfg(feature = "aws-kms")]
 mod pair_aws_kms;
 mod pair_ephemeral;
+mod pair_file;
+#[cfg(feature = "pkcs11")]
+mod pair_pkcs11;
 mod public;
 
 pub use algorithms::KeyAlgorithm;
@@ -20,4 +23,7 @@ pub use pair::KeyPair;
 #[cfg(feature = "aws-kms")]
 pub use pair_aws_kms::AwsKmsKeyPair;
 pub use pair_ephemeral::EphemeralKeyPair;
+pub use pair_file::{FileKeyPair, KeyPassphrase};
+#[cfg(feature = "pkcs11")]
+pub use pair_pkcs11::Pkcs11KeyPair;
 pub use public::{KeyId, KeyRole, PublicKey};
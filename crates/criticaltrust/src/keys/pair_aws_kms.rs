@@ -28,27 +28,31 @@ impl AwsKmsKeyPair {
     pub async fn new(key_id: &str, kms_client: Client, role: KeyRole) -> Result<Self, Error> {
         let public_key_response = kms_client.get_public_key().key_id(key_id).send().await?;
 
-        let public_key = match public_key_response.key_spec() {
-            Some(KeySpec::EccNistP256)
-                if public_key_response
-                    .signing_algorithms()
-                    .contains(&SigningAlgorithmSpec::EcdsaSha256) =>
-            {
-                PublicKey {
-                    role,
-                    algorithm: KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer,
-                    expiry: None,
-                    public: PublicKeyBytes::owned(
-                        public_key_response
-                            .public_key()
-                            .unwrap()
-                            .clone()
-                            .into_inner(),
-                    ),
-                }
+        let supports = |spec| public_key_response.signing_algorithms().contains(&spec);
+        let algorithm = match public_key_response.key_spec() {
+            Some(KeySpec::EccNistP256) if supports(SigningAlgorithmSpec::EcdsaSha256) => {
+                KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer
+            }
+            Some(KeySpec::EccNistEdwards25519) if supports(SigningAlgorithmSpec::Ed25519Sha512) => {
+                KeyAlgorithm::Ed25519
+            }
+            Some(KeySpec::MlDsa65) if supports(SigningAlgorithmSpec::MlDsaShake256) => {
+                KeyAlgorithm::MlDsa65
             }
             _ => return Err(Error::UnsupportedKey),
         };
+        let public_key = PublicKey {
+            role,
+            algorithm,
+            expiry: None,
+            public: PublicKeyBytes::owned(
+                public_key_response
+                    .public_key()
+                    .unwrap()
+                    .clone()
+                    .into_inner(),
+            ),
+        };
 
         Ok(Self {
             kms: kms_client,
@@ -64,11 +68,24 @@ impl KeyPair for AwsKmsKeyPair {
     }
 
     async fn sign(&self, data: &PayloadBytes<'_>) -> Result<SignatureBytes<'static>, Error> {
-        let (digest, algorithm) = match self.public_key.algorithm {
+        let (message, message_type, algorithm) = match self.public_key.algorithm {
             KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer => (
                 hash_sha256(data.as_bytes()),
+                MessageType::Digest,
                 SigningAlgorithmSpec::EcdsaSha256,
             ),
+            // Ed25519 hashes the message as part of signing, so KMS needs the whole payload.
+            KeyAlgorithm::Ed25519 => (
+                data.as_bytes().to_vec(),
+                MessageType::Raw,
+                SigningAlgorithmSpec::Ed25519Sha512,
+            ),
+            // Same as Ed25519, ML-DSA hashes the message as part of signing.
+            KeyAlgorithm::MlDsa65 => (
+                data.as_bytes().to_vec(),
+                MessageType::Raw,
+                SigningAlgorithmSpec::MlDsaShake256,
+            ),
             KeyAlgorithm::Unknown => return Err(Error::UnsupportedKey),
         };
 
@@ -76,8 +93,8 @@ impl KeyPair for AwsKmsKeyPair {
             .kms
             .sign()
             .key_id(&self.key_id)
-            .message(Blob::new(digest))
-            .message_type(MessageType::Digest)
+            .message(Blob::new(message))
+            .message_type(message_type)
             .signing_algorithm(algorithm)
             .send()
             .await?;
@@ -115,6 +115,19 @@ mod tests {
             .is_ok());
     }
 
+    #[tokio::test]
+    async fn test_ed25519_signatures_are_valid() {
+        let key = EphemeralKeyPair::generate(KeyAlgorithm::Ed25519, KeyRole::Root, None).unwrap();
+        let data = PayloadBytes::borrowed(b"Hello world");
+
+        let signature = key.sign(&data).await.unwrap();
+
+        assert!(key
+            .public()
+            .verify(KeyRole::Root, &data, &signature)
+            .is_ok());
+    }
+
     #[tokio::test]
     async fn test_sign_with_unknown_algorithm_fails() {
         let mut key = EphemeralKeyPair::generate(ALGORITHM, KeyRole::Root, None).unwrap();
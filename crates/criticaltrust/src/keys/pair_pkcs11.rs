@@ -0,0 +1,480 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::newtypes::{PayloadBytes, SignatureBytes};
+use super::PublicKey;
+use crate::keys::newtypes::PublicKeyBytes;
+use crate::keys::{KeyAlgorithm, KeyPair, KeyRole};
+use crate::sha256::hash_sha256;
+use crate::Error;
+use cryptoki::context::Pkcs11;
+use cryptoki::error::RvError;
+use cryptoki::mechanism::eddsa::{EddsaParams, EddsaSignatureScheme};
+use cryptoki::mechanism::Mechanism;
+use cryptoki::object::{Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle};
+use cryptoki::session::{Session, UserType};
+use cryptoki::types::AuthPin;
+use elliptic_curve::pkcs8::EncodePublicKey;
+use std::sync::Mutex;
+
+/// DER encoding of the OID of the P-256 curve (1.2.840.10045.3.1.7).
+const P256_PARAMS: &[u8] = &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+/// DER encoding of the OID of the Ed25519 curve (1.3.101.112).
+const ED25519_PARAMS: &[u8] = &[0x06, 0x03, 0x2b, 0x65, 0x70];
+
+/// Pair of public and private keys stored in a token accessed through PKCS#11, like a Hardware
+/// Security Module.
+///
+/// The private key never leaves the token, and this struct asks the token for every signature
+/// request. The public key is read when the struct is instantiated, and signatures are verified
+/// without interacting with the token.
+pub struct Pkcs11KeyPair {
+    session: Mutex<Session>,
+    private_key: ObjectHandle,
+    public_key: PublicKey,
+}
+
+impl Pkcs11KeyPair {
+    /// Load the key labelled `key_label` from the token labelled `token_label`, logging in with
+    /// the user `pin`. The PKCS#11 library must already be initialized, and the key must use one
+    /// of the algorithms supported by criticaltrust.
+    pub fn new(
+        pkcs11: &Pkcs11,
+        token_label: &str,
+        pin: &str,
+        key_label: &str,
+        role: KeyRole,
+    ) -> Result<Self, Error> {
+        let mut slot = None;
+        for candidate in pkcs11.get_slots_with_token()? {
+            if pkcs11.get_token_info(candidate)?.label().trim_end() == token_label {
+                slot = Some(candidate);
+                break;
+            }
+        }
+        let slot = slot.ok_or_else(|| Error::Pkcs11TokenNotFound(token_label.into()))?;
+
+        let session = pkcs11.open_ro_session(slot)?;
+        // The login state is shared by all the sessions of the application with the token, so
+        // another key pair loaded from the same token might have already logged in.
+        match session.login(UserType::User, Some(&AuthPin::new(pin.into()))) {
+            Ok(()) | Err(cryptoki::error::Error::Pkcs11(RvError::UserAlreadyLoggedIn, _)) => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        let find = |class| -> Result<Option<ObjectHandle>, Error> {
+            let objects = session.find_objects(&[
+                Attribute::Class(class),
+                Attribute::Label(key_label.as_bytes().to_vec()),
+            ])?;
+            match objects.as_slice() {
+                [] => Ok(None),
+                [object] => Ok(Some(*object)),
+                _ => Err(Error::Pkcs11KeyAmbiguous(key_label.into())),
+            }
+        };
+        let (Some(private_key), Some(public_handle)) = (
+            find(ObjectClass::PRIVATE_KEY)?,
+            find(ObjectClass::PUBLIC_KEY)?,
+        ) else {
+            return Err(Error::Pkcs11KeyNotFound(key_label.into()));
+        };
+
+        let attributes = session.get_attributes(
+            public_handle,
+            &[
+                AttributeType::KeyType,
+                AttributeType::EcParams,
+                AttributeType::EcPoint,
+            ],
+        )?;
+        let mut key_type = None;
+        let mut params = None;
+        let mut point = None;
+        for attribute in attributes {
+            match attribute {
+                Attribute::KeyType(value) => key_type = Some(value),
+                Attribute::EcParams(value) => params = Some(value),
+                Attribute::EcPoint(value) => point = Some(value),
+                _ => {}
+            }
+        }
+
+        let (algorithm, public) = match (key_type, params.as_deref(), point) {
+            (Some(KeyType::EC), Some(P256_PARAMS), Some(point)) => {
+                let point = unwrap_octet_string(&point, 65);
+                let key = p256::PublicKey::from_sec1_bytes(point)
+                    .map_err(|e| Error::InvalidKey(e.to_string()))?;
+                (
+                    KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer,
+                    key.to_public_key_der()
+                        .map_err(|e| Error::InvalidKey(e.to_string()))?
+                        .to_vec(),
+                )
+            }
+            (Some(KeyType::EC_EDWARDS), Some(ED25519_PARAMS), Some(point)) => {
+                let point: [u8; 32] = unwrap_octet_string(&point, 32)
+                    .try_into()
+                    .map_err(|_| Error::InvalidKey("invalid Ed25519 public key".into()))?;
+                let key = ed25519_dalek::VerifyingKey::from_bytes(&point)
+                    .map_err(|e| Error::InvalidKey(e.to_string()))?;
+                (
+                    KeyAlgorithm::Ed25519,
+                    ed25519_dalek::pkcs8::EncodePublicKey::to_public_key_der(&key)
+                        .map_err(|e| Error::InvalidKey(e.to_string()))?
+                        .to_vec(),
+                )
+            }
+            _ => return Err(Error::UnsupportedKey),
+        };
+
+        Ok(Self {
+            session: Mutex::new(session),
+            private_key,
+            public_key: PublicKey {
+                role,
+                algorithm,
+                expiry: None,
+                public: PublicKeyBytes::owned(public),
+            },
+        })
+    }
+}
+
+impl KeyPair for Pkcs11KeyPair {
+    fn public(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    async fn sign(&self, data: &PayloadBytes<'_>) -> Result<SignatureBytes<'static>, Error> {
+        let session = self.session.lock().expect("poisoned PKCS#11 session");
+        match self.public_key.algorithm {
+            KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer => {
+                // Tokens return the raw `r || s` signature of the digest, while criticaltrust
+                // encodes signatures as ASN.1.
+                let raw = session.sign(
+                    &Mechanism::Ecdsa,
+                    self.private_key,
+                    &hash_sha256(data.as_bytes()),
+                )?;
+                let signature =
+                    p256::ecdsa::Signature::from_slice(&raw).map_err(|_| Error::SignatureFailed)?;
+                Ok(SignatureBytes::owned(
+                    signature.to_der().to_bytes().to_vec(),
+                ))
+            }
+            KeyAlgorithm::Ed25519 => {
+                let mechanism = Mechanism::Eddsa(EddsaParams::new(EddsaSignatureScheme::Pure));
+                Ok(SignatureBytes::owned(session.sign(
+                    &mechanism,
+                    self.private_key,
+                    data.as_bytes(),
+                )?))
+            }
+            KeyAlgorithm::MlDsa65 | KeyAlgorithm::Unknown => Err(Error::UnsupportedKey),
+        }
+    }
+}
+
+/// Tokens should wrap EC points in a DER-encoded OCTET STRING, but some return the bare point.
+fn unwrap_octet_string(bytes: &[u8], point_len: usize) -> &[u8] {
+    match bytes {
+        [0x04, len, point @ ..] if *len as usize == point_len && point.len() == point_len => point,
+        _ => bytes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cryptoki::context::{CInitializeArgs, CInitializeFlags};
+    use std::path::Path;
+    use std::process::Command;
+
+    // The tests for this module use SoftHSM, a software implementation of a PKCS#11 token, and
+    // are skipped unless the `SOFTHSM2_MODULE` environment variable contains the path of the
+    // SoftHSM library.
+    //
+    // SoftHSM reads its configuration from the path in the `SOFTHSM2_CONF` environment variable.
+    // Rather than changing the environment of the test process, which is shared with the tests
+    // running concurrently, each test re-runs itself in a child process with the variable
+    // pointing to a configuration that stores tokens in a temporary directory.
+
+    const SO_PIN: &str = "12345678";
+    const USER_PIN: &str = "87654321";
+    const CHILD_ENV: &str = "CRITICALTRUST_SOFTHSM_TEST";
+
+    #[tokio::test]
+    async fn test_roundtrip_ecdsa() {
+        let Some(softhsm) = softhsm("test_roundtrip_ecdsa") else {
+            return;
+        };
+        let label = softhsm.create_token();
+        softhsm.generate_key(
+            &label,
+            &Mechanism::EccKeyPairGen,
+            KeyType::EC,
+            P256_PARAMS,
+            "ecdsa",
+        );
+
+        let key =
+            Pkcs11KeyPair::new(&softhsm.pkcs11, &label, USER_PIN, "ecdsa", KeyRole::Root).unwrap();
+        assert_eq!(
+            KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer,
+            key.public().algorithm
+        );
+        assert_roundtrip(&key).await;
+    }
+
+    #[tokio::test]
+    async fn test_roundtrip_ed25519() {
+        let Some(softhsm) = softhsm("test_roundtrip_ed25519") else {
+            return;
+        };
+        let label = softhsm.create_token();
+        softhsm.generate_key(
+            &label,
+            &Mechanism::EccEdwardsKeyPairGen,
+            KeyType::EC_EDWARDS,
+            ED25519_PARAMS,
+            "ed25519",
+        );
+
+        let key = Pkcs11KeyPair::new(&softhsm.pkcs11, &label, USER_PIN, "ed25519", KeyRole::Root)
+            .unwrap();
+        assert_eq!(KeyAlgorithm::Ed25519, key.public().algorithm);
+        assert_roundtrip(&key).await;
+    }
+
+    #[tokio::test]
+    async fn test_multiple_keys_in_the_same_token() {
+        let Some(softhsm) = softhsm("test_multiple_keys_in_the_same_token") else {
+            return;
+        };
+        let label = softhsm.create_token();
+        for key_label in ["first", "second"] {
+            softhsm.generate_key(
+                &label,
+                &Mechanism::EccKeyPairGen,
+                KeyType::EC,
+                P256_PARAMS,
+                key_label,
+            );
+        }
+
+        // Loading the second key logs in while the session of the first key is still logged in.
+        let first =
+            Pkcs11KeyPair::new(&softhsm.pkcs11, &label, USER_PIN, "first", KeyRole::Root).unwrap();
+        let second =
+            Pkcs11KeyPair::new(&softhsm.pkcs11, &label, USER_PIN, "second", KeyRole::Root).unwrap();
+        assert_ne!(first.public().public, second.public().public);
+        assert_roundtrip(&first).await;
+        assert_roundtrip(&second).await;
+    }
+
+    #[tokio::test]
+    async fn test_ambiguous_key_label() {
+        let Some(softhsm) = softhsm("test_ambiguous_key_label") else {
+            return;
+        };
+        let label = softhsm.create_token();
+        for _ in 0..2 {
+            softhsm.generate_key(
+                &label,
+                &Mechanism::EccKeyPairGen,
+                KeyType::EC,
+                P256_PARAMS,
+                "duplicate",
+            );
+        }
+
+        assert!(matches!(
+            Pkcs11KeyPair::new(&softhsm.pkcs11, &label, USER_PIN, "duplicate", KeyRole::Root),
+            Err(Error::Pkcs11KeyAmbiguous(key)) if key == "duplicate"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_missing_token_and_key() {
+        let Some(softhsm) = softhsm("test_missing_token_and_key") else {
+            return;
+        };
+        let label = softhsm.create_token();
+
+        assert!(matches!(
+            Pkcs11KeyPair::new(&softhsm.pkcs11, "missing", USER_PIN, "key", KeyRole::Root),
+            Err(Error::Pkcs11TokenNotFound(token)) if token == "missing"
+        ));
+        assert!(matches!(
+            Pkcs11KeyPair::new(&softhsm.pkcs11, &label, USER_PIN, "missing", KeyRole::Root),
+            Err(Error::Pkcs11KeyNotFound(key)) if key == "missing"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_wrong_pin() {
+        let Some(softhsm) = softhsm("test_wrong_pin") else {
+            return;
+        };
+        let label = softhsm.create_token();
+
+        assert!(matches!(
+            Pkcs11KeyPair::new(&softhsm.pkcs11, &label, "0000", "key", KeyRole::Root),
+            Err(Error::Pkcs11Failed(_))
+        ));
+    }
+
+    async fn assert_roundtrip(key: &Pkcs11KeyPair) {
+        let payload = PayloadBytes::borrowed(b"Hello world");
+        let signature = key.sign(&payload).await.unwrap();
+        key.public()
+            .verify(KeyRole::Root, &payload, &signature)
+            .unwrap();
+
+        let other = PayloadBytes::borrowed(b"Hello world!");
+        assert!(key
+            .public()
+            .verify(KeyRole::Root, &other, &signature)
+            .is_err());
+    }
+
+    /// Loads SoftHSM when running in the child process of `test`. Otherwise, runs `test` in a
+    /// child process configured to use SoftHSM, or skips it if SoftHSM is not available, and
+    /// returns `None`.
+    fn softhsm(test: &str) -> Option<SoftHsm> {
+        let Some(module) = std::env::var_os("SOFTHSM2_MODULE") else {
+            eprintln!("skipping {test}: SOFTHSM2_MODULE is not set");
+            return None;
+        };
+        if std::env::var_os(CHILD_ENV).is_some_and(|child| child == test) {
+            return Some(SoftHsm::load(module.as_ref()));
+        }
+
+        let tokens = tempfile::tempdir().unwrap();
+        let config = tokens.path().join("softhsm2.conf");
+        std::fs::write(
+            &config,
+            format!(
+                "directories.tokendir = {}\nobjectstore.backend = file\n",
+                tokens.path().display()
+            ),
+        )
+        .unwrap();
+
+        let (_krate, module_path) = module_path!().split_once("::").unwrap();
+        let output = Command::new(std::env::current_exe().unwrap())
+            .arg(format!("{module_path}::{test}"))
+            .args(["--exact", "--nocapture"])
+            .env("SOFTHSM2_CONF", &config)
+            .env(CHILD_ENV, test)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            output.status.success() && stdout.contains("1 passed"),
+            "{test} failed in the child process:\n{stdout}\n{stderr}"
+        );
+        None
+    }
+
+    struct SoftHsm {
+        pkcs11: Pkcs11,
+        // Only one token can be initialized at a time, as it uses the first free slot.
+        lock: std::sync::Mutex<usize>,
+    }
+
+    impl SoftHsm {
+        fn load(module: &Path) -> SoftHsm {
+            let pkcs11 = Pkcs11::new(module).expect("failed to load SoftHSM");
+            pkcs11
+                .initialize(CInitializeArgs::new(CInitializeFlags::OS_LOCKING_OK))
+                .unwrap();
+
+            SoftHsm {
+                pkcs11,
+                lock: std::sync::Mutex::new(0),
+            }
+        }
+
+        fn create_token(&self) -> String {
+            let mut count = self.lock.lock().unwrap();
+            *count += 1;
+            let label = format!("criticaltrust-{count}");
+
+            let slot = *self
+                .pkcs11
+                .get_all_slots()
+                .unwrap()
+                .iter()
+                .find(|slot| {
+                    !self
+                        .pkcs11
+                        .get_token_info(**slot)
+                        .unwrap()
+                        .token_initialized()
+                })
+                .expect("no free slot");
+            self.pkcs11
+                .init_token(slot, &AuthPin::new(SO_PIN.into()), &label)
+                .unwrap();
+
+            let session = self.pkcs11.open_rw_session(slot).unwrap();
+            session
+                .login(UserType::So, Some(&AuthPin::new(SO_PIN.into())))
+                .unwrap();
+            session.init_pin(&AuthPin::new(USER_PIN.into())).unwrap();
+            label
+        }
+
+        fn generate_key(
+            &self,
+            token_label: &str,
+            mechanism: &Mechanism,
+            key_type: KeyType,
+            params: &[u8],
+            key_label: &str,
+        ) {
+            let slot = *self
+                .pkcs11
+                .get_slots_with_token()
+                .unwrap()
+                .iter()
+                .find(|slot| {
+                    self.pkcs11
+                        .get_token_info(**slot)
+                        .unwrap()
+                        .label()
+                        .trim_end()
+                        == token_label
+                })
+                .unwrap();
+            let session = self.pkcs11.open_rw_session(slot).unwrap();
+            session
+                .login(UserType::User, Some(&AuthPin::new(USER_PIN.into())))
+                .unwrap();
+
+            let label = Attribute::Label(key_label.as_bytes().to_vec());
+            session
+                .generate_key_pair(
+                    mechanism,
+                    &[
+                        Attribute::Token(true),
+                        Attribute::KeyType(key_type),
+                        Attribute::EcParams(params.to_vec()),
+                        Attribute::Verify(true),
+                        label.clone(),
+                    ],
+                    &[
+                        Attribute::Token(true),
+                        Attribute::Private(true),
+                        Attribute::Sensitive(true),
+                        Attribute::Sign(true),
+                        label,
+                    ],
+                )
+                .unwrap();
+        }
+    }
+}
@@ -7,6 +7,7 @@ use crate::keys::KeyAlgorithm;
 use crate::sha256::hash_sha256;
 use crate::signatures::{PublicKeysRepository, Signable};
 use crate::Error;
+use base64::Engine;
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 
@@ -34,13 +35,25 @@ impl PublicKey {
         role: KeyRole,
         payload: &PayloadBytes<'_>,
         signature: &SignatureBytes<'_>,
+    ) -> Result<(), Error> {
+        self.verify_at(role, payload, signature, OffsetDateTime::now_utc())
+    }
+
+    /// Same as [`verify`](Self::verify), but checking the expiry of the key against the time the
+    /// signature was made at rather than the current time.
+    pub(crate) fn verify_at(
+        &self,
+        role: KeyRole,
+        payload: &PayloadBytes<'_>,
+        signature: &SignatureBytes<'_>,
+        signed_at: OffsetDateTime,
     ) -> Result<(), Error> {
         if role != self.role || role == KeyRole::Unknown {
             return Err(Error::VerificationFailed);
         }
 
         if let Some(expiry) = self.expiry {
-            if OffsetDateTime::now_utc() > expiry {
+            if signed_at > expiry {
                 return Err(Error::VerificationFailed);
             }
         }
@@ -70,6 +83,14 @@ impl PublicKeysRepository for PublicKey {
             None
         }
     }
+
+    fn keys_with_role(&self, role: KeyRole) -> Vec<KeyId> {
+        if self.role == role {
+            vec![self.calculate_id()]
+        } else {
+            Vec::new()
+        }
+    }
 }
 
 impl Signable for PublicKey {
@@ -77,7 +98,7 @@ impl Signable for PublicKey {
 }
 
 /// Role of the key, used to determine which kinds of payloads the key is authorized to verify.
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Copy, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub enum KeyRole {
     /// `releases` key role, used to sign releases.
@@ -90,6 +111,8 @@ pub enum KeyRole {
     Revocation,
     /// `root` key role, used to sign other keys.
     Root,
+    /// `timestamps` key role, used to countersign signatures with the time they were made.
+    Timestamps,
     #[serde(other)]
     #[doc(hidden)]
     Unknown,
@@ -102,6 +125,16 @@ pub enum KeyRole {
 #[serde(transparent)]
 pub struct KeyId(#[serde(with = "crate::serde_base64")] Vec<u8>);
 
+impl std::fmt::Display for KeyId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            base64::engine::general_purpose::STANDARD.encode(&self.0)
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -278,6 +311,7 @@ mod tests {
         // Test just a few positive combinations
         assert!(key(KeyRole::Root, KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer).is_supported());
         assert!(key(KeyRole::Packages, KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer).is_supported());
+        assert!(key(KeyRole::Packages, KeyAlgorithm::Ed25519).is_supported());
     }
 
     #[test]
@@ -9,14 +9,12 @@ pub mod errors;
 pub mod integrity;
 pub mod keys;
 pub mod manifests;
+pub mod revocation_info;
 mod serde_base64;
 mod sha256;
 pub mod signatures;
 pub mod v2;
 
-#[cfg(feature = "hash-revocation")]
-pub mod revocation_info;
-
 #[cfg(test)]
 mod test_utils;
 
@@ -6,11 +6,11 @@
 use std::path::PathBuf;
 
 use crate::keys::{KeyRole, PublicKey};
-#[cfg(feature = "hash-revocation")]
 use crate::revocation_info::RevocationInfo;
 use crate::signatures::{Signable, SignedPayload};
 use serde::de::Error as _;
 use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
 
 /// Typed representation of a manifest version number.
 ///
@@ -103,7 +103,7 @@ pub struct ReleaseArtifact {
     pub sha256: Vec<u8>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Copy)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Copy)]
 pub enum ReleaseArtifactFormat {
     #[serde(rename = "tar.zst")]
     TarZst,
@@ -164,8 +164,21 @@ pub struct PackageFile {
 pub struct KeysManifest {
     pub version: ManifestVersion<1>,
     pub keys: Vec<SignedPayload<PublicKey>>,
-    #[cfg(feature = "hash-revocation")]
     pub revoked_signatures: SignedPayload<RevocationInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub root_rotations: Vec<SignedPayload<RootRotation>>,
+}
+
+/// Replacement of a trust root by a new one, signed by the root being replaced.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RootRotation {
+    pub root: PublicKey,
+    #[serde(with = "time::serde::rfc3339")]
+    pub effective_at: OffsetDateTime,
+}
+
+impl Signable for RootRotation {
+    const SIGNED_BY_ROLE: KeyRole = KeyRole::Root;
 }
 
 #[cfg(test)]
@@ -2,7 +2,9 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::keys::KeyRole;
+use crate::sha256::hash_sha256;
 use crate::signatures::Signable;
+use crate::Error;
 use serde::{Deserialize, Serialize};
 use serde_with::base64::Base64;
 use serde_with::serde_as;
@@ -27,9 +29,28 @@ impl RevocationInfo {
             expires_at,
         }
     }
+
+    /// Ensures `content` was not revoked.
+    ///
+    /// Expired revocation information is rejected rather than ignored, as an outdated copy (for
+    /// example one cached while offline) could be missing revocations published since.
+    pub fn verify(&self, content: &[u8]) -> Result<(), Error> {
+        self.verify_at(content, OffsetDateTime::now_utc())
+    }
+
+    /// Same as [`verify`](Self::verify), but checking the expiry as of `at` instead of now.
+    pub fn verify_at(&self, content: &[u8], at: OffsetDateTime) -> Result<(), Error> {
+        if self.expires_at <= at {
+            return Err(Error::RevocationSignatureExpired(self.expires_at));
+        }
+        let sha256 = hash_sha256(content);
+        if self.revoked_content_sha256.contains(&sha256) {
+            return Err(Error::ContentRevoked(hex::encode(sha256)));
+        }
+        Ok(())
+    }
 }
 
-#[cfg(feature = "hash-revocation")]
 impl Signable for RevocationInfo {
     const SIGNED_BY_ROLE: KeyRole = KeyRole::Revocation;
 }
@@ -38,10 +59,50 @@ impl Signable for RevocationInfo {
 mod tests {
     use super::*;
     use time::macros::datetime;
+    use time::Duration;
 
     #[test]
     fn new_revocation_info() {
         let r = RevocationInfo::new(vec![vec![12, 21, 33]], datetime!(2400-10-10 00:00 UTC));
         assert_eq!("RevocationInfo { revoked_content_sha256: [[12, 21, 33]], expires_at: 2400-10-10 0:00:00.0 +00:00:00 }", format!("{r:?}"));
     }
+
+    #[test]
+    fn verify_content() {
+        let revoked = hash_sha256(b"revoked");
+        let r = RevocationInfo::new(vec![revoked], OffsetDateTime::now_utc() + Duration::days(1));
+
+        assert!(r.verify(b"allowed").is_ok());
+        assert!(matches!(
+            r.verify(b"revoked"),
+            Err(Error::ContentRevoked(hash)) if hash == hex::encode(hash_sha256(b"revoked"))
+        ));
+    }
+
+    #[test]
+    fn verify_content_with_expired_revocation_info() {
+        let r = RevocationInfo::new(vec![], datetime!(1999-12-31 00:00 UTC));
+        assert!(matches!(
+            r.verify(b"allowed"),
+            Err(Error::RevocationSignatureExpired(_))
+        ));
+    }
+
+    #[test]
+    fn verify_content_at_a_past_time() {
+        let revoked = hash_sha256(b"revoked");
+        let r = RevocationInfo::new(vec![revoked], datetime!(1999-12-31 00:00 UTC));
+
+        assert!(r
+            .verify_at(b"allowed", datetime!(1999-12-30 00:00 UTC))
+            .is_ok());
+        assert!(matches!(
+            r.verify_at(b"revoked", datetime!(1999-12-30 00:00 UTC)),
+            Err(Error::ContentRevoked(_))
+        ));
+        assert!(matches!(
+            r.verify_at(b"allowed", datetime!(1999-12-31 00:00 UTC)),
+            Err(Error::RevocationSignatureExpired(_))
+        ));
+    }
 }
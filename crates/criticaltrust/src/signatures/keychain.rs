@@ -2,19 +2,21 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::keys::{KeyId, KeyRole, PublicKey};
-use crate::manifests::KeysManifest;
-#[cfg(feature = "hash-revocation")]
+use crate::manifests::{KeysManifest, RootRotation};
 use crate::revocation_info::RevocationInfo;
 use crate::signatures::{PublicKeysRepository, SignedPayload};
 use crate::Error;
-use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use time::OffsetDateTime;
 
 /// Collection of all trusted public keys.
-#[derive(Serialize, Deserialize)]
+///
+/// Keychains can't be serialized, as a deserialized keychain would be trusted without verifying
+/// any of its keys. They are rebuilt from the trust root and the keys manifest instead.
 pub struct Keychain {
     keys: HashMap<KeyId, PublicKey>,
-    #[cfg(feature = "hash-revocation")]
+    trust_root: PublicKey,
+    root_rotations: Vec<SignedPayload<RootRotation>>,
     revocation_info: Option<RevocationInfo>,
 }
 
@@ -27,7 +29,8 @@ impl Keychain {
     pub fn new(trust_root: &PublicKey) -> Result<Self, Error> {
         let mut keychain = Self {
             keys: HashMap::new(),
-            #[cfg(feature = "hash-revocation")]
+            trust_root: trust_root.clone(),
+            root_rotations: Vec::new(),
             revocation_info: None,
         };
 
@@ -43,34 +46,93 @@ impl Keychain {
         &self.keys
     }
 
-    #[cfg(feature = "hash-revocation")]
     pub fn revocation_info(&self) -> Option<&RevocationInfo> {
         self.revocation_info.as_ref()
     }
 
+    /// The current root of trust: either the one the keychain was created with, or the last root
+    /// it was rotated to.
+    pub fn trust_root(&self) -> &PublicKey {
+        &self.trust_root
+    }
+
+    /// Rotations of the root of trust followed by this keychain, in the order they were followed.
+    pub fn root_rotations(&self) -> &[SignedPayload<RootRotation>] {
+        &self.root_rotations
+    }
+
+    /// Follow the chain of [`RootRotation`]s starting from the current root of trust.
+    ///
+    /// A rotation is followed when it is signed by the current root of trust and already
+    /// effective, after which the rotations signed by the new root are looked for. The other
+    /// rotations are ignored, as they might belong to a different chain or take effect later.
+    ///
+    /// Following a rotation forgets every key trusted through the previous root, so rotations
+    /// have to be followed before keys are loaded.
+    pub fn follow_root_rotations(
+        &mut self,
+        rotations: &[SignedPayload<RootRotation>],
+    ) -> Result<(), Error> {
+        let now = OffsetDateTime::now_utc();
+        // Each rotation is followed at most once, to avoid looping on chains going back to a
+        // previous root. The cache of the payloads is bypassed, as it doesn't record which root
+        // verified them.
+        let mut followed = vec![false; rotations.len()];
+        loop {
+            let next = rotations.iter().enumerate().find_map(|(idx, rotation)| {
+                if followed[idx] {
+                    return None;
+                }
+                let verified = rotation.verify_uncached(&self.trust_root).ok()?;
+                (verified.effective_at <= now).then_some((idx, verified.root))
+            });
+            let Some((idx, root)) = next else {
+                return Ok(());
+            };
+            if root.role != KeyRole::Root {
+                return Err(Error::WrongKeyRoleForTrustRoot(root.role));
+            }
+
+            followed[idx] = true;
+            self.keys.clear();
+            self.revocation_info = None;
+            self.load_inner(&root)?;
+            self.trust_root = root;
+            self.root_rotations.push(rotations[idx].clone());
+        }
+    }
+
     /// Update the [`Keychain`] provided the [`KeysManifest`]:
-    /// 1. Verify and load all the verified keys.
-    /// 2. Verify and replace the Revocation information from the revoked content.
+    /// 1. Follow the rotations of the root of trust.
+    /// 2. Verify and load all the verified keys.
+    /// 3. Verify and replace the Revocation information from the revoked content.
+    ///
+    /// Once loaded, the revocation information is checked every time a payload is verified.
     pub fn load_all(&mut self, keys_manifest: &KeysManifest) -> Result<(), Error> {
-        #[cfg(feature = "hash-revocation")]
         if self.revocation_info.is_some() {
             return Err(Error::RevocationInfoOverwriting);
         }
 
+        self.follow_root_rotations(&keys_manifest.root_rotations)?;
+
         // Load all keys from KeysManifest.
         for key in &keys_manifest.keys {
-            // Invalid keys are silently ignored, as they might be signed by a different root key
-            // used by a different release of criticalup, or they might be using an algorithm not
-            // supported by the current version of criticaltrust.
-            let _ = self.load(key)?;
+            match self.load(key) {
+                Ok(_) => {}
+                // Keys that can't be verified are skipped, as they might be signed by a different
+                // root key used by a different release of criticalup, or by a key that expired.
+                // Keys using an algorithm or role not supported by the current version of
+                // criticaltrust are skipped too. `report` explains why each key was skipped.
+                Err(err @ (Error::VerificationFailed | Error::UnsupportedKey)) => {
+                    tracing::debug!("Skipping a key of the keys manifest: {err}");
+                }
+                Err(err) => return Err(err),
+            }
         }
 
-        #[cfg(feature = "hash-revocation")]
-        {
-            // Special case: verify and load only RevocationInfo.
-            let revocation_info = keys_manifest.revoked_signatures.get_verified(self)?;
-            self.revocation_info = Some(revocation_info.clone());
-        }
+        // Special case: verify and load only RevocationInfo.
+        let revocation_info = keys_manifest.revoked_signatures.get_verified(self)?;
+        self.revocation_info = Some(revocation_info.clone());
 
         Ok(())
     }
@@ -84,6 +146,43 @@ impl Keychain {
         self.load_inner(&key)
     }
 
+    /// List the trust root and every key of the [`KeysManifest`], along with whether this keychain
+    /// trusts them and why not. Meant to diagnose verification failures, for example after
+    /// calling [`load_all`](Self::load_all) (which ignores the keys it can't load).
+    pub fn report(&self, keys_manifest: &KeysManifest) -> Vec<KeyReport> {
+        let now = OffsetDateTime::now_utc();
+        let trusted = |key: &PublicKey, status| match key.expiry {
+            Some(expiry) if expiry < now => KeyStatus::Expired(expiry),
+            _ => status,
+        };
+
+        let mut report = vec![KeyReport {
+            status: trusted(&self.trust_root, KeyStatus::TrustRoot),
+            key: Some(self.trust_root.clone()),
+        }];
+        for signed in &keys_manifest.keys {
+            report.push(match signed.verify_uncached(self) {
+                Ok(key) if !key.is_supported() => KeyReport {
+                    status: KeyStatus::Unsupported,
+                    key: Some(key),
+                },
+                Ok(key) if self.keys.contains_key(&key.calculate_id()) => KeyReport {
+                    status: trusted(&key, KeyStatus::Trusted),
+                    key: Some(key),
+                },
+                Ok(key) => KeyReport {
+                    status: KeyStatus::NotLoaded,
+                    key: Some(key),
+                },
+                Err(err) => KeyReport {
+                    status: KeyStatus::Untrusted(err),
+                    key: signed.deserialize_unverified().ok(),
+                },
+            });
+        }
+        report
+    }
+
     fn load_inner(&mut self, key: &PublicKey) -> Result<KeyId, Error> {
         if !key.is_supported() {
             return Err(Error::UnsupportedKey);
@@ -94,26 +193,65 @@ impl Keychain {
     }
 }
 
+/// Key listed by [`Keychain::report`].
+#[derive(Debug)]
+pub struct KeyReport {
+    /// Contents of the key, or `None` if they could not be decoded. The contents of keys that are
+    /// not trusted were **not** verified, and must only be used for diagnostics.
+    pub key: Option<PublicKey>,
+    pub status: KeyStatus,
+}
+
+/// Whether a [`Keychain`] trusts a key listed by [`Keychain::report`].
+#[derive(Debug)]
+pub enum KeyStatus {
+    /// The key is the root of trust of the keychain.
+    TrustRoot,
+    /// The key is trusted, and can be used to verify signatures.
+    Trusted,
+    /// The key is trusted but expired at the given time, so it can't verify signatures anymore.
+    Expired(OffsetDateTime),
+    /// The role or the algorithm of the key is not supported by this version of criticaltrust.
+    Unsupported,
+    /// The key is signed by a trusted key, but it was not loaded in the keychain, for example
+    /// because it comes before the key signing it in the keys manifest.
+    NotLoaded,
+    /// The signatures of the key could not be verified with the keys in the keychain.
+    Untrusted(Error),
+}
+
 impl PublicKeysRepository for Keychain {
     fn get(&self, id: &KeyId) -> Option<&PublicKey> {
         self.keys.get(id)
     }
+
+    fn revocation_info(&self) -> Option<&RevocationInfo> {
+        self.revocation_info.as_ref()
+    }
+
+    fn keys_with_role(&self, role: KeyRole) -> Vec<KeyId> {
+        let mut ids: Vec<KeyId> = self
+            .keys
+            .iter()
+            .filter(|(_, key)| key.role == role)
+            .map(|(id, _)| id.clone())
+            .collect();
+        // Keep errors listing the keys stable.
+        ids.sort_by_key(|id| id.to_string());
+        ids
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::keys::{EphemeralKeyPair, KeyAlgorithm, KeyPair};
-    #[cfg(feature = "hash-revocation")]
     use crate::manifests::ManifestVersion;
-    use crate::signatures::{Signable, SignedPayload};
-    #[cfg(feature = "hash-revocation")]
+    use crate::signatures::{Signable, SignedPayload, VerificationPolicy};
     use time::macros::datetime;
-    #[cfg(feature = "hash-revocation")]
     use time::{Duration, OffsetDateTime};
 
     // Make sure there is enough number of days for expiration so tests don't need constant updates.
-    #[cfg(feature = "hash-revocation")]
     const EXPIRATION_EXTENSION_IN_DAYS: Duration = Duration::days(180);
 
     #[test]
@@ -212,6 +350,85 @@ mod tests {
         assert!(matches!(keychain.load(&other), Err(Error::UnsupportedKey)));
     }
 
+    #[tokio::test]
+    async fn test_ed25519_trust_root() {
+        let root = generate_key_with(KeyAlgorithm::Ed25519, KeyRole::Root);
+        let mut keychain = Keychain::new(root.public()).unwrap();
+
+        let (key, public) = generate_trusted_key(KeyRole::Packages, &root).await;
+        keychain.load(&public).unwrap();
+
+        let mut payload = SignedPayload::new(&"Hello world".to_string()).unwrap();
+        payload.add_signature(&key).await.unwrap();
+        assert_eq!("Hello world", *payload.get_verified(&keychain).unwrap());
+    }
+
+    // While migrating between algorithms, a keys manifest contains keys of both algorithms, and
+    // payloads are signed by both.
+    #[tokio::test]
+    async fn test_load_all_with_mixed_algorithms() {
+        let root = generate_key(KeyRole::Root);
+        let mut keychain = Keychain::new(root.public()).unwrap();
+
+        let ed25519_root = generate_key_with(KeyAlgorithm::Ed25519, KeyRole::Root);
+        let mut signed_ed25519_root = SignedPayload::new(ed25519_root.public()).unwrap();
+        signed_ed25519_root.add_signature(&root).await.unwrap();
+
+        let (ecdsa_packages, signed_ecdsa_packages) =
+            generate_trusted_key(KeyRole::Packages, &root).await;
+        let ed25519_packages = generate_key_with(KeyAlgorithm::Ed25519, KeyRole::Packages);
+        let mut signed_ed25519_packages = SignedPayload::new(ed25519_packages.public()).unwrap();
+        signed_ed25519_packages
+            .add_signature(&ed25519_root)
+            .await
+            .unwrap();
+
+        let revocation = generate_key_with(KeyAlgorithm::Ed25519, KeyRole::Revocation);
+        let mut signed_revocation = SignedPayload::new(revocation.public()).unwrap();
+        signed_revocation.add_signature(&root).await.unwrap();
+        signed_revocation
+            .add_signature(&ed25519_root)
+            .await
+            .unwrap();
+
+        let mut revoked_signatures = SignedPayload::new(&RevocationInfo::new(
+            vec![],
+            OffsetDateTime::now_utc() + Duration::days(1),
+        ))
+        .unwrap();
+        revoked_signatures.add_signature(&revocation).await.unwrap();
+
+        keychain
+            .load_all(&KeysManifest {
+                version: ManifestVersion,
+                keys: vec![
+                    signed_ed25519_root,
+                    signed_ecdsa_packages,
+                    signed_ed25519_packages,
+                    signed_revocation,
+                ],
+                revoked_signatures,
+                root_rotations: vec![],
+            })
+            .unwrap();
+
+        let mut payload = SignedPayload::new(&"Hello world".to_string()).unwrap();
+        payload.add_signature(&ecdsa_packages).await.unwrap();
+        payload.add_signature(&ed25519_packages).await.unwrap();
+        assert!(payload
+            .get_verified_with_policy(
+                &keychain,
+                &VerificationPolicy::new().require(KeyRole::Packages, 2)
+            )
+            .is_ok());
+
+        for key in [&ecdsa_packages, &ed25519_packages] {
+            let mut payload = SignedPayload::new(&"Hello world".to_string()).unwrap();
+            payload.add_signature(key).await.unwrap();
+            assert!(payload.get_verified(&keychain).is_ok());
+        }
+    }
+
     impl Signable for String {
         const SIGNED_BY_ROLE: KeyRole = KeyRole::Packages;
     }
@@ -222,7 +439,6 @@ mod tests {
 
     // Test `load_all` method with RevocationInfo being an empty list.
     #[tokio::test]
-    #[cfg(feature = "hash-revocation")]
     async fn test_load_all_revoked_content_empty() {
         let root = generate_key(KeyRole::Root);
         let (revocation_keypair, signed_public_revocation_key) =
@@ -241,6 +457,7 @@ mod tests {
             version: ManifestVersion,
             keys: vec![signed_public_revocation_key],
             revoked_signatures: signed_revoked_content,
+            root_rotations: vec![],
         };
 
         keychain.load_all(&keys_manifest).unwrap();
@@ -253,7 +470,6 @@ mod tests {
     // Test `load_all` method with RevocationInfo but with one item in the list. The call
     // to `load_all` should not fail in verifying the revocation key.
     #[tokio::test]
-    #[cfg(feature = "hash-revocation")]
     async fn test_load_all_revoked_content_one_item() {
         let root = generate_key(KeyRole::Root);
         let (revocation_keypair, signed_public_revocation_key) =
@@ -274,6 +490,7 @@ mod tests {
             version: ManifestVersion,
             keys: vec![signed_public_revocation_key],
             revoked_signatures: signed_revoked_content,
+            root_rotations: vec![],
         };
 
         keychain.load_all(&keys_manifest).unwrap();
@@ -284,7 +501,6 @@ mod tests {
     }
 
     #[tokio::test]
-    #[cfg(feature = "hash-revocation")]
     async fn test_error_on_load_all_when_revocation_info_is_some() {
         let root = generate_key(KeyRole::Root);
         let (revocation_keypair, signed_public_revocation_key) =
@@ -303,6 +519,7 @@ mod tests {
             version: ManifestVersion,
             keys: vec![signed_public_revocation_key],
             revoked_signatures: signed_revoked_content,
+            root_rotations: vec![],
         };
         keychain.load_all(&keys_manifest).unwrap();
         assert!(keychain.revocation_info.is_some());
@@ -312,10 +529,250 @@ mod tests {
         ));
     }
 
+    // Root rotations
+
+    #[tokio::test]
+    async fn test_follow_root_rotation() {
+        let old_root = generate_key(KeyRole::Root);
+        let new_root = generate_key(KeyRole::Root);
+        let mut keychain = Keychain::new(old_root.public()).unwrap();
+
+        let rotation = generate_rotation(&old_root, &new_root, Duration::days(-1)).await;
+        keychain.follow_root_rotations(&[rotation]).unwrap();
+
+        assert_eq!(new_root.public(), keychain.trust_root());
+        assert_eq!(1, keychain.root_rotations().len());
+        assert!(keychain.get(&old_root.public().calculate_id()).is_none());
+
+        // Keys are only trusted through the new root.
+        let (_, old_key) = generate_trusted_key(KeyRole::Packages, &old_root).await;
+        assert!(matches!(
+            keychain.load(&old_key),
+            Err(Error::VerificationFailed)
+        ));
+        let (_, new_key) = generate_trusted_key(KeyRole::Packages, &new_root).await;
+        assert!(keychain.load(&new_key).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_follow_chain_of_root_rotations() {
+        let roots: Vec<_> = (0..3).map(|_| generate_key(KeyRole::Root)).collect();
+
+        // The order of the rotations doesn't matter.
+        let mut rotations = vec![
+            generate_rotation(&roots[1], &roots[2], Duration::days(-1)).await,
+            generate_rotation(&roots[0], &roots[1], Duration::days(-2)).await,
+        ];
+        let mut keychain = Keychain::new(roots[0].public()).unwrap();
+        keychain.follow_root_rotations(&rotations).unwrap();
+        assert_eq!(roots[2].public(), keychain.trust_root());
+        assert_eq!(2, keychain.root_rotations().len());
+
+        // Going back to a previous root doesn't loop forever.
+        rotations.push(generate_rotation(&roots[2], &roots[0], Duration::days(-1)).await);
+        let mut keychain = Keychain::new(roots[0].public()).unwrap();
+        keychain.follow_root_rotations(&rotations).unwrap();
+        assert_eq!(roots[0].public(), keychain.trust_root());
+        assert_eq!(3, keychain.root_rotations().len());
+    }
+
+    #[tokio::test]
+    async fn test_ignore_pending_and_untrusted_root_rotations() {
+        let root = generate_key(KeyRole::Root);
+        let other_root = generate_key(KeyRole::Root);
+        let new_root = generate_key(KeyRole::Root);
+        let mut keychain = Keychain::new(root.public()).unwrap();
+
+        let rotations = [
+            generate_rotation(&root, &new_root, Duration::days(1)).await,
+            generate_rotation(&other_root, &new_root, Duration::days(-1)).await,
+        ];
+        keychain.follow_root_rotations(&rotations).unwrap();
+
+        assert_eq!(root.public(), keychain.trust_root());
+        assert!(keychain.root_rotations().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_root_rotation_to_non_root_key() {
+        let root = generate_key(KeyRole::Root);
+        let packages = generate_key(KeyRole::Packages);
+        let mut keychain = Keychain::new(root.public()).unwrap();
+
+        let rotation = generate_rotation(&root, &packages, Duration::days(-1)).await;
+        assert!(matches!(
+            keychain.follow_root_rotations(&[rotation]),
+            Err(Error::WrongKeyRoleForTrustRoot(KeyRole::Packages))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_load_all_follows_root_rotations() {
+        let old_root = generate_key(KeyRole::Root);
+        let new_root = generate_key(KeyRole::Root);
+        let (_, old_packages) = generate_trusted_key(KeyRole::Packages, &old_root).await;
+        let (revocation_keypair, revocation) =
+            generate_trusted_key(KeyRole::Revocation, &new_root).await;
+
+        let mut revoked_signatures = SignedPayload::new(&RevocationInfo::new(
+            vec![],
+            OffsetDateTime::now_utc() + EXPIRATION_EXTENSION_IN_DAYS,
+        ))
+        .unwrap();
+        revoked_signatures
+            .add_signature(&revocation_keypair)
+            .await
+            .unwrap();
+
+        let mut keychain = Keychain::new(old_root.public()).unwrap();
+        keychain
+            .load_all(&KeysManifest {
+                version: ManifestVersion,
+                keys: vec![old_packages, revocation],
+                revoked_signatures,
+                root_rotations: vec![
+                    generate_rotation(&old_root, &new_root, Duration::days(-1)).await,
+                ],
+            })
+            .unwrap();
+
+        assert_eq!(new_root.public(), keychain.trust_root());
+        assert_eq!(vec![KeyRole::Root, KeyRole::Revocation], {
+            let mut roles: Vec<_> = keychain.keys().values().map(|key| key.role).collect();
+            roles.sort_by_key(|role| *role != KeyRole::Root);
+            roles
+        });
+        assert!(keychain.revocation_info().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_load_all_rejects_malformed_keys() {
+        let root = generate_key(KeyRole::Root);
+        let (revocation, signed_revocation) =
+            generate_trusted_key(KeyRole::Revocation, &root).await;
+
+        // A payload correctly signed by the root of trust, which is not a key.
+        let mut not_a_key = SignedPayload::new(&RevocationInfo::new(
+            vec![],
+            OffsetDateTime::now_utc() + EXPIRATION_EXTENSION_IN_DAYS,
+        ))
+        .unwrap();
+        not_a_key.add_signature(&root).await.unwrap();
+        let not_a_key: SignedPayload<PublicKey> =
+            serde_json::from_str(&serde_json::to_string(&not_a_key).unwrap()).unwrap();
+
+        let mut revoked_signatures = SignedPayload::new(&RevocationInfo::new(
+            vec![],
+            OffsetDateTime::now_utc() + EXPIRATION_EXTENSION_IN_DAYS,
+        ))
+        .unwrap();
+        revoked_signatures.add_signature(&revocation).await.unwrap();
+
+        let mut keychain = Keychain::new(root.public()).unwrap();
+        assert!(matches!(
+            keychain.load_all(&KeysManifest {
+                version: ManifestVersion,
+                keys: vec![signed_revocation, not_a_key],
+                revoked_signatures,
+                root_rotations: vec![],
+            }),
+            Err(Error::DeserializationFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_report() {
+        let root = generate_key(KeyRole::Root);
+        let other_root = generate_key(KeyRole::Root);
+        let mut keychain = Keychain::new(root.public()).unwrap();
+
+        let (_, trusted) = generate_trusted_key(KeyRole::Packages, &root).await;
+        let (_, untrusted) = generate_trusted_key(KeyRole::Packages, &other_root).await;
+        let (revocation, signed_revocation) =
+            generate_trusted_key(KeyRole::Revocation, &root).await;
+
+        let expired_at = OffsetDateTime::now_utc() - Duration::days(1);
+        let expired = EphemeralKeyPair::generate(
+            KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer,
+            KeyRole::Releases,
+            Some(expired_at),
+        )
+        .unwrap();
+        let mut signed_expired = SignedPayload::new(expired.public()).unwrap();
+        signed_expired.add_signature(&root).await.unwrap();
+
+        let mut unsupported_key = generate_key(KeyRole::Releases).public().clone();
+        unsupported_key.role = KeyRole::Unknown;
+        let mut unsupported = SignedPayload::new(&unsupported_key).unwrap();
+        unsupported.add_signature(&root).await.unwrap();
+
+        let mut revoked_signatures = SignedPayload::new(&RevocationInfo::new(
+            vec![],
+            OffsetDateTime::now_utc() + EXPIRATION_EXTENSION_IN_DAYS,
+        ))
+        .unwrap();
+        revoked_signatures.add_signature(&revocation).await.unwrap();
+
+        let manifest = KeysManifest {
+            version: ManifestVersion,
+            keys: vec![
+                trusted,
+                untrusted,
+                signed_revocation,
+                signed_expired,
+                unsupported,
+            ],
+            revoked_signatures,
+            root_rotations: vec![],
+        };
+        keychain.load_all(&manifest).unwrap();
+
+        let report = keychain.report(&manifest);
+        assert_eq!(6, report.len());
+        assert!(matches!(report[0].status, KeyStatus::TrustRoot));
+        assert_eq!(Some(root.public()), report[0].key.as_ref());
+        assert!(matches!(report[1].status, KeyStatus::Trusted));
+        assert!(matches!(
+            report[2].status,
+            KeyStatus::Untrusted(Error::VerificationFailed)
+        ));
+        // The contents of untrusted keys are still decoded, to help diagnosing the failure.
+        assert_eq!(KeyRole::Packages, report[2].key.as_ref().unwrap().role);
+        assert!(matches!(report[3].status, KeyStatus::Trusted));
+        assert!(matches!(report[4].status, KeyStatus::Expired(at) if at == expired_at));
+        assert!(matches!(report[5].status, KeyStatus::Unsupported));
+    }
+
+    #[tokio::test]
+    async fn test_report_keys_not_loaded() {
+        let root = generate_key(KeyRole::Root);
+        let keychain = Keychain::new(root.public()).unwrap();
+        let (_, signed) = generate_trusted_key(KeyRole::Packages, &root).await;
+
+        let mut revoked_signatures = SignedPayload::new(&RevocationInfo::new(
+            vec![],
+            OffsetDateTime::now_utc() + EXPIRATION_EXTENSION_IN_DAYS,
+        ))
+        .unwrap();
+        revoked_signatures.add_signature(&root).await.unwrap();
+
+        let report = keychain.report(&KeysManifest {
+            version: ManifestVersion,
+            keys: vec![signed],
+            revoked_signatures,
+            root_rotations: vec![],
+        });
+        assert!(matches!(report[1].status, KeyStatus::NotLoaded));
+    }
+
     // Utilities
 
     fn generate_key(role: KeyRole) -> EphemeralKeyPair {
-        EphemeralKeyPair::generate(KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer, role, None).unwrap()
+        generate_key_with(KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer, role)
+    }
+
+    fn generate_key_with(algorithm: KeyAlgorithm, role: KeyRole) -> EphemeralKeyPair {
+        EphemeralKeyPair::generate(algorithm, role, None).unwrap()
     }
 
     async fn generate_trusted_key<K: KeyPair>(
@@ -327,4 +784,18 @@ mod tests {
         payload.add_signature(trusted_by).await.unwrap();
         (key, payload)
     }
+
+    async fn generate_rotation<K: KeyPair>(
+        from: &K,
+        to: &EphemeralKeyPair,
+        effective_in: Duration,
+    ) -> SignedPayload<RootRotation> {
+        let mut payload = SignedPayload::new(&RootRotation {
+            root: to.public().clone(),
+            effective_at: OffsetDateTime::now_utc() + effective_in,
+        })
+        .unwrap();
+        payload.add_signature(from).await.unwrap();
+        payload
+    }
 }
@@ -5,10 +5,15 @@
 //!
 //! This module provides [`SignedPayload`], a wrapper around digitally signed payloads that
 //! enforces signatures are properly verified before the inner contents are accessible.
-//! [`Keychain`] is also provided to establish a root of trust.
+//! [`Keychain`] is also provided to establish a root of trust, [`VerificationPolicy`] to require
+//! multiple signatures, and [`Timestamp`] to keep signatures valid after their keys expire.
 
 mod keychain;
 mod payload;
+mod policy;
+mod timestamp;
 
-pub use keychain::Keychain;
+pub use keychain::{KeyReport, KeyStatus, Keychain};
 pub use payload::{PublicKeysRepository, Signable, SignedPayload};
+pub use policy::VerificationPolicy;
+pub use timestamp::Timestamp;
@@ -3,9 +3,13 @@
 
 use crate::keys::newtypes::{PayloadBytes, SignatureBytes};
 use crate::keys::{KeyId, KeyPair, KeyRole, PublicKey};
+use crate::revocation_info::RevocationInfo;
+use crate::signatures::timestamp::{verify_timestamp, Timestamp};
+use crate::signatures::VerificationPolicy;
 use crate::Error;
 use serde::{Deserialize, Serialize};
 use std::cell::{Ref, RefCell};
+use time::OffsetDateTime;
 
 /// Piece of data with signatures attached to it.
 ///
@@ -17,7 +21,7 @@ pub struct SignedPayload<T: Signable> {
     signatures: Vec<Signature>,
     signed: String,
     #[serde(skip)]
-    verified_deserialized: RefCell<Option<T>>,
+    verified_deserialized: RefCell<Option<Verified<T>>>,
 }
 
 impl<T: Signable> std::fmt::Debug for SignedPayload<T> {
@@ -49,24 +53,87 @@ impl<T: Signable> SignedPayload<T> {
             signature: keypair
                 .sign(&PayloadBytes::borrowed(self.signed.as_bytes()))
                 .await?,
+            timestamp: None,
         });
         Ok(())
     }
 
+    /// Countersign every signature without a timestamp with the provided [`KeyPair`], which
+    /// should have the [`Timestamps`](KeyRole::Timestamps) role, attesting the signatures were
+    /// made now. See [`Timestamp`] for how timestamps are used.
+    pub async fn add_timestamp<K: KeyPair>(&mut self, timestamp_key: &K) -> Result<(), Error> {
+        self.add_timestamp_at(timestamp_key, OffsetDateTime::now_utc())
+            .await
+    }
+
+    pub(crate) async fn add_timestamp_at<K: KeyPair>(
+        &mut self,
+        timestamp_key: &K,
+        signed_at: OffsetDateTime,
+    ) -> Result<(), Error> {
+        for signature in &mut self.signatures {
+            if signature.timestamp.is_some() {
+                continue;
+            }
+            let mut timestamp =
+                SignedPayload::new(&Timestamp::new(&signature.signature, signed_at))?;
+            timestamp.add_signature(timestamp_key).await?;
+            signature.timestamp = Some(timestamp);
+        }
+        // Timestamps change which signatures are valid, so previous verifications are stale.
+        *self.verified_deserialized.get_mut() = None;
+        Ok(())
+    }
+
+    /// Add a hybrid signature to this signed payload, made of a signature by a classical key and
+    /// one by a post-quantum key. Payloads signed this way stay trusted even if one of the two
+    /// algorithms is broken, once verified with [`VerificationPolicy::require_hybrid`].
+    pub async fn add_hybrid_signature<C: KeyPair, Q: KeyPair>(
+        &mut self,
+        classical: &C,
+        post_quantum: &Q,
+    ) -> Result<(), Error> {
+        if classical.public().algorithm.is_post_quantum()
+            || !post_quantum.public().algorithm.is_post_quantum()
+        {
+            return Err(Error::InvalidHybridKeys);
+        }
+        self.add_signature(classical).await?;
+        self.add_signature(post_quantum).await
+    }
+
     /// Verifies the signatures attached to the signed payload and returns the deserialized data
     /// (if the signature matched).
     ///
     /// As signature verification and deserialization is expensive, it is only performed the first
     /// time the method is called. The cached results from the initial call will be returned in the
-    /// rest of the cases.
+    /// rest of the cases, after checking the payload against the current revocation information.
     pub fn get_verified(&self, keys: &dyn PublicKeysRepository) -> Result<Ref<'_, T>, Error> {
+        self.get_verified_with_policy(keys, &VerificationPolicy::default())
+    }
+
+    /// Same as [`get_verified`](Self::get_verified), but requiring the payload to be signed by as
+    /// many distinct keys as the [`VerificationPolicy`] asks for.
+    ///
+    /// The keys that signed the payload are cached along with the data, so a cached payload is
+    /// still checked against the policy.
+    pub fn get_verified_with_policy(
+        &self,
+        keys: &dyn PublicKeysRepository,
+        policy: &VerificationPolicy,
+    ) -> Result<Ref<'_, T>, Error> {
         let borrow = self.verified_deserialized.borrow();
 
-        if borrow.is_none() {
+        if let Some(verified) = &*borrow {
+            check_revocation(keys, policy, &self.signed, verified.signed_at)?;
+            check_policy::<T>(keys, policy, &verified.signed_by, &verified.timestamped)?;
+        } else {
             let value = verify_signature(
                 keys,
+                policy,
                 &self.signatures,
                 PayloadBytes::borrowed(self.signed.as_bytes()),
+                None,
             )?;
 
             // In theory, `borrow_mut()` could panic if an immutable borrow was alive at the same
@@ -77,7 +144,7 @@ impl<T: Signable> SignedPayload<T> {
         }
 
         Ok(Ref::map(self.verified_deserialized.borrow(), |b| {
-            b.as_ref().unwrap()
+            &b.as_ref().unwrap().value
         }))
     }
 
@@ -88,41 +155,204 @@ impl<T: Signable> SignedPayload<T> {
     /// returned. Otherwise, signature verification will be performed with the provided keychain
     /// before deserializing.
     pub fn into_verified(self, keys: &dyn PublicKeysRepository) -> Result<T, Error> {
-        if let Some(deserialized) = self.verified_deserialized.into_inner() {
-            Ok(deserialized)
+        self.into_verified_with_policy(keys, &VerificationPolicy::default())
+    }
+
+    /// Same as [`into_verified`](Self::into_verified), but requiring the payload to be signed by
+    /// as many distinct keys as the [`VerificationPolicy`] asks for.
+    pub fn into_verified_with_policy(
+        self,
+        keys: &dyn PublicKeysRepository,
+        policy: &VerificationPolicy,
+    ) -> Result<T, Error> {
+        if let Some(verified) = self.verified_deserialized.into_inner() {
+            check_revocation(keys, policy, &self.signed, verified.signed_at)?;
+            check_policy::<T>(keys, policy, &verified.signed_by, &verified.timestamped)?;
+            Ok(verified.value)
         } else {
             verify_signature(
                 keys,
+                policy,
                 &self.signatures,
                 PayloadBytes::borrowed(self.signed.as_bytes()),
+                None,
             )
+            .map(|verified| verified.value)
         }
     }
+
+    /// Verifies the signatures without looking at or populating the cache, for payloads whose
+    /// trusted keys change between verifications.
+    pub(crate) fn verify_uncached(&self, keys: &dyn PublicKeysRepository) -> Result<T, Error> {
+        self.verify_uncached_signed_at(keys, &VerificationPolicy::default(), None)
+    }
+
+    /// Same as [`verify_uncached`](Self::verify_uncached), but with the time the payload claims
+    /// to be signed at, used instead of its timestamps when the policy checks the revocation
+    /// information as of the signing time.
+    pub(crate) fn verify_uncached_signed_at(
+        &self,
+        keys: &dyn PublicKeysRepository,
+        policy: &VerificationPolicy,
+        signed_at: Option<OffsetDateTime>,
+    ) -> Result<T, Error> {
+        verify_signature(
+            keys,
+            policy,
+            &self.signatures,
+            PayloadBytes::borrowed(self.signed.as_bytes()),
+            signed_at,
+        )
+        .map(|verified| verified.value)
+    }
+
+    /// Deserializes the payload **without** verifying its signatures. The result must not be
+    /// trusted, and is only meant to describe payloads that failed verification.
+    pub(crate) fn deserialize_unverified(&self) -> Result<T, Error> {
+        serde_json::from_str(&self.signed).map_err(Error::DeserializationFailed)
+    }
+}
+
+/// Deserialized payload, along with the keys whose signature was verified, the subset of them
+/// whose signature has a trusted timestamp, and when the payload was signed according to them.
+#[derive(Clone)]
+struct Verified<T> {
+    value: T,
+    signed_by: Vec<KeyId>,
+    timestamped: Vec<KeyId>,
+    signed_at: Option<OffsetDateTime>,
 }
 
 fn verify_signature<T: Signable>(
     keys: &dyn PublicKeysRepository,
+    policy: &VerificationPolicy,
     signatures: &[Signature],
     signed: PayloadBytes<'_>,
-) -> Result<T, Error> {
+    signed_at: Option<OffsetDateTime>,
+) -> Result<Verified<T>, Error> {
+    let now = OffsetDateTime::now_utc();
+    let mut signed_by: Vec<KeyId> = Vec::new();
+    let mut timestamped: Vec<KeyId> = Vec::new();
+    let mut latest_timestamp = None;
+    let mut failure = None;
     for signature in signatures {
+        // Multiple signatures by the same key only count once.
+        if signed_by.contains(&signature.key_sha256) {
+            continue;
+        }
         let key = match keys.get(&signature.key_sha256) {
             Some(key) => key,
             None => continue,
         };
 
-        match key.verify(T::SIGNED_BY_ROLE, &signed, &signature.signature) {
-            Ok(()) => {}
+        // A trusted timestamp proves when the signature was made, so the signature stays valid
+        // after the key expires.
+        let signed_at = signature
+            .timestamp
+            .as_ref()
+            .and_then(|timestamp| verify_timestamp(keys, policy, timestamp, &signature.signature));
+        let at = signed_at.map_or(now, |signed_at| signed_at.min(now));
+
+        match key.verify_at(T::SIGNED_BY_ROLE, &signed, &signature.signature, at) {
+            Ok(()) => {
+                signed_by.push(signature.key_sha256.clone());
+                if signed_at.is_some() {
+                    timestamped.push(signature.key_sha256.clone());
+                    latest_timestamp = latest_timestamp.max(Some(at));
+                }
+            }
             Err(Error::VerificationFailed) => continue,
-            Err(other) => return Err(other),
+            // Other failures, like a malformed key, only discard this signature: the payload is
+            // still accepted if enough of the other signatures are valid.
+            Err(other) => {
+                failure.get_or_insert(other);
+            }
+        }
+    }
+
+    if signed_by.is_empty() {
+        return Err(failure.unwrap_or(Error::VerificationFailed));
+    }
+    let signed_at = signed_at.or(latest_timestamp);
+    check_revocation(keys, policy, signed.as_bytes(), signed_at)?;
+
+    check_policy::<T>(keys, policy, &signed_by, &timestamped)?;
+
+    // Deserialization is performed after the signature is verified, to ensure we are not
+    // deserializing malicious data.
+    let value = serde_json::from_slice(signed.as_bytes()).map_err(Error::DeserializationFailed)?;
+    Ok(Verified {
+        value,
+        signed_by,
+        timestamped,
+        signed_at,
+    })
+}
+
+/// Rejects revoked payloads. Also performed for cached payloads, as the revocation information of
+/// the keys might be newer than the verification.
+///
+/// The revocation information must be unexpired as of now, or as of the latest trusted timestamp
+/// of the payload if the policy allows it.
+fn check_revocation(
+    keys: &dyn PublicKeysRepository,
+    policy: &VerificationPolicy,
+    signed: impl AsRef<[u8]>,
+    signed_at: Option<OffsetDateTime>,
+) -> Result<(), Error> {
+    let Some(revocation_info) = keys.revocation_info() else {
+        return Ok(());
+    };
+    match signed_at {
+        Some(signed_at) if policy.checks_revocation_at_timestamp() => {
+            revocation_info.verify_at(signed.as_ref(), signed_at)
         }
+        _ => revocation_info.verify(signed.as_ref()),
+    }
+}
+
+fn check_policy<T: Signable>(
+    keys: &dyn PublicKeysRepository,
+    policy: &VerificationPolicy,
+    signed_by: &[KeyId],
+    timestamped: &[KeyId],
+) -> Result<(), Error> {
+    let required = policy.threshold(T::SIGNED_BY_ROLE);
+    if signed_by.len() < required {
+        return Err(Error::NotEnoughSignatures {
+            role: T::SIGNED_BY_ROLE,
+            required,
+            signed_by: signed_by.to_vec(),
+            missing: keys
+                .keys_with_role(T::SIGNED_BY_ROLE)
+                .into_iter()
+                .filter(|id| !signed_by.contains(id))
+                .collect(),
+        });
+    }
 
-        // Deserialization is performed after the signature is verified, to ensure we are not
-        // deserializing malicious data.
-        return serde_json::from_slice(signed.as_bytes()).map_err(Error::DeserializationFailed);
+    if policy.requires_hybrid(T::SIGNED_BY_ROLE) {
+        let post_quantum: Vec<bool> = signed_by
+            .iter()
+            .filter_map(|id| keys.get(id))
+            .map(|key| key.algorithm.is_post_quantum())
+            .collect();
+        for missing_post_quantum in [true, false] {
+            if !post_quantum.contains(&missing_post_quantum) {
+                return Err(Error::MissingHybridSignature {
+                    role: T::SIGNED_BY_ROLE,
+                    missing_post_quantum,
+                });
+            }
+        }
     }
 
-    Err(Error::VerificationFailed)
+    if policy.requires_timestamp(T::SIGNED_BY_ROLE) && timestamped.is_empty() {
+        return Err(Error::MissingTimestamp {
+            role: T::SIGNED_BY_ROLE,
+        });
+    }
+    Ok(())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -130,6 +360,8 @@ struct Signature {
     key_sha256: KeyId,
     #[serde(with = "crate::serde_base64")]
     signature: SignatureBytes<'static>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp: Option<SignedPayload<Timestamp>>,
 }
 
 /// Trait representing contents that can be wrapped in a [`SignedPayload`].
@@ -146,23 +378,31 @@ pub trait Signable: Serialize + for<'de> Deserialize<'de> {
 pub trait PublicKeysRepository {
     /// Retrieve a key by its ID.
     fn get(&self, id: &KeyId) -> Option<&PublicKey>;
+
+    /// Revocation information every verified payload is checked against, if any.
+    fn revocation_info(&self) -> Option<&RevocationInfo> {
+        None
+    }
+
+    /// IDs of the keys with the given role, listed when a payload lacks signatures. Repositories
+    /// unable to enumerate their keys can keep the default, returning no keys.
+    fn keys_with_role(&self, _role: KeyRole) -> Vec<KeyId> {
+        Vec::new()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::keys::{EphemeralKeyPair, PublicKey};
+    use crate::keys::newtypes::PublicKeyBytes;
+    use crate::keys::{EphemeralKeyPair, KeyAlgorithm, PublicKey};
     use crate::manifests::{KeysManifest, ManifestVersion};
-    #[cfg(feature = "hash-revocation")]
-    use crate::revocation_info::RevocationInfo;
     use crate::signatures::Keychain;
     use crate::test_utils::{base64_encode, TestEnvironment};
-    #[cfg(feature = "hash-revocation")]
     use time::{Duration, OffsetDateTime};
 
     const SAMPLE_DATA: &str = r#"{"answer":42}"#;
     // Make sure there is enough number of days for expiration so tests don't need constant updates.
-    #[cfg(feature = "hash-revocation")]
     const EXPIRATION_EXTENSION_IN_DAYS: Duration = Duration::days(180);
 
     #[tokio::test]
@@ -316,6 +556,350 @@ mod tests {
         );
     }
 
+    // Verification policies
+
+    #[tokio::test]
+    async fn test_policy_with_enough_signatures() {
+        let mut test_env = TestEnvironment::prepare().await;
+        let key1 = test_env.create_key(KeyRole::Packages).await;
+        let key2 = test_env.create_key(KeyRole::Packages).await;
+        let policy = VerificationPolicy::new().require(KeyRole::Packages, 2);
+
+        let payload = prepare_payload(&[&key1, &key2], SAMPLE_DATA).await;
+        assert_eq!(
+            42,
+            payload
+                .get_verified_with_policy(test_env.keychain(), &policy)
+                .unwrap()
+                .answer
+        );
+        let payload = prepare_payload(&[&key2, &key1], SAMPLE_DATA).await;
+        assert_eq!(
+            42,
+            payload
+                .into_verified_with_policy(test_env.keychain(), &policy)
+                .unwrap()
+                .answer
+        );
+    }
+
+    #[tokio::test]
+    async fn test_policy_with_not_enough_signatures() {
+        let mut test_env = TestEnvironment::prepare().await;
+        let key1 = test_env.create_key(KeyRole::Packages).await;
+        let key2 = test_env.create_key(KeyRole::Packages).await;
+        let untrusted = test_env.create_untrusted_key(KeyRole::Packages);
+        let policy = VerificationPolicy::new().require(KeyRole::Packages, 2);
+
+        // Signatures by the same key or by untrusted keys don't count.
+        let payload = prepare_payload(&[&key1, &key1, &untrusted], SAMPLE_DATA).await;
+        match payload.into_verified_with_policy(test_env.keychain(), &policy) {
+            Err(Error::NotEnoughSignatures {
+                role,
+                required,
+                signed_by,
+                missing,
+            }) => {
+                assert_eq!(KeyRole::Packages, role);
+                assert_eq!(2, required);
+                assert_eq!(vec![key1.public().calculate_id()], signed_by);
+                assert_eq!(vec![key2.public().calculate_id()], missing);
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+
+        // Other roles still need a single signature.
+        let policy = VerificationPolicy::new().require(KeyRole::Releases, 2);
+        let payload = prepare_payload(&[&key1], SAMPLE_DATA).await;
+        assert!(payload
+            .get_verified_with_policy(test_env.keychain(), &policy)
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_policy_with_cached_payload() {
+        let mut test_env = TestEnvironment::prepare().await;
+        let key = test_env.create_key(KeyRole::Packages).await;
+        let payload = prepare_payload(&[&key], SAMPLE_DATA).await;
+        assert!(payload.get_verified(test_env.keychain()).is_ok());
+
+        // The cached verification doesn't satisfy a stricter policy.
+        let policy = VerificationPolicy::new().require(KeyRole::Packages, 2);
+        assert!(matches!(
+            payload.get_verified_with_policy(test_env.keychain(), &policy),
+            Err(Error::NotEnoughSignatures { .. })
+        ));
+        assert!(matches!(
+            payload.into_verified_with_policy(test_env.keychain(), &policy),
+            Err(Error::NotEnoughSignatures { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_policy_requiring_hybrid_signatures() {
+        let mut test_env = TestEnvironment::prepare().await;
+        let classical = test_env.create_key(KeyRole::Packages).await;
+        let post_quantum = test_env
+            .create_key_with_algorithm(KeyRole::Packages, KeyAlgorithm::MlDsa65)
+            .await;
+        let policy = VerificationPolicy::new().require_hybrid(KeyRole::Packages);
+
+        let mut payload = SignedPayload::<TestData>::new(&TestData { answer: 42 }).unwrap();
+        payload
+            .add_hybrid_signature(&classical, &post_quantum)
+            .await
+            .unwrap();
+        assert_eq!(
+            42,
+            payload
+                .get_verified_with_policy(test_env.keychain(), &policy)
+                .unwrap()
+                .answer
+        );
+
+        // Without the policy, either signature is enough.
+        for key in [&classical, &post_quantum] {
+            let payload = prepare_payload(&[key], SAMPLE_DATA).await;
+            assert!(payload.get_verified(test_env.keychain()).is_ok());
+        }
+
+        let payload = prepare_payload(&[&classical], SAMPLE_DATA).await;
+        assert!(matches!(
+            payload.into_verified_with_policy(test_env.keychain(), &policy),
+            Err(Error::MissingHybridSignature {
+                role: KeyRole::Packages,
+                missing_post_quantum: true
+            })
+        ));
+        let payload = prepare_payload(&[&post_quantum], SAMPLE_DATA).await;
+        assert!(matches!(
+            payload.into_verified_with_policy(test_env.keychain(), &policy),
+            Err(Error::MissingHybridSignature {
+                role: KeyRole::Packages,
+                missing_post_quantum: false
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_signature_with_wrong_keys() {
+        let mut test_env = TestEnvironment::prepare().await;
+        let classical = test_env.create_key(KeyRole::Packages).await;
+        let post_quantum = test_env
+            .create_key_with_algorithm(KeyRole::Packages, KeyAlgorithm::MlDsa65)
+            .await;
+
+        let mut payload = SignedPayload::<TestData>::new(&TestData { answer: 42 }).unwrap();
+        for (first, second) in [
+            (&post_quantum, &classical),
+            (&classical, &classical),
+            (&post_quantum, &post_quantum),
+        ] {
+            assert!(matches!(
+                payload.add_hybrid_signature(first, second).await,
+                Err(Error::InvalidHybridKeys)
+            ));
+        }
+        assert!(payload.get_verified(test_env.keychain()).is_err());
+    }
+
+    // Timestamps
+
+    #[tokio::test]
+    async fn test_timestamp_before_expiry() {
+        let mut test_env = TestEnvironment::prepare().await;
+        let expired = test_env.create_key_with_expiry(KeyRole::Packages, -1).await;
+        let timestamps = test_env.create_key(KeyRole::Timestamps).await;
+
+        let mut payload = prepare_payload(&[&expired], SAMPLE_DATA).await;
+        payload
+            .add_timestamp_at(&timestamps, OffsetDateTime::now_utc() - Duration::hours(2))
+            .await
+            .unwrap();
+
+        // Timestamps are preserved when the payload is serialized.
+        let payload: SignedPayload<TestData> =
+            serde_json::from_str(&serde_json::to_string(&payload).unwrap()).unwrap();
+        assert_eq!(
+            42,
+            payload.get_verified(test_env.keychain()).unwrap().answer
+        );
+        assert_eq!(
+            42,
+            payload.into_verified(test_env.keychain()).unwrap().answer
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timestamp_after_expiry() {
+        let mut test_env = TestEnvironment::prepare().await;
+        let expired = test_env.create_key_with_expiry(KeyRole::Packages, -1).await;
+        let timestamps = test_env.create_key(KeyRole::Timestamps).await;
+
+        let mut payload = prepare_payload(&[&expired], SAMPLE_DATA).await;
+        payload.add_timestamp(&timestamps).await.unwrap();
+        assert!(matches!(
+            payload.get_verified(test_env.keychain()),
+            Err(Error::VerificationFailed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_timestamp_by_untrusted_keys() {
+        let mut test_env = TestEnvironment::prepare().await;
+        let expired = test_env.create_key_with_expiry(KeyRole::Packages, -1).await;
+        let untrusted = test_env.create_untrusted_key(KeyRole::Timestamps);
+        let wrong_role = test_env.create_key(KeyRole::Packages).await;
+
+        for key in [&untrusted, &wrong_role] {
+            let mut payload = prepare_payload(&[&expired], SAMPLE_DATA).await;
+            payload
+                .add_timestamp_at(key, OffsetDateTime::now_utc() - Duration::hours(2))
+                .await
+                .unwrap();
+            assert!(matches!(
+                payload.into_verified(test_env.keychain()),
+                Err(Error::VerificationFailed)
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timestamp_of_another_signature() {
+        let mut test_env = TestEnvironment::prepare().await;
+        let expired = test_env.create_key_with_expiry(KeyRole::Packages, -1).await;
+        let timestamps = test_env.create_key(KeyRole::Timestamps).await;
+
+        let mut other = prepare_payload(&[&expired], r#"{"answer":0}"#).await;
+        other
+            .add_timestamp_at(&timestamps, OffsetDateTime::now_utc() - Duration::hours(2))
+            .await
+            .unwrap();
+
+        let mut payload = prepare_payload(&[&expired], SAMPLE_DATA).await;
+        payload.signatures[0].timestamp = other.signatures[0].timestamp.take();
+        assert!(matches!(
+            payload.into_verified(test_env.keychain()),
+            Err(Error::VerificationFailed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_policy_requiring_timestamps() {
+        let mut test_env = TestEnvironment::prepare().await;
+        let key = test_env.create_key(KeyRole::Packages).await;
+        let timestamps = test_env.create_key(KeyRole::Timestamps).await;
+        let policy = VerificationPolicy::new().require_timestamp(KeyRole::Packages);
+
+        let mut payload = prepare_payload(&[&key], SAMPLE_DATA).await;
+        assert!(payload.get_verified(test_env.keychain()).is_ok());
+        assert!(matches!(
+            payload.get_verified_with_policy(test_env.keychain(), &policy),
+            Err(Error::MissingTimestamp {
+                role: KeyRole::Packages
+            })
+        ));
+
+        // Adding a timestamp discards the cached verification.
+        payload.add_timestamp(&timestamps).await.unwrap();
+        assert!(payload
+            .get_verified_with_policy(test_env.keychain(), &policy)
+            .is_ok());
+        assert!(payload
+            .into_verified_with_policy(test_env.keychain(), &policy)
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_revocation_info_expired_after_timestamp() {
+        // Keys are verified against the revocation info once loaded, so they all have to be part
+        // of the keys manifest.
+        let generate = |role| {
+            EphemeralKeyPair::generate(KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer, role, None)
+                .unwrap()
+        };
+        let root = generate(KeyRole::Root);
+        let mut keys = vec![];
+        let mut pairs = vec![];
+        for role in [KeyRole::Revocation, KeyRole::Packages, KeyRole::Timestamps] {
+            let pair = generate(role);
+            let mut key = SignedPayload::new(pair.public()).unwrap();
+            key.add_signature(&root).await.unwrap();
+            keys.push(key);
+            pairs.push(pair);
+        }
+        let mut revoked_signatures = SignedPayload::new(&RevocationInfo::new(
+            vec![crate::sha256::hash_sha256(r#"{"answer":0}"#.as_bytes())],
+            OffsetDateTime::now_utc() - Duration::days(1),
+        ))
+        .unwrap();
+        revoked_signatures.add_signature(&pairs[0]).await.unwrap();
+
+        let mut keychain = Keychain::new(root.public()).unwrap();
+        keychain
+            .load_all(&KeysManifest {
+                version: ManifestVersion,
+                keys,
+                revoked_signatures,
+                root_rotations: vec![],
+            })
+            .unwrap();
+        let (key, timestamps) = (&pairs[1], &pairs[2]);
+        let policy = VerificationPolicy::new().revocation_at_timestamp();
+
+        // Signed while the revocation information was still valid.
+        let mut payload = prepare_payload(&[key], SAMPLE_DATA).await;
+        payload
+            .add_timestamp_at(timestamps, OffsetDateTime::now_utc() - Duration::days(2))
+            .await
+            .unwrap();
+        assert!(matches!(
+            payload.get_verified(&keychain),
+            Err(Error::RevocationSignatureExpired(..))
+        ));
+        assert_eq!(
+            42,
+            payload
+                .get_verified_with_policy(&keychain, &policy)
+                .unwrap()
+                .answer
+        );
+        // The cached verification is checked against the policy it is used with.
+        assert!(matches!(
+            payload.get_verified(&keychain),
+            Err(Error::RevocationSignatureExpired(..))
+        ));
+        assert!(payload
+            .into_verified_with_policy(&keychain, &policy)
+            .is_ok());
+
+        // Revoked content is still rejected.
+        let mut revoked = prepare_payload(&[key], r#"{"answer":0}"#).await;
+        revoked
+            .add_timestamp_at(timestamps, OffsetDateTime::now_utc() - Duration::days(2))
+            .await
+            .unwrap();
+        assert!(matches!(
+            revoked.into_verified_with_policy(&keychain, &policy),
+            Err(Error::ContentRevoked(_))
+        ));
+
+        // Signed after the revocation information expired, or without a timestamp.
+        let mut late = prepare_payload(&[key], SAMPLE_DATA).await;
+        late.add_timestamp_at(timestamps, OffsetDateTime::now_utc() - Duration::hours(2))
+            .await
+            .unwrap();
+        assert!(matches!(
+            late.into_verified_with_policy(&keychain, &policy),
+            Err(Error::RevocationSignatureExpired(..))
+        ));
+        let untimestamped = prepare_payload(&[key], SAMPLE_DATA).await;
+        assert!(matches!(
+            untimestamped.into_verified_with_policy(&keychain, &policy),
+            Err(Error::RevocationSignatureExpired(..))
+        ));
+    }
+
     // Misc tests
 
     #[tokio::test]
@@ -360,7 +944,6 @@ mod tests {
             "#,
         ).unwrap();
 
-        #[cfg(feature = "hash-revocation")]
         let revoked_signatures = serde_json::from_str(r#"
         {"signatures":[{"key_sha256":"1LAfvhHLQ0bPmRSEgYcDfas2gr+7ZCSUT8MBjsksqnM=","signature":"MEUCICWz68Ry/cgEbp3hRl1zeEDB7cbAjghR4wRIbmsPZaSmAiEAu7HLBjdOjWMMaUWkj+Sm9saLy2eorY17eHY+PRQMXU0="}],"signed":"{\"revoked_content_sha256\":[],\"expires_at\":\"2025-08-05T00:00:00Z\"}"}
         "#).unwrap();
@@ -368,19 +951,31 @@ mod tests {
         let km = KeysManifest {
             version: ManifestVersion,
             keys: vec![revocation_key, packages_key],
-            #[cfg(feature = "hash-revocation")]
             revoked_signatures,
+            root_rotations: vec![],
         };
 
+        // Without revocation information, the payload is verified by the keys alone.
         let mut keychain = Keychain::new(&root_key).unwrap();
-        keychain.load_all(&km).unwrap();
+        for key in &km.keys {
+            keychain.load(key).unwrap();
+        }
         assert_eq!(42, payload.get_verified(&keychain).unwrap().answer);
+
+        // The revocation information of the fixture expired, so it is no longer trusted.
+        let mut keychain = Keychain::new(&root_key).unwrap();
+        keychain.load_all(&km).unwrap();
+        let payload: SignedPayload<TestData> =
+            serde_json::from_str(&serde_json::to_string(&payload).unwrap()).unwrap();
+        assert!(matches!(
+            payload.get_verified(&keychain),
+            Err(Error::RevocationSignatureExpired(..))
+        ));
     }
 
     // Revocation.
 
     #[tokio::test]
-    #[cfg(feature = "hash-revocation")]
     async fn test_verify_revocation_info() {
         let mut test_env = TestEnvironment::prepare().await;
         let key_revocation = test_env.create_key(KeyRole::Revocation).await;
@@ -406,7 +1001,6 @@ mod tests {
     }
 
     #[tokio::test]
-    #[cfg(feature = "hash-revocation")]
     async fn test_verify_revocation_info_incorrect_keyrole() {
         let mut test_env = TestEnvironment::prepare().await;
         let key_not_revocation_role = test_env.create_key(KeyRole::Packages).await;
@@ -428,7 +1022,6 @@ mod tests {
     }
 
     #[test]
-    #[cfg(feature = "hash-revocation")]
     fn test_verify_deserialized_with_revocation_info() {
         // We need to recreate and initialize the keys for each these tests separately because
         // for most part the content and datetime etc. are different. So, a new set of keys is
@@ -467,6 +1060,7 @@ mod tests {
             version: ManifestVersion,
             keys: vec![revocation_key, packages_key],
             revoked_signatures,
+            root_rotations: vec![],
         };
 
         assert!(keychain.load_all(&km).is_ok());
@@ -477,135 +1071,152 @@ mod tests {
             .is_empty());
     }
 
-    #[test]
-    #[cfg(feature = "hash-revocation")]
-    #[ignore = "Needs to be tested along with Install command"]
-    fn verify_revoked_payload() {
-        let mut keychain = Keychain::new(
-            &serde_json::from_str(
-            r#"{
-            "role":"root",
-            "algorithm":"ecdsa-p256-sha256-asn1-spki-der",
-            "expiry":null,
-            "public":"MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEsmIrrJH8LARwp79Qh6w9cEAFVS/QwDpbJwHQwyGC7LiAFvXpox2Whn2zgVKgs2ehLSCnNNdqDH6H+WTDfcU91Q=="}
-            "#
-            ).unwrap()).unwrap();
-
-        let revocation_key: SignedPayload<PublicKey> = serde_json::from_str(
-            r#"
-                {"signatures":[{"key_sha256":"0Hjy0uISLPXHhJygWpfT/subu3C07tvzuaV3xJNIoSU=",
-                "signature":"MEQCICEqWyDgJ81t5y9f7xiixTD//5s8/EuYG5laHR6O7rV3AiBx4zpBQmIbci6FXCcYJIBSXjCspJbKgAgeYRcToeSUvw=="}],
-                "signed":"{\"role\":\"revocation\",\"algorithm\":\"ecdsa-p256-sha256-asn1-spki-der\",\"expiry\":null,\"public\":\"MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEdPE2wdSb3dqGW/sFa0TYRAXe0hGKL1xTk9XZcrNtz4bfssW7QI8GXXAO/rlTm/n69obkPK8lin69QnUCOpAW5g==\"}"
-                }"#).unwrap();
-
-        let packages_key: SignedPayload<PublicKey> = serde_json::from_str(
-            r#"
-               {"signatures":[{"key_sha256":"0Hjy0uISLPXHhJygWpfT/subu3C07tvzuaV3xJNIoSU=",
-               "signature":"MEQCIGeVaDYN5ADdZ3PCsfBJ+f4GvdUN+nELsuVaJyNCx6Z/AiBWaeMTXVez3MEXg51KAgu9Z8uYX9P3VmsNxgzaDtu2Rg=="}],
-               "signed":"{\"role\":\"packages\",\"algorithm\":\"ecdsa-p256-sha256-asn1-spki-der\",\"expiry\":null,\"public\":\"MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEDuOHCcbc7DNhLpHBwZolEgX33VOf039pRi0FQH6rfS/0uSRawucX4LSKc6Dg4eim3SAbbtRTf+oSl0tTG3KUUg==\"}"}
-            "#).unwrap();
-
-        let revoked_signatures: SignedPayload<RevocationInfo>  = serde_json::from_str(
-            r#"
-              {"signatures":[{"key_sha256":"jpwiXafZnKIYVd50u9qlqp/X+KXuB/qtu0chxx3bO5w=",
-              "signature":"MEUCIQCQoHFae7QtfiSw0Okz+dQ4HOtR4Or0XutByRMySpdhwgIgNoQQpeEPmTK/2Vkg6xWP0oIBUF3PV88/RMIUSwLZATU="}],
-              "signed":"{\"revoked_content_sha256\":[[57,55,54,101,97,97,99,53,53,99,101,102,102,50,49,53,53,48,99,55,100,52,97,57,100,52,97,101,100,101,52,101,48,49,102,48,57,100,99,57,53,51,48,48,57,51,97,98,98,57,102,49,100,48,56,53,101,49,48,50,51,99,55,49]],\"expires_at\":\"2025-08-05T00:00:00Z\"}"
-              }"#).unwrap();
+    #[tokio::test]
+    async fn test_verify_ignores_failing_extra_signature() {
+        let mut test_env = TestEnvironment::prepare().await;
+        let key = test_env.create_key(KeyRole::Packages).await;
 
-        let km = KeysManifest {
-            version: ManifestVersion,
-            keys: vec![revocation_key, packages_key],
-            revoked_signatures,
-        };
+        // A trusted key that can't be parsed, so its signatures fail with an error other than
+        // `VerificationFailed`.
+        let mut malformed = test_env
+            .create_untrusted_key(KeyRole::Packages)
+            .public()
+            .clone();
+        malformed.public = PublicKeyBytes::owned(vec![1, 2, 3]);
+        test_env.sign_and_add_key(&malformed).await;
+        let malformed_signature = serde_json::json!({
+            "key_sha256": malformed.calculate_id(),
+            "signature": base64_encode(b"signature"),
+        });
 
-        assert!(keychain.load_all(&km).is_ok());
+        let mut payload =
+            serde_json::to_value(prepare_payload(&[&key], SAMPLE_DATA).await).unwrap();
+        payload["signatures"]
+            .as_array_mut()
+            .unwrap()
+            .push(malformed_signature.clone());
+        let get_payload: SignedPayload<TestData> = serde_json::from_value(payload.clone()).unwrap();
         assert_eq!(
-            keychain
-                .revocation_info()
+            42,
+            get_payload
+                .get_verified(test_env.keychain())
                 .unwrap()
-                .revoked_content_sha256
-                .len(),
-            1
+                .answer
+        );
+        let into_payload: SignedPayload<TestData> = serde_json::from_value(payload).unwrap();
+        assert_eq!(
+            42,
+            into_payload
+                .into_verified(test_env.keychain())
+                .unwrap()
+                .answer
         );
 
-        let s: SignedPayload<String> = serde_json::from_str(
-            r#"
-              {"signatures":[{"key_sha256":"UExDkEYvGWey+Cbllq3lu0gWZnj+k3yXmtKT10E8hUw=",
-              "signature":"MEQCIBhecxmblDtvC0LM0Kb/GEZszbUK14XHEVTKY3mKJ70hAiBkzqiQx++aCbUKEn3GWOqlu60BoZJo5JcrwAbGggAueg=="}],
-              "signed":"976eaac55ceff21550c7d4a9d4aede4e01f09dc9530093abb9f1d085e1023c71"}
-            "#
-        ).unwrap();
-
-        // Since the payload is in the revoked signatures, this verification will fail.
+        // Without a valid signature, the failure is reported.
+        let payload: SignedPayload<TestData> = serde_json::from_value(serde_json::json!({
+            "signatures": [malformed_signature],
+            "signed": SAMPLE_DATA,
+        }))
+        .unwrap();
         assert!(matches!(
-            s.get_verified(&keychain),
-            Err(Error::VerificationFailed)
+            payload.get_verified(test_env.keychain()),
+            Err(Error::InvalidKey(_))
         ));
     }
 
-    #[test]
-    #[cfg(feature = "hash-revocation")]
-    #[ignore = "Needs to be tested along with Install command"]
-    fn verify_revoked_payload_expired_hashes() {
-        let mut keychain = Keychain::new(
-            &serde_json::from_str(
-                r#"
-              {"role":"root",
-              "algorithm":"ecdsa-p256-sha256-asn1-spki-der",
-              "expiry":null,
-              "public":"MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEysuTQtxZPS8brgpNB9drJEVKAw/VKgMBNwj8Z9rgJu2gZvs3lhScO6PYLJF4RlYOeroVKJ5iTQAwvS5+f8fuPw=="}
-             "#).unwrap()).unwrap();
+    #[tokio::test]
+    async fn test_verify_revoked_payload() {
+        let revocation_info = RevocationInfo::new(
+            vec![crate::sha256::hash_sha256(SAMPLE_DATA.as_bytes())],
+            OffsetDateTime::now_utc() + EXPIRATION_EXTENSION_IN_DAYS,
+        );
+        let mut test_env = TestEnvironment::prepare_with_revocation_info(revocation_info).await;
+        let key = test_env.create_key(KeyRole::Packages).await;
 
-        let revocation_key: SignedPayload<PublicKey> = serde_json::from_str(
-            r#"
-                 {"signatures":[{"key_sha256":"kymyTeYBNiOW8JqBr3FBB96stFb07TdvWmKsYFaASqY=",
-                 "signature":"MEYCIQD4op6c7uAoYwENrInZ3+DlUYeCfIzhk3fPZjacSpEZqQIhANmADQcvEFdtSfsIY550Vsozmyk9q+DD8V5bN7VqVALi"}],
-                 "signed":"{\"role\":\"revocation\",\"algorithm\":\"ecdsa-p256-sha256-asn1-spki-der\",\"expiry\":null,\"public\":\"MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEqReu6kYhzYa6fI7LB14gG2yecR+jtXChwf1Z5wEHLasU6NDu7iE2eBWUeggOhIMnbKkR66F5B6F4KQIxdp9A2w==\"}"}
-        "#).unwrap();
+        let payload = prepare_payload(&[&key], SAMPLE_DATA).await;
+        assert!(matches!(
+            payload.get_verified(test_env.keychain()),
+            Err(Error::ContentRevoked(_))
+        ));
+        let payload = prepare_payload(&[&key], SAMPLE_DATA).await;
+        assert!(matches!(
+            payload.into_verified(test_env.keychain()),
+            Err(Error::ContentRevoked(_))
+        ));
 
-        let packages_key: SignedPayload<PublicKey> = serde_json::from_str(
-            r#"
-                {"signatures":[{"key_sha256":"kymyTeYBNiOW8JqBr3FBB96stFb07TdvWmKsYFaASqY=",
-                "signature":"MEYCIQDSqBcxonf8PhwWl1IrJoRmHJTmDj6kNO283vmpeXyxnwIhAOJckzfu/PQ1J3UjR3xVYwOM8ZUMK/jmPjLb9wmyPFNb"}],
-                "signed":"{\"role\":\"packages\",\"algorithm\":\"ecdsa-p256-sha256-asn1-spki-der\",\"expiry\":null,\"public\":\"MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEmZl6pB5HF0fc7hkOfnrP4WNhk+jFDxzXDUoawhRnpu+XrYrdgMTl1+wcobxk5rwSdAtarm63vPPkQJEV6LrTxA==\"}"}
-            "#
-        ).unwrap();
+        // Other payloads signed by the same key are still accepted.
+        let payload = prepare_payload(&[&key], r#"{"answer":43}"#).await;
+        assert_eq!(
+            43,
+            payload.get_verified(test_env.keychain()).unwrap().answer
+        );
+    }
 
-        let revoked_signatures: SignedPayload<RevocationInfo>  = serde_json::from_str(
-            r#"
-              {"signatures":[{"key_sha256":"jONxDp7vf+gLKbRwNhriqdZgKrNzKz66hyNTpMLDJNQ=",
-              "signature":"MEUCIQCvcU+4YVx2roWJ9Coq/OzUJJxOANLm2VSTyCeCOZptDwIgA7bZYU78oHQPISarXI6mI+BAU0ut3zqWjAh2/bpRejU="}],
-              "signed":"{\"revoked_content_sha256\":[],\"expires_at\":\"1999-12-31T00:00:00Z\"}"}
-            "#
-        ).unwrap();
+    #[tokio::test]
+    async fn test_verify_cached_payload_against_revocation_info() {
+        let revocation_info = RevocationInfo::new(
+            vec![crate::sha256::hash_sha256(SAMPLE_DATA.as_bytes())],
+            OffsetDateTime::now_utc() + EXPIRATION_EXTENSION_IN_DAYS,
+        );
+        let mut test_env = TestEnvironment::prepare_with_revocation_info(revocation_info).await;
+        let key = test_env.create_key(KeyRole::Packages).await;
 
-        let km = KeysManifest {
-            version: ManifestVersion,
-            keys: vec![revocation_key, packages_key],
-            revoked_signatures,
-        };
+        // Verified, and cached, before the revocation information was available.
+        let payload = prepare_payload(&[&key], SAMPLE_DATA).await;
+        assert_eq!(42, payload.get_verified(key.public()).unwrap().answer);
 
-        assert!(keychain.load_all(&km).is_ok());
-        assert_eq!(
-            keychain
-                .revocation_info()
-                .unwrap()
-                .revoked_content_sha256
-                .len(),
-            0
-        );
+        assert!(matches!(
+            payload.get_verified(test_env.keychain()),
+            Err(Error::ContentRevoked(_))
+        ));
+        assert!(matches!(
+            payload.into_verified(test_env.keychain()),
+            Err(Error::ContentRevoked(_))
+        ));
+    }
 
-        let s: SignedPayload<String> = serde_json::from_str(
-            r#"
-               {"signatures":[{"key_sha256":"+bdNiRBQ5inCKFRFsoLVFP1hGAdUs1RylZT/SSUQGvI=",
-               "signature":"MEQCID8V05t2bFC/GtUFit9jF17AlUqVchRWBFMhFuLjX0PuAiAlofxEfyIc9ZqB5fvmHk5NEP+vis4auT4429xqICv9Sw=="}],
-               "signed":"\"976eaac55ceff21550c7d4a9d4aede4e01f09dc9530093abb9f1d085e1023c71\""}
-            "#
-        ).unwrap();
+    #[tokio::test]
+    async fn test_verify_with_expired_revocation_info() {
+        // Keys are verified against the revocation info once loaded, so they all have to be part
+        // of the keys manifest.
+        let generate = |role| {
+            EphemeralKeyPair::generate(KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer, role, None)
+                .unwrap()
+        };
+        let root = generate(KeyRole::Root);
+        let mut keys = vec![];
+        let mut pairs = vec![];
+        for role in [KeyRole::Revocation, KeyRole::Packages] {
+            let pair = generate(role);
+            let mut key = SignedPayload::new(pair.public()).unwrap();
+            key.add_signature(&root).await.unwrap();
+            keys.push(key);
+            pairs.push(pair);
+        }
+        let mut revoked_signatures =
+            SignedPayload::new(&RevocationInfo::new(vec![], OffsetDateTime::now_utc())).unwrap();
+        revoked_signatures.add_signature(&pairs[0]).await.unwrap();
+
+        let mut keychain = Keychain::new(root.public()).unwrap();
+        keychain
+            .load_all(&KeysManifest {
+                version: ManifestVersion,
+                keys,
+                revoked_signatures,
+                root_rotations: vec![],
+            })
+            .unwrap();
 
-        // Since revocation info has a date that is long passed, the error is about expiration of signatures.
+        // Since revocation info has a date that is passed, the error is about expiration of
+        // signatures.
+        let payload = prepare_payload(&[&pairs[1]], SAMPLE_DATA).await;
+        assert!(matches!(
+            payload.get_verified(&keychain),
+            Err(Error::RevocationSignatureExpired(..))
+        ));
+        let payload = prepare_payload(&[&pairs[1]], SAMPLE_DATA).await;
         assert!(matches!(
-            s.get_verified(&keychain),
+            payload.into_verified(&keychain),
             Err(Error::RevocationSignatureExpired(..))
         ));
     }
@@ -0,0 +1,131 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::keys::KeyRole;
+use std::collections::{HashMap, HashSet};
+
+/// Number of distinct trusted keys that have to sign a payload, depending on the role of the keys
+/// verifying it, whether both a classical and a post-quantum key have to be among them, whether
+/// their signatures need a trusted timestamp, and as of when the revocation information has to be
+/// valid.
+///
+/// Without other requirements, a payload is accepted as soon as one trusted key with the right
+/// role signed it, which is also what [`SignedPayload::get_verified`] and
+/// [`SignedPayload::into_verified`] do.
+///
+/// [`SignedPayload::get_verified`]: crate::signatures::SignedPayload::get_verified
+/// [`SignedPayload::into_verified`]: crate::signatures::SignedPayload::into_verified
+#[derive(Debug, Clone, Default)]
+pub struct VerificationPolicy {
+    thresholds: HashMap<KeyRole, usize>,
+    hybrid: HashSet<KeyRole>,
+    timestamped: HashSet<KeyRole>,
+    revocation_at_timestamp: bool,
+}
+
+impl VerificationPolicy {
+    /// Create a policy requiring a single signature for every key role.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require payloads verified by `role` to be signed by at least `threshold` distinct keys.
+    ///
+    /// A payload always needs at least one signature, so a `threshold` of zero is treated as one.
+    pub fn require(mut self, role: KeyRole, threshold: usize) -> Self {
+        self.thresholds.insert(role, threshold.max(1));
+        self
+    }
+
+    /// Require payloads verified by `role` to be signed by both a classical and a post-quantum key,
+    /// once the keys with that role migrated to hybrid signatures. This is checked in addition to
+    /// the threshold.
+    pub fn require_hybrid(mut self, role: KeyRole) -> Self {
+        self.hybrid.insert(role);
+        self
+    }
+
+    /// Whether payloads verified by `role` have to be signed by both a classical and a
+    /// post-quantum key.
+    pub fn requires_hybrid(&self, role: KeyRole) -> bool {
+        self.hybrid.contains(&role)
+    }
+
+    /// Require at least one of the signatures of payloads verified by `role` to have a trusted
+    /// [`Timestamp`](crate::signatures::Timestamp), so that the payloads can still be verified
+    /// after the keys signing them expire.
+    pub fn require_timestamp(mut self, role: KeyRole) -> Self {
+        self.timestamped.insert(role);
+        self
+    }
+
+    /// Whether payloads verified by `role` need a signature with a trusted timestamp.
+    pub fn requires_timestamp(&self, role: KeyRole) -> bool {
+        self.timestamped.contains(&role)
+    }
+
+    /// Check the expiry of the revocation information as of the latest trusted timestamp of the
+    /// payload, rather than as of now. Payloads without a trusted timestamp still require the
+    /// revocation information to be unexpired.
+    ///
+    /// This allows re-auditing archived payloads offline, with the keys manifest of the time, long
+    /// after its revocation information expired. The downside is that revocations published after
+    /// that revocation information expired are not noticed, so this is only meant for audits
+    /// against a keys manifest that is known to be the latest one.
+    pub fn revocation_at_timestamp(mut self) -> Self {
+        self.revocation_at_timestamp = true;
+        self
+    }
+
+    /// Whether the revocation information is checked as of the trusted timestamp of the payload.
+    pub fn checks_revocation_at_timestamp(&self) -> bool {
+        self.revocation_at_timestamp
+    }
+
+    /// Number of distinct keys required to sign payloads verified by `role`.
+    pub fn threshold(&self, role: KeyRole) -> usize {
+        self.thresholds.get(&role).copied().unwrap_or(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thresholds() {
+        let policy = VerificationPolicy::new()
+            .require(KeyRole::Releases, 2)
+            .require(KeyRole::Packages, 0);
+
+        assert_eq!(2, policy.threshold(KeyRole::Releases));
+        assert_eq!(1, policy.threshold(KeyRole::Packages));
+        assert_eq!(1, policy.threshold(KeyRole::Redirects));
+    }
+
+    #[test]
+    fn test_hybrid() {
+        let policy = VerificationPolicy::new().require_hybrid(KeyRole::Releases);
+
+        assert!(policy.requires_hybrid(KeyRole::Releases));
+        assert!(!policy.requires_hybrid(KeyRole::Packages));
+        assert_eq!(1, policy.threshold(KeyRole::Releases));
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let policy = VerificationPolicy::new().require_timestamp(KeyRole::Packages);
+
+        assert!(policy.requires_timestamp(KeyRole::Packages));
+        assert!(!policy.requires_timestamp(KeyRole::Releases));
+        assert!(!policy.requires_hybrid(KeyRole::Packages));
+    }
+
+    #[test]
+    fn test_revocation_at_timestamp() {
+        assert!(!VerificationPolicy::new().checks_revocation_at_timestamp());
+        assert!(VerificationPolicy::new()
+            .revocation_at_timestamp()
+            .checks_revocation_at_timestamp());
+    }
+}
@@ -0,0 +1,54 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::keys::newtypes::SignatureBytes;
+use crate::keys::KeyRole;
+use crate::sha256::hash_sha256;
+use crate::signatures::{PublicKeysRepository, Signable, SignedPayload, VerificationPolicy};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Statement by a key with the [`Timestamps`](KeyRole::Timestamps) role that a signature was made
+/// at a given time, attached to the signature with [`SignedPayload::add_timestamp`].
+///
+/// A signature with a trusted timestamp is verified against the expiry of its key as of the time
+/// in the timestamp, so payloads signed before a key expired can still be verified afterwards.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Timestamp {
+    /// SHA-256 of the countersigned signature.
+    #[serde(with = "crate::serde_base64")]
+    pub signature_sha256: Vec<u8>,
+    /// When the signature was made, or at least when it was countersigned.
+    #[serde(with = "time::serde::rfc3339")]
+    pub signed_at: OffsetDateTime,
+}
+
+impl Timestamp {
+    pub(crate) fn new(signature: &SignatureBytes<'_>, signed_at: OffsetDateTime) -> Self {
+        Self {
+            signature_sha256: hash_sha256(signature.as_bytes()),
+            signed_at,
+        }
+    }
+}
+
+impl Signable for Timestamp {
+    const SIGNED_BY_ROLE: KeyRole = KeyRole::Timestamps;
+}
+
+/// Returns when `signature` was made, if `timestamp` is trusted and countersigns it.
+pub(super) fn verify_timestamp(
+    keys: &dyn PublicKeysRepository,
+    policy: &VerificationPolicy,
+    timestamp: &SignedPayload<Timestamp>,
+    signature: &SignatureBytes<'_>,
+) -> Option<OffsetDateTime> {
+    // The time in the timestamp is needed to check the revocation information as of that time
+    // (if the policy allows it), and is only trusted once the signatures are verified.
+    let claimed = timestamp.deserialize_unverified().ok()?.signed_at;
+    // The cache is bypassed, as the same timestamp might be verified with different keychains.
+    let timestamp = timestamp
+        .verify_uncached_signed_at(keys, policy, Some(claimed))
+        .ok()?;
+    (timestamp.signature_sha256 == hash_sha256(signature.as_bytes())).then_some(timestamp.signed_at)
+}
@@ -3,7 +3,6 @@
 
 use crate::keys::{EphemeralKeyPair, KeyAlgorithm, KeyPair, KeyRole, PublicKey};
 use crate::manifests::{KeysManifest, ManifestVersion};
-#[cfg(feature = "hash-revocation")]
 use crate::revocation_info::RevocationInfo;
 use crate::signatures::{Keychain, SignedPayload};
 use base64::Engine;
@@ -11,7 +10,6 @@ use time::{Duration, OffsetDateTime};
 
 const ALGORITHM: KeyAlgorithm = KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer;
 // Make sure there is enough number of days for expiration so tests don't need constant updates.
-#[cfg(feature = "hash-revocation")]
 const EXPIRATION_EXTENSION_IN_DAYS: Duration = Duration::days(180);
 
 pub(crate) struct TestEnvironment {
@@ -21,35 +19,29 @@ pub(crate) struct TestEnvironment {
 
 impl TestEnvironment {
     pub(crate) async fn prepare() -> Self {
-        let root = EphemeralKeyPair::generate(ALGORITHM, KeyRole::Root, None).unwrap();
-
-        #[cfg(feature = "hash-revocation")]
-        let (revoked_signatures, signed_revocation_key) = {
-            let revocation_key =
-                EphemeralKeyPair::generate(ALGORITHM, KeyRole::Revocation, None).unwrap();
-            let mut signed_revocation_key = SignedPayload::new(revocation_key.public()).unwrap();
-            signed_revocation_key.add_signature(&root).await.unwrap();
+        let expires_at = OffsetDateTime::now_utc() + EXPIRATION_EXTENSION_IN_DAYS;
+        Self::prepare_with_revocation_info(RevocationInfo::new(vec![], expires_at)).await
+    }
 
-            let expiration_datetime = OffsetDateTime::now_utc() + EXPIRATION_EXTENSION_IN_DAYS;
+    pub(crate) async fn prepare_with_revocation_info(revocation_info: RevocationInfo) -> Self {
+        let root = EphemeralKeyPair::generate(ALGORITHM, KeyRole::Root, None).unwrap();
 
-            let mut revoked_signatures =
-                SignedPayload::new(&RevocationInfo::new(vec![], expiration_datetime)).unwrap();
-            revoked_signatures
-                .add_signature(&revocation_key)
-                .await
-                .unwrap();
+        let revocation_key =
+            EphemeralKeyPair::generate(ALGORITHM, KeyRole::Revocation, None).unwrap();
+        let mut signed_revocation_key = SignedPayload::new(revocation_key.public()).unwrap();
+        signed_revocation_key.add_signature(&root).await.unwrap();
 
-            (revoked_signatures, signed_revocation_key)
-        };
+        let mut revoked_signatures = SignedPayload::new(&revocation_info).unwrap();
+        revoked_signatures
+            .add_signature(&revocation_key)
+            .await
+            .unwrap();
 
         let keys_manifest = KeysManifest {
             version: ManifestVersion,
-            #[cfg(not(feature = "hash-revocation"))]
-            keys: vec![],
-            #[cfg(feature = "hash-revocation")]
             keys: vec![signed_revocation_key],
-            #[cfg(feature = "hash-revocation")]
             revoked_signatures,
+            root_rotations: vec![],
         };
         let mut keychain = Keychain::new(root.public()).unwrap();
         keychain.load_all(&keys_manifest).unwrap();
@@ -70,6 +62,16 @@ impl TestEnvironment {
         key
     }
 
+    pub(crate) async fn create_key_with_algorithm(
+        &mut self,
+        role: KeyRole,
+        algorithm: KeyAlgorithm,
+    ) -> EphemeralKeyPair {
+        let key = EphemeralKeyPair::generate(algorithm, role, None).unwrap();
+        self.sign_and_add_key(key.public()).await;
+        key
+    }
+
     pub(crate) async fn create_key_with_expiry(
         &mut self,
         role: KeyRole,
@@ -81,7 +83,7 @@ impl TestEnvironment {
         key
     }
 
-    async fn sign_and_add_key(&mut self, key: &PublicKey) {
+    pub(crate) async fn sign_and_add_key(&mut self, key: &PublicKey) {
         let mut payload = SignedPayload::new(key).unwrap();
         payload.add_signature(&self.root).await.unwrap();
 
@@ -1,11 +1,14 @@
 // SPDX-FileCopyrightText: The Ferrocene Developers
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use crate::Context;
 use clap::{
     builder::{BoolishValueParser, TypedValueParser},
     ArgAction,
 };
-use criticalup_core::download_server_client::Connectivity;
+use criticalup_core::download_server_client::{Connectivity, DownloadServerClient};
+use criticalup_core::project_manifest::ProjectManifestProduct;
+use criticalup_core::state::State;
 
 #[derive(clap::Args, Debug)]
 pub(crate) struct Network {
@@ -16,3 +19,19 @@ pub(crate) struct Network {
     }))]
     pub connectivity: Connectivity,
 }
+
+/// Creates the client networked commands use to talk to the download server, with the settings of
+/// `product` if any, and warns if its authentication token is about to expire.
+pub(crate) async fn download_server_client(
+    ctx: &Context,
+    state: &State,
+    connectivity: Connectivity,
+    product: Option<&ProjectManifestProduct>,
+) -> DownloadServerClient {
+    let mut client = DownloadServerClient::new(&ctx.config, state, connectivity);
+    if let Some(product) = product {
+        client.set_product_settings(product);
+    }
+    client.warn_if_token_expires_soon().await;
+    client
+}
@@ -33,14 +33,37 @@ impl CommandExecute for Criticalup {
             CriticalupSubcommand::Archive(archive) => archive.execute(ctx).await,
             CriticalupSubcommand::Auth(auth) => auth.execute(ctx).await,
             CriticalupSubcommand::Clean(clean) => clean.execute(ctx).await,
+            CriticalupSubcommand::Component(component) => component.execute(ctx).await,
             CriticalupSubcommand::Doc(doc) => doc.execute(ctx).await,
+            CriticalupSubcommand::Doctor(doctor) => doctor.execute(ctx).await,
+            CriticalupSubcommand::Env(env) => env.execute(ctx).await,
+            CriticalupSubcommand::Ide(ide) => ide.execute(ctx).await,
             CriticalupSubcommand::Init(init) => init.execute(ctx).await,
             CriticalupSubcommand::Install(install) => install.execute(ctx).await,
+            CriticalupSubcommand::Keys(keys) => keys.execute(ctx).await,
             CriticalupSubcommand::Link(link) => link.execute(ctx).await,
             CriticalupSubcommand::Remove(remove) => remove.execute(ctx).await,
             CriticalupSubcommand::Run(run) => run.execute(ctx).await,
+            CriticalupSubcommand::Target(target) => target.execute(ctx).await,
             CriticalupSubcommand::Verify(verify) => verify.execute(ctx).await,
             CriticalupSubcommand::Which(which) => which.execute(ctx).await,
         }
     }
 }
+
+/// Whether `stream` is a terminal, which tests can override with `CRITICALUP_TEST_MOCK_TTY`.
+pub(crate) fn is_tty(ctx: &Context, stream: &dyn std::io::IsTerminal) -> bool {
+    if ctx.config.whitelabel.test_mode {
+        // If the environment variable is set, pay attention to it
+        if let Some(var) = std::env::var_os("CRITICALUP_TEST_MOCK_TTY") {
+            if var == "1" {
+                return true;
+            } else if var == "0" {
+                return false;
+            } else {
+                panic!("CRITICALUP_TEST_MOCK_TTY should only ever be 0 or 1, or unset");
+            }
+        }
+    }
+    stream.is_terminal()
+}
@@ -6,14 +6,14 @@ use std::os::unix::fs::MetadataExt;
 use std::{
     env::current_dir,
     fs::OpenOptions,
-    io::{stdout, Write},
+    io::{stdout, Read, Write},
     path::PathBuf,
 };
 
 use clap::Parser;
-use criticaltrust::{integrity::IntegrityVerifier, signatures::Keychain};
+use criticaltrust::{integrity::IntegrityVerifier, manifests::ReleaseArtifactFormat};
 use criticalup_core::{
-    download_server_client::DownloadServerClient, project_manifest::ProjectManifest, state::State,
+    download_server_client::Connectivity, project_manifest::ProjectManifest, state::State,
 };
 use tempfile::TempDir;
 use tokio::task::spawn_blocking;
@@ -21,7 +21,10 @@ use tracing::Span;
 use walkdir::WalkDir;
 
 use crate::{
-    cli::{connectivity::Network, CommandExecute},
+    cli::{
+        connectivity::{download_server_client, Network},
+        CommandExecute,
+    },
     errors::Error,
     Context,
 };
@@ -56,12 +59,16 @@ impl CommandExecute for Archive {
         span.record("project", tracing::field::display(project.display()));
 
         let state = State::load(&ctx.config).await?;
-        let client = DownloadServerClient::new(&ctx.config, &state, self.network.connectivity);
-        let keys = client.keys().await?;
-
         let project_manifest = ProjectManifest::load(&project)?;
 
-        archive(client, &keys, &project_manifest, self.out.as_ref()).await?;
+        archive(
+            ctx,
+            &state,
+            self.network.connectivity,
+            &project_manifest,
+            self.out.as_ref(),
+        )
+        .await?;
 
         Ok(())
     }
@@ -69,72 +76,84 @@ impl CommandExecute for Archive {
 
 #[tracing::instrument(level = "debug", skip_all, fields(product_path))]
 async fn archive(
-    client: DownloadServerClient,
-    keys: &Keychain,
+    ctx: &Context,
+    state: &State,
+    connectivity: Connectivity,
     project_manifest: &ProjectManifest,
     out: Option<&PathBuf>,
 ) -> Result<(), Error> {
-    // Path to installables we will include in the archive
-    // Note: Do not try to get clever and parallize the building of this, download
-    //       bandwidth is not generous for many people.
-    let mut installables = vec![];
+    // Build a sysroot of the installables in a tempdir.
+    let working_dir = TempDir::new()?;
 
-    // Collect a list of installables
     for product in project_manifest.products() {
         let product_name = product.name();
         let release = product.release();
+        let format = product.format().unwrap_or(DEFAULT_RELEASE_ARTIFACT_FORMAT);
+
+        let client = download_server_client(ctx, state, connectivity, Some(product)).await;
+        let keys = client.keys().await?;
 
+        // Path to installables we will include in the archive
+        // Note: Do not try to get clever and parallize the building of this, download
+        //       bandwidth is not generous for many people.
+        let mut installables = vec![];
+
+        // Collect a list of installables
         for package in product.packages() {
             let package_path = client
-                .package(
-                    product_name,
-                    release,
-                    package,
-                    DEFAULT_RELEASE_ARTIFACT_FORMAT,
-                )
+                .package(product_name, release, package, format)
                 .await?;
             installables.push(package_path);
         }
-    }
 
-    // Build a sysroot of the installables in a tempdir.
-    let working_dir = TempDir::new()?;
-    for installable in installables {
-        let working_path = working_dir.path().to_path_buf();
-        spawn_blocking(move || {
-            let decoder = xz2::read::XzDecoder::new(installable.as_slice());
-            let mut archive = tar::Archive::new(decoder);
-            archive.set_preserve_permissions(true);
-            archive.set_preserve_mtime(true);
-            archive.set_unpack_xattrs(true);
-
-            archive.unpack(working_path)
-        })
-        .await??;
-    }
+        for installable in installables {
+            let working_path = working_dir.path().to_path_buf();
+            spawn_blocking(move || {
+                let decoder: Box<dyn Read> = match format {
+                    ReleaseArtifactFormat::TarXz => {
+                        Box::new(xz2::read::XzDecoder::new(installable.as_slice()))
+                    }
+                    ReleaseArtifactFormat::TarZst => {
+                        Box::new(zstd::stream::read::Decoder::new(installable.as_slice())?)
+                    }
+                    ReleaseArtifactFormat::Unknown => {
+                        return Err(Error::UnsupportedArtifactFormat(format))
+                    }
+                };
+                let mut archive = tar::Archive::new(decoder);
+                archive.set_preserve_permissions(true);
+                archive.set_preserve_mtime(true);
+                archive.set_unpack_xattrs(true);
+
+                archive.unpack(working_path)?;
+                Ok(())
+            })
+            .await??;
+        }
 
-    // Run the verifier over the tempdir.
-    tracing::info!("Verifying toolchain...");
-    let mut integrity_verifier = IntegrityVerifier::new(keys);
-    for entry in WalkDir::new(&working_dir) {
-        let entry = entry?;
+        // Run the verifier over the tempdir.
+        tracing::info!("Verifying toolchain...");
+        let mut integrity_verifier = IntegrityVerifier::new(&keys);
+        for entry in WalkDir::new(&working_dir) {
+            let entry = entry?;
 
-        if entry.file_type().is_file() {
-            tracing::trace!("Adding {}", tracing::field::display(entry.path().display()));
+            if entry.file_type().is_file() {
+                tracing::trace!("Adding {}", tracing::field::display(entry.path().display()));
 
-            #[cfg(not(windows))]
-            let mode = entry.metadata()?.mode();
-            // Windows does not have the same concept of permissions, we just no-op mode.
-            #[cfg(windows)]
-            let mode = 0;
+                #[cfg(not(windows))]
+                let mode = entry.metadata()?.mode();
+                // Windows does not have the same concept of permissions, we just no-op mode.
+                #[cfg(windows)]
+                let mode = 0;
 
-            integrity_verifier.add(entry.path(), mode, &tokio::fs::read(&entry.path()).await?);
+                integrity_verifier.add(entry.path(), mode, &tokio::fs::read(&entry.path()).await?);
+            }
         }
+        integrity_verifier
+            .verify()
+            .map_err(Error::IntegrityErrorsWhileArchiving)?;
+        tracing::info!("Verified toolchain");
     }
-    integrity_verifier
-        .verify()
-        .map_err(Error::IntegrityErrorsWhileArchiving)?;
-    tracing::info!("Verified toolchain");
 
     // Wrap it up.
     let out_cloned = out.map(|v| v.to_path_buf());
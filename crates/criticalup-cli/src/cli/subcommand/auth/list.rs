@@ -0,0 +1,98 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::cli::CommandExecute;
+use crate::errors::{Error, LibError};
+use crate::Context;
+use clap::Parser;
+use criticalup_core::download_server_client::{Connectivity, DownloadServerClient};
+use criticalup_core::errors::DownloadServerError;
+use criticalup_core::state::State;
+
+const DEFAULT_PROFILE_LABEL: &str = "(default)";
+
+/// List the stored authentication tokens and the organizations they belong to
+#[derive(Debug, Parser)]
+pub(crate) struct AuthList;
+
+impl CommandExecute for AuthList {
+    #[tracing::instrument(level = "debug", skip_all)]
+    async fn execute(self, ctx: &Context) -> Result<(), Error> {
+        let state = State::load(&ctx.config).await?;
+        let download_server = DownloadServerClient::new(&ctx.config, &state, Connectivity::Online);
+
+        let mut profiles = Vec::new();
+        if let Some(token) = state.authentication_token().await {
+            profiles.push((DEFAULT_PROFILE_LABEL.to_string(), token));
+        }
+        for name in state.authentication_profile_names() {
+            if let Some(token) = state.authentication_profile(&name) {
+                profiles.push((name, token));
+            }
+        }
+
+        if profiles.is_empty() {
+            eprintln!("no authentication tokens stored");
+            eprintln!();
+            eprintln!("You can store a new authentication token by running:");
+            eprintln!();
+            eprintln!("    criticalup auth set [--profile <PROFILE>]");
+            eprintln!();
+            return Ok(());
+        }
+
+        let mut rows = vec![[
+            "PROFILE".to_string(),
+            "TOKEN NAME".to_string(),
+            "ORGANIZATION".to_string(),
+            "EXPIRES AT".to_string(),
+        ]];
+        for (profile, token) in profiles {
+            let row = match download_server.get_token_data(&token).await {
+                Ok(data) => [
+                    profile,
+                    data.name,
+                    data.organization_name,
+                    data.expires_at.unwrap_or_else(|| "none".into()),
+                ],
+                Err(LibError::DownloadServerError {
+                    kind: DownloadServerError::AuthenticationTokenExpired,
+                    ..
+                }) => [profile, "(expired token)".into(), "-".into(), "-".into()],
+                Err(LibError::DownloadServerError {
+                    kind: DownloadServerError::AuthenticationTokenRevoked,
+                    ..
+                }) => [profile, "(revoked token)".into(), "-".into(), "-".into()],
+                Err(LibError::DownloadServerError {
+                    kind:
+                        DownloadServerError::AuthenticationFailed
+                        | DownloadServerError::AuthenticationTokenMalformed,
+                    ..
+                })
+                | Err(LibError::InvalidAuthenicationToken) => {
+                    [profile, "(invalid token)".into(), "-".into(), "-".into()]
+                }
+                Err(err) => return Err(err.into()),
+            };
+            rows.push(row);
+        }
+
+        let mut widths = [0; 4];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+        for row in rows {
+            let line = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            println!("{}", line.trim_end());
+        }
+
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
 // SPDX-FileCopyrightText: The Ferrocene Developers
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+mod list;
 mod remove;
 mod set;
+mod status;
 
 use crate::cli::CommandExecute;
 use crate::errors::{Error, LibError};
@@ -11,13 +13,17 @@ use clap::{Parser, Subcommand};
 use criticalup_core::download_server_client::{Connectivity, DownloadServerClient};
 use criticalup_core::errors::DownloadServerError;
 use criticalup_core::state::State;
+use list::AuthList;
 use remove::AuthRemove;
 use set::AuthSet;
+use status::AuthStatus;
 
 #[derive(Subcommand, Debug)]
 pub(crate) enum AuthSubcommand {
     Set(AuthSet),
     Remove(AuthRemove),
+    List(AuthList),
+    Status(AuthStatus),
 }
 
 /// Show and change authentication with the download server
@@ -31,8 +37,10 @@ impl CommandExecute for Auth {
     #[tracing::instrument(level = "debug", skip_all)]
     async fn execute(self, ctx: &Context) -> Result<(), Error> {
         match self.command {
+            Some(AuthSubcommand::List(list)) => return list.execute(ctx).await,
             Some(AuthSubcommand::Remove(remove)) => return remove.execute(ctx).await,
             Some(AuthSubcommand::Set(set)) => return set.execute(ctx).await,
+            Some(AuthSubcommand::Status(status)) => return status.execute(ctx).await,
             None => (),
         };
 
@@ -52,21 +60,41 @@ impl CommandExecute for Auth {
 
                 Ok(())
             }
-            Err(LibError::DownloadServerError {
-                kind: DownloadServerError::AuthenticationFailed,
-                ..
-            }) => {
-                eprintln!("error: failed to authenticate with the download server");
-                eprintln!();
-                eprintln!("The authentication token could be missing, invalid or expired.");
-                eprintln!("You can set a new authentication token by running:");
-                eprintln!();
-                eprintln!("    criticalup auth set");
-                eprintln!();
+            Err(LibError::DownloadServerError { url, kind }) => match token_rejection(&kind) {
+                Some(reason) => {
+                    eprintln!("error: failed to authenticate with the download server");
+                    eprintln!();
+                    eprintln!("{reason}");
+                    eprintln!("You can set a new authentication token by running:");
+                    eprintln!();
+                    eprintln!("    criticalup auth set");
+                    eprintln!();
 
-                Err(Error::Exit(1))
-            }
+                    Err(Error::Exit(1))
+                }
+                None => Err(LibError::DownloadServerError { url, kind }.into()),
+            },
             Err(err) => Err(err.into()),
         }
     }
 }
+
+/// Explains why the download server rejected the authentication token, or returns `None` if the
+/// error was not caused by the token.
+fn token_rejection(kind: &DownloadServerError) -> Option<&'static str> {
+    match kind {
+        DownloadServerError::AuthenticationFailed => {
+            Some("The authentication token could be missing, invalid or expired.")
+        }
+        DownloadServerError::AuthenticationTokenExpired => {
+            Some("The authentication token has expired.")
+        }
+        DownloadServerError::AuthenticationTokenRevoked => {
+            Some("The authentication token has been revoked.")
+        }
+        DownloadServerError::AuthenticationTokenMalformed => {
+            Some("The authentication token is malformed.")
+        }
+        _ => None,
+    }
+}
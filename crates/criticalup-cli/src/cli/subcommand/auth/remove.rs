@@ -9,16 +9,30 @@ use criticalup_core::state::State;
 
 /// Remove the authentication token used to interact with the download server
 #[derive(Debug, Parser)]
-pub(crate) struct AuthRemove;
+pub(crate) struct AuthRemove {
+    /// Remove the token stored in the named profile instead of the default token
+    #[arg(long)]
+    profile: Option<String>,
+}
 
 impl CommandExecute for AuthRemove {
     #[tracing::instrument(level = "debug", skip_all)]
     async fn execute(self, ctx: &Context) -> Result<(), Error> {
         let state = State::load(&ctx.config).await?;
 
-        if state.authentication_token().await.is_some() {
-            state.set_authentication_token(None);
-            state.persist().await?;
+        match &self.profile {
+            Some(profile) => {
+                if state.authentication_profile(profile).is_some() {
+                    state.set_authentication_profile(profile, None);
+                    state.persist().await?;
+                }
+            }
+            None => {
+                if state.authentication_token().await.is_some() {
+                    state.set_authentication_token(None);
+                    state.persist().await?;
+                }
+            }
         }
 
         Ok(())
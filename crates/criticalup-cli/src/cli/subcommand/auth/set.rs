@@ -4,11 +4,13 @@
 use std::io;
 use std::io::Write;
 
-use crate::cli::CommandExecute;
+use crate::cli::{is_tty, CommandExecute};
 use crate::errors::{Error, LibError};
 use crate::Context;
 use clap::Parser;
-use criticalup_core::download_server_client::{Connectivity, DownloadServerClient};
+use criticalup_core::download_server_client::{
+    token_expiry_warning_days, Connectivity, DownloadServerClient,
+};
 use criticalup_core::errors::DownloadServerError;
 use criticalup_core::state::{AuthenticationToken, State};
 
@@ -17,6 +19,9 @@ use criticalup_core::state::{AuthenticationToken, State};
 pub(crate) struct AuthSet {
     /// Authentication token to use; if not provided, it will be read from stdin
     token: Option<String>,
+    /// Store the token in the named profile instead of as the default token
+    #[arg(long)]
+    profile: Option<String>,
 }
 
 impl CommandExecute for AuthSet {
@@ -34,17 +39,38 @@ impl CommandExecute for AuthSet {
             token_from_stdin_programmatic().map_err(Error::CantReadTokenFromStdin)?
         };
 
-        state.set_authentication_token(Some(AuthenticationToken::seal(&token)));
+        let token = AuthenticationToken::seal(&token);
+        match &self.profile {
+            Some(profile) => state.set_authentication_profile(profile, Some(token.clone())),
+            None => state.set_authentication_token(Some(token.clone())),
+        }
 
-        match download_server.get_current_token_data().await {
-            Ok(_) => Ok(state.persist().await?),
+        // The new token is validated directly, as the client would otherwise prefer the token in
+        // the `CRITICALUP_TOKEN` environment variable.
+        match download_server.get_token_data(&token).await {
+            Ok(data) => {
+                state.persist().await?;
+                data.warn_if_expires_soon(token_expiry_warning_days());
+                Ok(())
+            }
+            Err(err) => Err(rejected_token_error(&err).unwrap_or_else(|| err.into())),
+        }
+    }
+}
 
-            Err(LibError::DownloadServerError {
-                kind: DownloadServerError::AuthenticationFailed,
-                ..
-            }) => Err(Error::InvalidAuthenticationToken),
-            Err(err) => Err(err.into()),
+/// Converts the errors caused by the download server rejecting the token into a dedicated error.
+fn rejected_token_error(err: &LibError) -> Option<Error> {
+    let LibError::DownloadServerError { kind, .. } = err else {
+        return None;
+    };
+    match kind {
+        DownloadServerError::AuthenticationFailed => Some(Error::InvalidAuthenticationToken),
+        DownloadServerError::AuthenticationTokenExpired => Some(Error::ExpiredAuthenticationToken),
+        DownloadServerError::AuthenticationTokenRevoked => Some(Error::RevokedAuthenticationToken),
+        DownloadServerError::AuthenticationTokenMalformed => {
+            Some(Error::MalformedAuthenticationToken)
         }
+        _ => None,
     }
 }
 
@@ -90,19 +116,3 @@ fn token_from_stdin_programmatic() -> Result<String, std::io::Error> {
 
     Ok(token)
 }
-
-fn is_tty(ctx: &Context, stream: &dyn std::io::IsTerminal) -> bool {
-    if ctx.config.whitelabel.test_mode {
-        // If the environment variable is set, pay attention to it
-        if let Some(var) = std::env::var_os("CRITICALUP_TEST_MOCK_TTY") {
-            if var == "1" {
-                return true;
-            } else if var == "0" {
-                return false;
-            } else {
-                panic!("CRITICALUP_TEST_MOCK_TTY should only ever be 0 or 1, or unset");
-            }
-        }
-    }
-    stream.is_terminal()
-}
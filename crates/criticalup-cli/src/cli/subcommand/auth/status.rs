@@ -0,0 +1,108 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::cli::CommandExecute;
+use crate::errors::{Error, LibError};
+use crate::Context;
+use clap::Parser;
+use criticalup_core::download_server_client::{
+    token_expiry_warning_days, Connectivity, DownloadServerClient, TokenExpiryStatus,
+};
+use criticalup_core::envvars::EnvVars;
+use criticalup_core::errors::DownloadServerError;
+use criticalup_core::state::State;
+use time::OffsetDateTime;
+
+// Exit codes of `criticalup auth status`, documented in the user manual. Any other failure (for
+// example a network error) exits with 1, like every other command.
+const EXIT_NO_TOKEN: i32 = 2;
+const EXIT_TOKEN_REJECTED: i32 = 3;
+const EXIT_TOKEN_EXPIRED: i32 = 4;
+const EXIT_TOKEN_EXPIRES_SOON: i32 = 5;
+
+/// Check whether the authentication token is valid, with exit codes suitable for CI
+#[derive(Debug, Parser)]
+pub(crate) struct AuthStatus {
+    /// Check the token stored in the named profile instead of the default token
+    #[arg(long)]
+    profile: Option<String>,
+    /// Number of days before expiry when the token is reported as expiring soon
+    #[arg(long, value_name = "DAYS")]
+    warn_days: Option<u32>,
+}
+
+impl CommandExecute for AuthStatus {
+    #[tracing::instrument(level = "debug", skip_all)]
+    async fn execute(self, ctx: &Context) -> Result<(), Error> {
+        let state = State::load(&ctx.config).await?;
+
+        // The token of a named profile is checked even when the `CRITICALUP_TOKEN` environment
+        // variable is set, as the variable would otherwise take precedence.
+        let profile_token = self
+            .profile
+            .as_deref()
+            .map(|profile| state.authentication_profile(profile));
+        let token_configured = match &profile_token {
+            Some(token) => token.is_some(),
+            None => {
+                EnvVars::new().criticalup_token.is_some()
+                    || state.authentication_token().await.is_some()
+            }
+        };
+        if !token_configured {
+            eprintln!("status:             missing");
+            return Err(Error::Exit(EXIT_NO_TOKEN));
+        }
+
+        let download_server = DownloadServerClient::new(&ctx.config, &state, Connectivity::Online);
+        let data = match profile_token.flatten() {
+            Some(token) => download_server.get_token_data(&token).await,
+            None => download_server.get_current_token_data().await,
+        };
+        let data = match data {
+            Ok(data) => data,
+            Err(LibError::InvalidAuthenicationToken) => {
+                eprintln!("status:             malformed");
+                return Err(Error::Exit(EXIT_TOKEN_REJECTED));
+            }
+            Err(LibError::DownloadServerError { url, kind }) => {
+                let (status, code) = match kind {
+                    DownloadServerError::AuthenticationTokenExpired => {
+                        ("expired", EXIT_TOKEN_EXPIRED)
+                    }
+                    DownloadServerError::AuthenticationTokenRevoked => {
+                        ("revoked", EXIT_TOKEN_REJECTED)
+                    }
+                    DownloadServerError::AuthenticationTokenMalformed => {
+                        ("malformed", EXIT_TOKEN_REJECTED)
+                    }
+                    DownloadServerError::AuthenticationFailed => ("invalid", EXIT_TOKEN_REJECTED),
+                    kind => return Err(LibError::DownloadServerError { url, kind }.into()),
+                };
+                eprintln!("status:             {status}");
+                return Err(Error::Exit(code));
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let warn_days = self.warn_days.unwrap_or_else(token_expiry_warning_days);
+        let (status, code) = match data.expiry_status(warn_days, OffsetDateTime::now_utc()) {
+            TokenExpiryStatus::NoExpiry | TokenExpiryStatus::Valid(_) => ("valid", 0),
+            TokenExpiryStatus::ExpiresSoon(_) => ("expires soon", EXIT_TOKEN_EXPIRES_SOON),
+            TokenExpiryStatus::Expired(_) => ("expired", EXIT_TOKEN_EXPIRED),
+        };
+
+        eprintln!("status:             {status}");
+        eprintln!("token name:         {}", data.name);
+        eprintln!("organization name:  {}", data.organization_name);
+        eprintln!(
+            "expires at:         {}",
+            data.expires_at.as_deref().unwrap_or("none")
+        );
+
+        match code {
+            0 => Ok(()),
+            code => Err(Error::Exit(code)),
+        }
+    }
+}
@@ -2,48 +2,263 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use clap::Parser;
+use std::collections::{HashMap, HashSet};
+#[cfg(not(windows))]
+use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tokio::fs;
+use walkdir::WalkDir;
 
 use criticalup_core::binary_proxies;
-use criticalup_core::project_manifest::InstallationId;
-use criticalup_core::state::State;
+use criticalup_core::cache::{
+    keys_cache_path, package_cache_path, product_release_manifest_cache_path,
+    root_rotations_cache_path, server_cache_dir,
+};
+use criticalup_core::config::Config;
+use criticalup_core::object_store::ObjectStore;
+use criticalup_core::project_manifest::{InstallationId, ProjectManifest};
+use criticalup_core::state::{StaleManifestReason, State};
 
+use crate::cli::subcommand::install::DEFAULT_RELEASE_ARTIFACT_FORMAT;
+use crate::cli::subcommand::link::unlink_deleted_installations;
 use crate::cli::CommandExecute;
 use crate::errors::Error;
 use crate::Context;
 
-/// Delete cache and unused installations
+/// Delete unused cache and installations
 #[derive(Debug, Parser)]
-pub(crate) struct Clean;
+pub(crate) struct Clean {
+    /// List what would be deleted and how much space it would free, without deleting anything
+    #[arg(long)]
+    dry_run: bool,
+    /// Also delete cached artifacts downloaded more than DAYS days ago
+    #[arg(long, value_name = "DAYS")]
+    max_cache_age: Option<u64>,
+    /// Delete the oldest cached artifacts until the cache fits in SIZE (for example `500M` or `2G`)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    max_cache_size: Option<u64>,
+}
 
 impl CommandExecute for Clean {
     #[tracing::instrument(level = "debug", skip_all)]
     async fn execute(self, ctx: &Context) -> Result<(), Error> {
         let installations_dir = &ctx.config.paths.installation_dir;
         let state = State::load(&ctx.config).await?;
+        let mut freed = FreedSpace::default();
+
+        prune_stale_manifests(&state, self.dry_run);
+        if !self.dry_run {
+            state.persist().await?;
+        }
+        let removed =
+            delete_unused_installations(installations_dir, &state, self.dry_run, &mut freed)
+                .await?;
+        if !self.dry_run {
+            // Deletes unused binary proxies after state cleanup.
+            binary_proxies::update(&ctx.config, &state, &std::env::current_exe()?).await?;
+            unlink_deleted_installations(ctx, &state, &removed).await;
+        }
+        delete_untracked_installation_dirs(installations_dir, &state, self.dry_run, &mut freed)
+            .await?;
+        delete_unused_objects(&ObjectStore::new(&ctx.config), self.dry_run, &mut freed)?;
+        self.delete_cache_artifacts(&ctx.config, &state, &removed, &mut freed)?;
 
-        delete_cache_directory(&ctx.config.paths.cache_dir).await?;
-        delete_unused_installations(installations_dir, &state).await?;
-        // Deletes unused binary proxies after state cleanup.
-        binary_proxies::update(&ctx.config, &state, &std::env::current_exe()?).await?;
-        delete_untracked_installation_dirs(installations_dir, state).await?;
+        if self.dry_run {
+            tracing::info!("Cleaning would free {}", format_size(freed.bytes()));
+        } else if freed.bytes() > 0 {
+            tracing::info!("Freed {}", format_size(freed.bytes()));
+        }
 
         Ok(())
     }
 }
 
-async fn delete_cache_directory(cache_dir: &Path) -> Result<(), Error> {
-    if cache_dir.exists() {
-        tracing::info!("Cleaning cache directory");
-        tokio::fs::remove_dir_all(&cache_dir).await?;
+impl Clean {
+    /// Deletes the cached artifacts not needed by any remaining installation, then applies the
+    /// retention policies to the ones that are. The cached keys and root rotations are always kept.
+    fn delete_cache_artifacts(
+        &self,
+        config: &Config,
+        state: &State,
+        removed: &[InstallationId],
+        freed: &mut FreedSpace,
+    ) -> Result<(), Error> {
+        let cache_dir = &config.paths.cache_dir;
+        if !cache_dir.exists() {
+            return Ok(());
+        }
+        let referenced = referenced_cache_artifacts(config, state, removed);
+        // The trusted keys and root rotations of every download server are kept.
+        let is_trust_file = |path: &Path| {
+            path.parent().is_some_and(|server_dir| {
+                server_dir.parent() == Some(&cache_dir.join("servers"))
+                    && (path == keys_cache_path(server_dir)
+                        || path == root_rotations_cache_path(server_dir))
+            })
+        };
+
+        let mut to_delete = Vec::new();
+        let mut kept = Vec::new();
+        let mut kept_size = 0;
+        for entry in WalkDir::new(cache_dir) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            let path = entry.into_path();
+
+            if is_trust_file(&path) {
+                kept_size += metadata.len();
+            } else if !referenced.contains(&path) {
+                tracing::debug!("Cached artifact {} is not used", path.display());
+                to_delete.push(path);
+            } else if self.max_cache_age.is_some_and(|days| {
+                let max_age = Duration::from_secs(days * 24 * 60 * 60);
+                metadata
+                    .modified()
+                    .ok()
+                    .and_then(|modified| modified.elapsed().ok())
+                    .is_some_and(|age| age > max_age)
+            }) {
+                tracing::debug!("Cached artifact {} is too old", path.display());
+                to_delete.push(path);
+            } else {
+                kept_size += metadata.len();
+                kept.push((metadata.modified()?, metadata.len(), path));
+            }
+        }
+
+        if let Some(budget) = self.max_cache_size {
+            // Evict the artifacts downloaded first, until the rest fits in the budget.
+            kept.sort();
+            for (_, size, path) in kept {
+                if kept_size <= budget {
+                    break;
+                }
+                tracing::debug!("Cached artifact {} exceeds the size budget", path.display());
+                kept_size -= size;
+                to_delete.push(path);
+            }
+        }
+
+        for path in to_delete {
+            freed.add(&path)?;
+            if self.dry_run {
+                tracing::info!("Would delete cached artifact {}", path.display());
+            } else {
+                tracing::info!("Deleting cached artifact {}", path.display());
+                std::fs::remove_file(&path)
+                    .map_err(|err| Error::DeletingCachedArtifact { path, kind: err })?;
+            }
+        }
+
+        if !self.dry_run {
+            // Remove the directories left empty, failing to remove the others is expected.
+            for entry in WalkDir::new(cache_dir).min_depth(1).contents_first(true) {
+                let entry = entry?;
+                if entry.file_type().is_dir() {
+                    let _ = std::fs::remove_dir(entry.path());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Cached artifacts needed to reinstall the products of the installations that are kept, as
+/// pinned by the project manifests using them. There are no lockfiles to consider, as project
+/// manifests pin exact releases.
+fn referenced_cache_artifacts(
+    config: &Config,
+    state: &State,
+    removed: &[InstallationId],
+) -> HashSet<PathBuf> {
+    let cache_dir = &config.paths.cache_dir;
+    let mut referenced = HashSet::new();
+
+    for (installation_id, installation) in state.installations().iter() {
+        if removed.contains(installation_id) {
+            continue;
+        }
+        for manifest_path in installation.manifests() {
+            if !manifest_path.exists() {
+                continue;
+            }
+            let manifest = match ProjectManifest::load(manifest_path) {
+                Ok(manifest) => manifest,
+                Err(err) => {
+                    tracing::warn!(
+                        "Could not load project manifest {}, its cached artifacts are not kept: {err}",
+                        manifest_path.display()
+                    );
+                    continue;
+                }
+            };
+
+            for product in manifest.products() {
+                if product.installation_id() != *installation_id {
+                    continue;
+                }
+                let format = product.format().unwrap_or(DEFAULT_RELEASE_ARTIFACT_FORMAT);
+                let server_dir = server_cache_dir(
+                    cache_dir,
+                    product
+                        .download_server()
+                        .unwrap_or(&config.whitelabel.download_server_url),
+                );
+                referenced.insert(product_release_manifest_cache_path(
+                    &server_dir,
+                    product.name(),
+                    product.release(),
+                ));
+                for package in product.packages() {
+                    referenced.insert(package_cache_path(
+                        &server_dir,
+                        product.name(),
+                        product.release(),
+                        package,
+                        format,
+                    ));
+                }
+            }
+        }
+    }
+
+    referenced
+}
+
+/// Removes the stale project manifests from the state, reporting them. The installations no project
+/// uses anymore because of it are then deleted along with the other unused installations.
+fn prune_stale_manifests(state: &State, dry_run: bool) {
+    let stale = state.prune_stale_manifests();
+    if stale.is_empty() {
+        tracing::debug!("No stale project manifests found");
+    }
+    let action = if dry_run { "Would remove" } else { "Removing" };
+    for stale in &stale {
+        let reason = match stale.reason {
+            StaleManifestReason::Missing => "it no longer exists",
+            StaleManifestReason::Changed => "it no longer matches the installation",
+        };
+        tracing::info!(
+            "{action} project manifest {} from installation {}, as {reason}",
+            stale.manifest.display(),
+            stale.installation.0
+        );
     }
-    Ok(())
 }
 
 /// Deletes installation from `State` with `InstallationId`s that have empty manifest section, and
-/// deletes the installation directory from the disk if present.
-async fn delete_unused_installations(installations_dir: &Path, state: &State) -> Result<(), Error> {
+/// deletes the installation directory from the disk if present. Returns the deleted installations.
+async fn delete_unused_installations(
+    installations_dir: &Path,
+    state: &State,
+    dry_run: bool,
+    freed: &mut FreedSpace,
+) -> Result<Vec<InstallationId>, Error> {
     // We need to list all the available installations on the disk first, so we can check which
     // installations in state file are absent from the disk.
     let mut all_installations_on_disk: Vec<InstallationId> = Vec::new();
@@ -66,20 +281,28 @@ async fn delete_unused_installations(installations_dir: &Path, state: &State) ->
 
     if unused_installations.is_empty() {
         tracing::info!("No unused installations found");
-        return Ok(());
+        return Ok(unused_installations);
     }
 
-    for installation in unused_installations {
+    for installation in &unused_installations {
+        let installation_dir_to_delete = installations_dir.join(&installation.0);
+        if installation_dir_to_delete.exists() {
+            freed.add(&installation_dir_to_delete)?;
+        }
+
+        if dry_run {
+            tracing::info!("Would delete unused installation {}", installation.0);
+            continue;
+        }
         tracing::info!("Deleting unused installation {}", installation.0);
 
         // Remove installation from the state.
-        state.remove_installation(&installation);
+        state.remove_installation(installation);
         // The state will be saved onto the disk but the removal of the installation directory
         // will be done after this which may not exist.
         state.persist().await?;
 
         // Remove installation directory from physical location.
-        let installation_dir_to_delete = installations_dir.join(&installation.0);
         if installation_dir_to_delete.exists() {
             tracing::debug!(
                 "Deleting unused installation directory {}",
@@ -93,13 +316,15 @@ async fn delete_unused_installations(installations_dir: &Path, state: &State) ->
                 })?;
         }
     }
-    Ok(())
+    Ok(unused_installations)
 }
 
 /// Deletes the installation directories from the disk that do not exist in the State.
 async fn delete_untracked_installation_dirs(
     installations_dir: &PathBuf,
-    state: State,
+    state: &State,
+    dry_run: bool,
+    freed: &mut FreedSpace,
 ) -> Result<(), Error> {
     let installations_in_state = state.installations().clone();
     let mut are_untracked_installation_dirs_present = false;
@@ -111,6 +336,15 @@ async fn delete_untracked_installation_dirs(
             if let Some(name) = installation_dir_name.to_str() {
                 if !installations_in_state.contains_key(&InstallationId(name.into())) {
                     are_untracked_installation_dirs_present = true;
+                    freed.add(&item.path())?;
+
+                    if dry_run {
+                        tracing::info!(
+                            "Would delete untracked installation directory {}",
+                            item.path().display()
+                        );
+                        continue;
+                    }
                     tracing::info!(
                         "Deleting untracked installation directory {}",
                         item.path().to_path_buf().display()
@@ -133,3 +367,156 @@ async fn delete_untracked_installation_dirs(
 
     Ok(())
 }
+
+/// Deletes the objects of the object store no installation links to anymore.
+#[cfg(not(windows))]
+fn delete_unused_objects(
+    object_store: &ObjectStore,
+    dry_run: bool,
+    freed: &mut FreedSpace,
+) -> Result<(), Error> {
+    for object in object_store.objects()? {
+        let metadata = std::fs::symlink_metadata(&object)?;
+        // During a dry run nothing was deleted yet, so the links from the installations that
+        // would be deleted still have to be discounted.
+        let mut links = metadata.nlink();
+        if dry_run {
+            links -= freed.seen_links(&metadata);
+        }
+        if links > 1 {
+            continue;
+        }
+
+        freed.add(&object)?;
+        if dry_run {
+            tracing::debug!("Would delete unused object {}", object.display());
+        } else {
+            tracing::debug!("Deleting unused object {}", object.display());
+            std::fs::remove_file(&object).map_err(|err| Error::DeletingUnusedObject {
+                path: object,
+                kind: err,
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Link counts are not available on Windows, so the objects are kept.
+#[cfg(windows)]
+fn delete_unused_objects(_: &ObjectStore, _: bool, _: &mut FreedSpace) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Space freed by deleting files, where hardlinked files only count once all their links are
+/// deleted.
+#[derive(Default)]
+struct FreedSpace {
+    bytes: u64,
+    /// Size, link count and number of links seen for each hardlinked file.
+    linked: HashMap<(u64, u64), (u64, u64, u64)>,
+}
+
+impl FreedSpace {
+    fn add(&mut self, path: &Path) -> Result<(), Error> {
+        for entry in WalkDir::new(path) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+
+            #[cfg(not(windows))]
+            if metadata.nlink() > 1 {
+                let linked = self
+                    .linked
+                    .entry((metadata.dev(), metadata.ino()))
+                    .or_insert((metadata.len(), metadata.nlink(), 0));
+                linked.2 += 1;
+                continue;
+            }
+            self.bytes += metadata.len();
+        }
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    fn seen_links(&self, metadata: &std::fs::Metadata) -> u64 {
+        self.linked
+            .get(&(metadata.dev(), metadata.ino()))
+            .map_or(0, |(_, _, seen)| *seen)
+    }
+
+    fn bytes(&self) -> u64 {
+        self.bytes
+            + self
+                .linked
+                .values()
+                .filter(|(_, links, seen)| seen >= links)
+                .map(|(size, _, _)| size)
+                .sum::<u64>()
+    }
+}
+
+/// Parses a size in bytes, optionally followed by a `K`, `M`, `G` or `T` binary multiplier.
+fn parse_size(input: &str) -> Result<u64, String> {
+    let trimmed = input.trim();
+    let trimmed = trimmed
+        .strip_suffix("iB")
+        .or_else(|| trimmed.strip_suffix('B'))
+        .unwrap_or(trimmed);
+    let (number, multiplier) = match trimmed.char_indices().last() {
+        Some((idx, unit)) if unit.is_ascii_alphabetic() => {
+            let exponent = match unit.to_ascii_uppercase() {
+                'K' => 1,
+                'M' => 2,
+                'G' => 3,
+                'T' => 4,
+                _ => return Err(format!("unknown size unit '{unit}'")),
+            };
+            (&trimmed[..idx], 1024u64.pow(exponent))
+        }
+        _ => (trimmed, 1),
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid size '{input}'"))
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next;
+    }
+    format!("{size:.1} {unit}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes() {
+        assert_eq!(Ok(512), parse_size("512"));
+        assert_eq!(Ok(2 * 1024), parse_size("2K"));
+        assert_eq!(Ok(500 * 1024 * 1024), parse_size("500M"));
+        assert_eq!(Ok(3 * 1024 * 1024 * 1024), parse_size("3GiB"));
+        assert!(parse_size("10X").is_err());
+        assert!(parse_size("G").is_err());
+
+        assert_eq!("512 B", format_size(512));
+        assert_eq!("1.5 KiB", format_size(1536));
+        assert_eq!("2.0 GiB", format_size(2 * 1024 * 1024 * 1024));
+    }
+}
@@ -0,0 +1,205 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::env::current_dir;
+use std::path::{Path, PathBuf};
+
+use crate::cli::connectivity::{download_server_client, Network};
+use crate::cli::subcommand::ide::refresh as refresh_ide_config;
+use crate::cli::subcommand::install::{install_product_afresh, install_product_incrementally};
+use crate::cli::subcommand::link::relink;
+use crate::cli::subcommand::target::check_targets_available;
+use crate::cli::CommandExecute;
+use crate::errors::Error;
+use crate::Context;
+use clap::{Parser, Subcommand};
+use criticalup_core::project_manifest::{
+    ProjectManifest, ProjectManifestEditor, ProjectManifestProduct,
+};
+use criticalup_core::state::State;
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum ComponentSubcommand {
+    Add(ComponentAdd),
+    Remove(ComponentRemove),
+}
+
+/// Manage the packages of the manifest `criticalup.toml`
+#[derive(Debug, Parser)]
+pub(crate) struct Component {
+    #[command(subcommand)]
+    command: ComponentSubcommand,
+}
+
+impl CommandExecute for Component {
+    #[tracing::instrument(level = "debug", skip_all)]
+    async fn execute(self, ctx: &Context) -> Result<(), Error> {
+        match self.command {
+            ComponentSubcommand::Add(add) => add.execute(ctx).await,
+            ComponentSubcommand::Remove(remove) => remove.execute(ctx).await,
+        }
+    }
+}
+
+/// Add packages to the manifest `criticalup.toml` and install them
+#[derive(Debug, Parser)]
+pub(crate) struct ComponentAdd {
+    /// Packages to add, for example `llvm-tools-${rustc-host}`
+    #[arg(required = true)]
+    packages: Vec<String>,
+    /// Path to the manifest `criticalup.toml`
+    #[arg(long)]
+    project: Option<PathBuf>,
+    /// Product to add the packages to, instead of the first one of the manifest
+    #[arg(long)]
+    product: Option<String>,
+    #[clap(flatten)]
+    network: Network,
+}
+
+impl CommandExecute for ComponentAdd {
+    #[tracing::instrument(level = "debug", skip_all)]
+    async fn execute(self, ctx: &Context) -> Result<(), Error> {
+        let project = match self.project {
+            Some(project) => project,
+            None => ProjectManifest::discover(&current_dir()?)?,
+        };
+
+        let mut editor = ProjectManifestEditor::open(&project)?;
+        let product_name = editor.product_name(self.product.as_deref())?;
+        for package in &self.packages {
+            if !editor.add_to_list(&product_name, "packages", package)? {
+                tracing::info!("Package '{package}' is already part of product '{product_name}'");
+            }
+        }
+
+        let previous_manifest = ProjectManifest::load(&project)?;
+        let manifest = editor.manifest()?;
+        let (Some(previous), Some(product)) = (
+            find_product(&previous_manifest, &product_name),
+            find_product(&manifest, &product_name),
+        ) else {
+            return Ok(());
+        };
+
+        let state = State::load(&ctx.config).await?;
+        let client =
+            download_server_client(ctx, &state, self.network.connectivity, Some(product)).await;
+
+        // Validate the new packages and the targets against the release before touching the
+        // manifest, as the incremental installation would only fail after saving it.
+        let keys = client.keys().await?;
+        let release = client
+            .product_release_manifest(product.name(), product.release())
+            .await?
+            .signed
+            .into_verified(&keys)?;
+        for package in product.packages() {
+            if !previous.packages().contains(package)
+                && !release.packages.iter().any(|p| &p.package == package)
+            {
+                return Err(Error::PackageNotAvailable {
+                    package: package.clone(),
+                    release: release.release.clone(),
+                });
+            }
+        }
+        check_targets_available(product.targets(), &release)?;
+        editor.save()?;
+
+        if product.installation_id() == previous.installation_id() {
+            return Ok(());
+        }
+
+        if installation_exists(ctx, &state, product) {
+            state.update_installation_manifests(&product.installation_id(), &project)?;
+        } else if installation_exists(ctx, &state, previous) {
+            // Only download the new packages, reusing the rest of the existing installation.
+            install_product_incrementally(ctx, &state, &client, &project, product, previous)
+                .await?;
+        } else {
+            install_product_afresh(ctx, &state, &client, &project, product).await?;
+        }
+        finish_installation(ctx, &state, &project).await
+    }
+}
+
+/// Remove packages from the manifest `criticalup.toml` and install the remaining ones
+#[derive(Debug, Parser)]
+pub(crate) struct ComponentRemove {
+    /// Packages to remove, as written in the manifest
+    #[arg(required = true)]
+    packages: Vec<String>,
+    /// Path to the manifest `criticalup.toml`
+    #[arg(long)]
+    project: Option<PathBuf>,
+    /// Product to remove the packages from, instead of the first one of the manifest
+    #[arg(long)]
+    product: Option<String>,
+    #[clap(flatten)]
+    network: Network,
+}
+
+impl CommandExecute for ComponentRemove {
+    #[tracing::instrument(level = "debug", skip_all)]
+    async fn execute(self, ctx: &Context) -> Result<(), Error> {
+        let project = match self.project {
+            Some(project) => project,
+            None => ProjectManifest::discover(&current_dir()?)?,
+        };
+
+        let mut editor = ProjectManifestEditor::open(&project)?;
+        let product_name = editor.product_name(self.product.as_deref())?;
+        for package in &self.packages {
+            if !editor.remove_from_list(&product_name, "packages", package)? {
+                tracing::warn!("Package '{package}' is not part of product '{product_name}'");
+            }
+        }
+
+        // Fails if the last package was removed, before the manifest is saved.
+        let manifest = editor.manifest()?;
+        editor.save()?;
+
+        let Some(product) = find_product(&manifest, &product_name) else {
+            return Ok(());
+        };
+        let state = State::load(&ctx.config).await?;
+        if !installation_exists(ctx, &state, product) {
+            // The remaining packages are installed afresh. Their archives are usually still in the
+            // download cache, but they are revalidated with the server unless offline.
+            let client =
+                download_server_client(ctx, &state, self.network.connectivity, Some(product)).await;
+            install_product_afresh(ctx, &state, &client, &project, product).await?;
+        } else {
+            state.update_installation_manifests(&product.installation_id(), &project)?;
+        }
+        finish_installation(ctx, &state, &project).await
+    }
+}
+
+fn find_product<'a>(
+    manifest: &'a ProjectManifest,
+    name: &str,
+) -> Option<&'a ProjectManifestProduct> {
+    manifest.products().iter().find(|p| p.name() == name)
+}
+
+/// Whether the product is installed, both in the state and on disk.
+fn installation_exists(ctx: &Context, state: &State, product: &ProjectManifestProduct) -> bool {
+    let installation_id = product.installation_id();
+    state.installations().contains_key(&installation_id)
+        && ctx
+            .config
+            .paths
+            .installation_dir
+            .join(installation_id)
+            .exists()
+}
+
+async fn finish_installation(ctx: &Context, state: &State, project: &Path) -> Result<(), Error> {
+    state.persist().await?;
+    criticalup_core::binary_proxies::update(&ctx.config, state, &std::env::current_exe()?).await?;
+    relink(ctx, state, project).await;
+    refresh_ide_config(ctx, project);
+    Ok(())
+}
@@ -0,0 +1,713 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use criticalup_core::config::Config;
+use criticalup_core::download_server_client::{
+    token_expiry_warning_days, Connectivity, DownloadServerClient, TokenExpiryStatus,
+};
+use criticalup_core::envvars::EnvVars;
+use criticalup_core::errors::DownloadServerError;
+use criticalup_core::project_manifest::{InstallationId, ProjectManifest};
+use criticalup_core::state::{StaleManifest, StaleManifestReason, State};
+use serde_json::{json, Value};
+use time::OffsetDateTime;
+use tokio::process::Command;
+
+use crate::cli::connectivity::Network;
+use crate::cli::subcommand::link::parse_toolchain_list;
+use crate::cli::CommandExecute;
+use crate::errors::{Error, LibError};
+use crate::Context;
+
+/// Diagnose common problems with the criticalup setup and suggest how to fix them
+#[derive(Debug, Parser)]
+pub(crate) struct Doctor {
+    /// Print the report as JSON on stdout
+    #[arg(long)]
+    json: bool,
+    #[clap(flatten)]
+    network: Network,
+}
+
+impl CommandExecute for Doctor {
+    #[tracing::instrument(level = "debug", skip_all)]
+    async fn execute(self, ctx: &Context) -> Result<(), Error> {
+        let config = &ctx.config;
+        let mut checks = Vec::new();
+
+        check_directories(config, &mut checks);
+        check_path_variable(config, &mut checks);
+        match State::load(config).await {
+            Ok(state) => {
+                checks.push(Check::ok("state", "The state file is valid"));
+                // The state is left untouched: stale project manifests are only reported, and
+                // `criticalup clean` forgets them.
+                let stale = state.stale_manifests();
+                check_stale_manifests(&stale, &mut checks);
+
+                check_installations(config, &state, &stale, &mut checks).await?;
+                check_binary_proxies(config, &state, &mut checks)?;
+                check_rustup_link(config, Some(&state), &mut checks).await;
+                check_download_server(config, &state, self.network.connectivity, &mut checks).await;
+            }
+            Err(err) => {
+                checks.push(Check::error(
+                    "state",
+                    format!("{err}"),
+                    "Move the state file away, then run `criticalup auth set` and \
+                    `criticalup install` to recreate it",
+                ));
+                check_rustup_link(config, None, &mut checks).await;
+            }
+        }
+
+        if self.json {
+            let report = json!({
+                "checks": checks.iter().map(Check::to_json).collect::<Vec<_>>(),
+            });
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).expect("serializing the report failed")
+            );
+        } else {
+            for check in &checks {
+                eprintln!("{:<8} {}", check.status.as_str(), check.message);
+                if let Some(fix) = &check.fix {
+                    eprintln!("{:<8} fix: {fix}", "");
+                }
+            }
+        }
+
+        if checks.iter().any(|check| check.status == Status::Error) {
+            return Err(Error::Exit(1));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Skipped,
+    Warning,
+    Error,
+}
+
+impl Status {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Skipped => "skipped",
+            Status::Warning => "warning",
+            Status::Error => "error",
+        }
+    }
+}
+
+/// Outcome of one of the diagnostics, with how to fix it if it's a problem.
+struct Check {
+    name: &'static str,
+    status: Status,
+    message: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, message: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Ok,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    fn skipped(name: &'static str, message: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Skipped,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    fn warning(name: &'static str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Warning,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn error(name: &'static str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Error,
+            message: message.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "status": self.status.as_str(),
+            "message": self.message,
+            "fix": self.fix,
+        })
+    }
+}
+
+/// Checks the directories criticalup uses are directories and can be written to, if they exist.
+fn check_directories(config: &Config, checks: &mut Vec<Check>) {
+    let paths = &config.paths;
+    let directories = [
+        ("root", "Root directory", &paths.root, "CRITICALUP_ROOT"),
+        (
+            "proxy-dir",
+            "Proxy directory",
+            &paths.proxy_dir,
+            "CRITICALUP_ROOT",
+        ),
+        (
+            "installation-dir",
+            "Installation directory",
+            &paths.installation_dir,
+            "CRITICALUP_ROOT",
+        ),
+        (
+            "cache-dir",
+            "Cache directory",
+            &paths.cache_dir,
+            "CRITICALUP_CACHE_DIR",
+        ),
+    ];
+
+    for (name, label, path, env) in directories {
+        let check = if !path.exists() {
+            Check::ok(
+                name,
+                format!("{label} {} will be created when needed", path.display()),
+            )
+        } else if !path.is_dir() {
+            Check::error(
+                name,
+                format!("{label} {} is not a directory", path.display()),
+                format!(
+                    "Move {} away, or choose another path with {env}",
+                    path.display()
+                ),
+            )
+        } else if tempfile::tempfile_in(path).is_err() {
+            Check::error(
+                name,
+                format!("{label} {} is not writable", path.display()),
+                format!(
+                    "Make {} writable by the current user, or choose another path with {env}",
+                    path.display()
+                ),
+            )
+        } else {
+            Check::ok(name, format!("{label} {}", path.display()))
+        };
+        checks.push(check);
+    }
+}
+
+/// Checks the binary proxies can be found through `PATH`.
+fn check_path_variable(config: &Config, checks: &mut Vec<Check>) {
+    let bin_dir = config.paths.proxy_dir.join("bin");
+    let in_path = std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|entry| same_path(&entry, &bin_dir)));
+
+    checks.push(if in_path {
+        Check::ok("path", format!("{} is in PATH", bin_dir.display()))
+    } else {
+        Check::warning(
+            "path",
+            format!("{} is not in PATH", bin_dir.display()),
+            format!(
+                "Add {} to PATH, or run `criticalup link create` to use the toolchains through \
+                rustup",
+                bin_dir.display()
+            ),
+        )
+    });
+}
+
+/// Reports the project manifests the state remembers, but which were deleted or no longer match
+/// their installation.
+fn check_stale_manifests(stale: &[StaleManifest], checks: &mut Vec<Check>) {
+    if stale.is_empty() {
+        checks.push(Check::ok(
+            "project-manifests",
+            "No stale project manifests found",
+        ));
+    }
+    for stale in stale {
+        let reason = match stale.reason {
+            StaleManifestReason::Missing => "no longer exists",
+            StaleManifestReason::Changed => "no longer matches the installation",
+        };
+        checks.push(Check::warning(
+            "project-manifests",
+            format!(
+                "Project manifest {} of installation {} {reason}",
+                stale.manifest.display(),
+                stale.installation.0
+            ),
+            "Run `criticalup clean` to forget it",
+        ));
+    }
+}
+
+/// Checks the installations in the state match the installation directories on disk. Stale
+/// project manifests don't count as using an installation.
+async fn check_installations(
+    config: &Config,
+    state: &State,
+    stale: &[StaleManifest],
+    checks: &mut Vec<Check>,
+) -> Result<(), Error> {
+    let installation_dir = &config.paths.installation_dir;
+    let mut problems = false;
+
+    for (installation_id, installation) in state.installations().iter() {
+        let unused = installation.manifests().iter().all(|manifest| {
+            stale
+                .iter()
+                .any(|s| s.installation == *installation_id && s.manifest == *manifest)
+        });
+        if unused {
+            problems = true;
+            checks.push(Check::warning(
+                "installations",
+                format!(
+                    "Installation {} is no longer used by any project",
+                    installation_id.0
+                ),
+                "Run `criticalup clean` to delete it",
+            ));
+        } else if !installation_dir.join(&installation_id.0).is_dir() {
+            problems = true;
+            let project = installation
+                .manifests()
+                .first()
+                .expect("installation has manifests");
+            checks.push(Check::error(
+                "installations",
+                format!("Installation {} is missing", installation_id.0),
+                format!(
+                    "Run `criticalup install --project {}` to install it again",
+                    project.display()
+                ),
+            ));
+        }
+    }
+
+    if installation_dir.is_dir() {
+        let installations = state.installations();
+        for entry in std::fs::read_dir(installation_dir)? {
+            let entry = entry?;
+            let untracked = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| !installations.contains_key(&InstallationId(name.into())));
+            if untracked && entry.file_type()?.is_dir() {
+                problems = true;
+                checks.push(Check::warning(
+                    "installations",
+                    format!(
+                        "Installation directory {} is not tracked in the state",
+                        entry.path().display()
+                    ),
+                    "Run `criticalup clean` to delete it",
+                ));
+            }
+        }
+    }
+
+    if !problems {
+        checks.push(Check::ok(
+            "installations",
+            format!(
+                "{} installations are consistent with the state",
+                state.installations().len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks the binary proxies created by `binary_proxies::update` exist, and point to this
+/// criticalup binary where that can be determined.
+fn check_binary_proxies(
+    config: &Config,
+    state: &State,
+    checks: &mut Vec<Check>,
+) -> Result<(), Error> {
+    let expected = state.all_binary_proxy_names();
+    if expected.is_empty() {
+        checks.push(Check::skipped(
+            "binary-proxies",
+            "No installation provides binary proxies",
+        ));
+        return Ok(());
+    }
+
+    let bin_dir = config.paths.proxy_dir.join("bin");
+    #[cfg(not(windows))]
+    let current_exe = std::env::current_exe()?;
+    let mut missing = Vec::new();
+    let mut outdated = Vec::new();
+    for name in &expected {
+        let proxy = bin_dir.join(name);
+        if proxy.symlink_metadata().is_err() {
+            missing.push(name.display().to_string());
+            continue;
+        }
+        // Proxies are copies of the binary on Windows, so only symlinks can be checked.
+        #[cfg(not(windows))]
+        if !proxy
+            .read_link()
+            .is_ok_and(|target| same_path(&target, &current_exe))
+        {
+            outdated.push(name.display().to_string());
+        }
+    }
+
+    let fix = "Run `criticalup install` in one of your projects to recreate the binary proxies";
+    if !missing.is_empty() {
+        checks.push(Check::error(
+            "binary-proxies",
+            format!("Binary proxies are missing: {}", missing.join(", ")),
+            fix,
+        ));
+    }
+    if !outdated.is_empty() {
+        checks.push(Check::warning(
+            "binary-proxies",
+            format!(
+                "Binary proxies don't point to this criticalup: {}",
+                outdated.join(", ")
+            ),
+            fix,
+        ));
+    }
+    if missing.is_empty() && outdated.is_empty() {
+        checks.push(Check::ok(
+            "binary-proxies",
+            format!("{} binary proxies are in place", expected.len()),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks the `ferrocene` rustup toolchain, if any, links to the proxy directory, and the rustup
+/// toolchains linked to projects with `criticalup link create --name` link to their installation.
+async fn check_rustup_link(config: &Config, state: Option<&State>, checks: &mut Vec<Check>) {
+    let proxy_dir = &config.paths.proxy_dir;
+    let relink = "Run `criticalup link remove` and `criticalup link create` to link it again";
+
+    let output = match Command::new("rustup")
+        .args(["toolchain", "list", "-v"])
+        .output()
+        .await
+    {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            checks.push(Check::warning(
+                "rustup-link",
+                format!(
+                    "Listing the rustup toolchains failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+                "Check that rustup works by running `rustup toolchain list`",
+            ));
+            return;
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            checks.push(Check::skipped("rustup-link", "rustup is not installed"));
+            return;
+        }
+        Err(err) => {
+            checks.push(Check::warning(
+                "rustup-link",
+                format!("Running rustup failed: {err}"),
+                "Check that rustup works by running `rustup toolchain list`",
+            ));
+            return;
+        }
+    };
+
+    let toolchains = parse_toolchain_list(&String::from_utf8_lossy(&output.stdout));
+    if let Some(state) = state {
+        check_named_links(state, &toolchains, checks);
+    }
+    let linked = toolchains
+        .into_iter()
+        .find_map(|(name, path)| (name == "ferrocene").then_some(path));
+
+    checks.push(match linked {
+        None => Check::skipped(
+            "rustup-link",
+            "The `ferrocene` rustup toolchain is not linked",
+        ),
+        Some(path) if !path.exists() => Check::error(
+            "rustup-link",
+            format!(
+                "The `ferrocene` rustup toolchain links to {}, which does not exist",
+                path.display()
+            ),
+            relink,
+        ),
+        Some(path) if !same_path(&path, proxy_dir) => Check::warning(
+            "rustup-link",
+            format!(
+                "The `ferrocene` rustup toolchain links to {} instead of {}",
+                path.display(),
+                proxy_dir.display()
+            ),
+            relink,
+        ),
+        Some(_) => Check::ok(
+            "rustup-link",
+            format!(
+                "The `ferrocene` rustup toolchain links to {}",
+                proxy_dir.display()
+            ),
+        ),
+    });
+}
+
+/// Checks the toolchains linked to projects are still known to rustup and link to the installation
+/// recorded in the state, which must exist.
+fn check_named_links(state: &State, toolchains: &[(String, PathBuf)], checks: &mut Vec<Check>) {
+    for (name, link) in state.links() {
+        let relink = format!(
+            "Run `criticalup install --project {}` to link it again, or `criticalup link remove \
+            --name {name}` to remove it",
+            link.manifest.display()
+        );
+        let linked = toolchains
+            .iter()
+            .find_map(|(toolchain, path)| (*toolchain == name).then_some(path));
+        checks.push(match linked {
+            None => Check::warning(
+                "rustup-link",
+                format!("The `{name}` rustup toolchain is no longer linked"),
+                relink,
+            ),
+            Some(path) if !path.exists() => Check::error(
+                "rustup-link",
+                format!(
+                    "The `{name}` rustup toolchain links to {}, which does not exist",
+                    path.display()
+                ),
+                relink,
+            ),
+            Some(path) if !same_path(path, &link.path) => Check::warning(
+                "rustup-link",
+                format!(
+                    "The `{name}` rustup toolchain links to {} instead of {}",
+                    path.display(),
+                    link.path.display()
+                ),
+                relink,
+            ),
+            Some(_) => Check::ok(
+                "rustup-link",
+                format!(
+                    "The `{name}` rustup toolchain links to the installation of {}",
+                    link.manifest.display()
+                ),
+            ),
+        });
+    }
+}
+
+/// Checks the download servers can be reached and accept the authentication tokens: the default
+/// server with the default token, and the server and authentication profile of every product of
+/// the installed projects.
+async fn check_download_server(
+    config: &Config,
+    state: &State,
+    connectivity: Connectivity,
+    checks: &mut Vec<Check>,
+) {
+    if connectivity == Connectivity::Offline {
+        checks.push(Check::skipped("download-server", "Offline mode"));
+        checks.push(Check::skipped("authentication", "Offline mode"));
+        return;
+    }
+
+    let mut clients = vec![(
+        DownloadServerClient::new_read_only(config, state, Connectivity::Online),
+        None,
+    )];
+    for manifest_path in state
+        .installations()
+        .values()
+        .flat_map(|installation| installation.manifests())
+    {
+        // Broken and deleted manifests are reported by the other checks.
+        let Ok(manifest) = ProjectManifest::load(manifest_path) else {
+            continue;
+        };
+        for product in manifest.products() {
+            let mut client =
+                DownloadServerClient::new_read_only(config, state, Connectivity::Online);
+            client.set_product_settings(product);
+            let profile = product.auth_profile().map(String::from);
+            if !clients
+                .iter()
+                .any(|(c, p)| c.base_url() == client.base_url() && *p == profile)
+            {
+                clients.push((client, profile));
+            }
+        }
+    }
+
+    for (client, profile) in &clients {
+        check_one_download_server(state, client, profile.as_deref(), checks).await;
+    }
+}
+
+async fn check_one_download_server(
+    state: &State,
+    client: &DownloadServerClient,
+    profile: Option<&str>,
+    checks: &mut Vec<Check>,
+) {
+    let (token_name, set_token) = match profile {
+        Some(profile) => (
+            format!("authentication token of profile '{profile}'"),
+            format!("`criticalup auth set --profile {profile}`"),
+        ),
+        None => (
+            "authentication token".to_string(),
+            "`criticalup auth set`".to_string(),
+        ),
+    };
+    let token_configured = EnvVars::new().criticalup_token.is_some()
+        || match profile {
+            Some(profile) => state.authentication_profile(profile).is_some(),
+            None => state.authentication_token().await.is_some(),
+        };
+    let missing_token = Check::warning(
+        "authentication",
+        format!(
+            "No {token_name} is configured for the download server at {}",
+            client.base_url()
+        ),
+        format!("Run {set_token} with a token from the customer portal"),
+    );
+    if !token_configured && profile.is_some() {
+        // Requests fail right away when the profile does not exist, so the server can't be
+        // checked either.
+        checks.push(missing_token);
+        return;
+    }
+
+    if let Err(err) = client.keys().await {
+        checks.push(Check::error(
+            "download-server",
+            format!(
+                "Could not reach the download server at {}: {}",
+                client.base_url(),
+                error_chain(&err)
+            ),
+            "Check the network connection, and the proxy settings in the `HTTPS_PROXY` \
+            environment variable",
+        ));
+        checks.push(Check::skipped(
+            "authentication",
+            format!(
+                "The download server at {} can't be reached",
+                client.base_url()
+            ),
+        ));
+        return;
+    }
+    checks.push(Check::ok(
+        "download-server",
+        format!("Reached the download server at {}", client.base_url()),
+    ));
+
+    if !token_configured {
+        checks.push(missing_token);
+        return;
+    }
+
+    let renew = format!("Create a new token in the customer portal and run {set_token}");
+    checks.push(match client.get_current_token_data().await {
+        Ok(data) => {
+            match data.expiry_status(token_expiry_warning_days(), OffsetDateTime::now_utc()) {
+                TokenExpiryStatus::Expired(_) => Check::error(
+                    "authentication",
+                    format!("The {token_name} '{}' has expired", data.name),
+                    renew,
+                ),
+                TokenExpiryStatus::ExpiresSoon(_) => Check::warning(
+                    "authentication",
+                    format!(
+                        "The {token_name} '{}' expires on {}",
+                        data.name,
+                        data.expires_at.as_deref().unwrap_or_default()
+                    ),
+                    renew,
+                ),
+                TokenExpiryStatus::NoExpiry | TokenExpiryStatus::Valid(_) => Check::ok(
+                    "authentication",
+                    format!(
+                        "The {token_name} '{}' of {} is valid",
+                        data.name, data.organization_name
+                    ),
+                ),
+            }
+        }
+        Err(LibError::InvalidAuthenicationToken) => Check::error(
+            "authentication",
+            format!("The {token_name} is malformed"),
+            renew,
+        ),
+        Err(LibError::DownloadServerError {
+            kind:
+                kind @ (DownloadServerError::AuthenticationFailed
+                | DownloadServerError::AuthenticationTokenExpired
+                | DownloadServerError::AuthenticationTokenRevoked
+                | DownloadServerError::AuthenticationTokenMalformed),
+            ..
+        }) => Check::error("authentication", format!("{kind}"), renew),
+        Err(err) => Check::error(
+            "authentication",
+            format!("Checking the {token_name} failed: {}", error_chain(&err)),
+            "Run `criticalup doctor` again later",
+        ),
+    });
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    a == b
+        || matches!(
+            (a.canonicalize(), b.canonicalize()),
+            (Ok(a), Ok(b)) if a == b
+        )
+}
+
+/// Formats an error along with its sources, as the outermost errors are often too generic.
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        message.push_str(": ");
+        message.push_str(&err.to_string());
+        source = err.source();
+    }
+    message
+}
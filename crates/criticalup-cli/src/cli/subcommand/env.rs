@@ -0,0 +1,268 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::collections::HashSet;
+use std::env::consts::EXE_SUFFIX;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use clap::Parser;
+use criticalup_core::errors::Error as LibError;
+
+use crate::cli::subcommand::run::locate_installations;
+use crate::cli::CommandExecute;
+use crate::errors::Error;
+use crate::Context;
+
+/// Variable listing the directories a previous `criticalup env` prepended to `PATH`, so that they
+/// can be removed again when the environment changes.
+const ADDED_PATH_VAR: &str = "CRITICALUP_ENV_PATH";
+/// Same as [`ADDED_PATH_VAR`], for the dynamic library search path.
+const ADDED_LIBRARY_PATH_VAR: &str = "CRITICALUP_ENV_LIBRARY_PATH";
+
+#[cfg(target_os = "linux")]
+const LIBRARY_PATH_VAR: Option<&str> = Some("LD_LIBRARY_PATH");
+#[cfg(target_os = "macos")]
+const LIBRARY_PATH_VAR: Option<&str> = Some("DYLD_FALLBACK_LIBRARY_PATH");
+// Windows looks for libraries in `PATH`.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+const LIBRARY_PATH_VAR: Option<&str> = None;
+
+/// Variables pointing to the tools of the installation.
+const TOOL_VARS: [&str; 3] = ["RUSTC", "CARGO", "RUST_SRC_PATH"];
+
+/// Print shell commands setting up the environment for the project's toolchain
+#[derive(Debug, Parser)]
+pub(crate) struct Env {
+    /// Shell to print the commands for
+    #[arg(long, value_enum, default_value_t)]
+    shell: Shell,
+    /// Path to the manifest `criticalup.toml`
+    #[arg(long)]
+    project: Option<PathBuf>,
+    /// Outside of an installed project, print commands reverting a previous `criticalup env`
+    /// instead of failing
+    #[arg(long)]
+    allow_no_project: bool,
+    /// Print a snippet for the shell configuration, updating the environment when changing
+    /// directory
+    #[arg(long, conflicts_with_all = ["project", "allow_no_project"])]
+    hook: bool,
+}
+
+impl CommandExecute for Env {
+    #[tracing::instrument(level = "debug", skip_all)]
+    async fn execute(self, ctx: &Context) -> Result<(), Error> {
+        if self.hook {
+            print!("{}", self.shell.hook(ctx.config.whitelabel.name));
+            return Ok(());
+        }
+
+        let installations = match locate_installations(ctx, self.project).await {
+            Ok(installations) => installations,
+            Err(Error::InstallationNotFound(_))
+            | Err(Error::Lib(LibError::ProjectManifestDetectionFailed))
+                if self.allow_no_project =>
+            {
+                Vec::new()
+            }
+            Err(err) => return Err(err),
+        };
+
+        for (name, value) in environment(&installations)? {
+            match value {
+                Some(value) => println!("{}", self.shell.set(name, &value.to_string_lossy())),
+                None => println!("{}", self.shell.unset(name)),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+impl Default for Shell {
+    fn default() -> Self {
+        if cfg!(windows) {
+            Shell::Powershell
+        } else {
+            Shell::Bash
+        }
+    }
+}
+
+impl std::fmt::Display for Shell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let shell = match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+            Shell::Powershell => "powershell",
+        };
+        write!(f, "{shell}")
+    }
+}
+
+impl Shell {
+    fn set(&self, name: &str, value: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => {
+                format!("export {name}='{}';", value.replace('\'', r"'\''"))
+            }
+            Shell::Fish => format!(
+                "set -gx {name} '{}';",
+                value.replace('\\', r"\\").replace('\'', r"\'")
+            ),
+            Shell::Powershell => format!("$Env:{name} = '{}'", value.replace('\'', "''")),
+        }
+    }
+
+    fn unset(&self, name: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => format!("unset {name};"),
+            Shell::Fish => format!("set -e {name};"),
+            Shell::Powershell => {
+                format!("Remove-Item Env:{name} -ErrorAction SilentlyContinue")
+            }
+        }
+    }
+
+    fn hook(&self, binary: &str) -> String {
+        match self {
+            Shell::Bash => format!(
+                r#"_criticalup_hook() {{
+  local previous_exit_status=$?
+  if [[ "$PWD" != "${{_CRITICALUP_HOOK_PWD:-}}" ]]; then
+    _CRITICALUP_HOOK_PWD="$PWD"
+    eval "$({binary} env --shell bash --allow-no-project)"
+  fi
+  return $previous_exit_status
+}}
+if [[ ";${{PROMPT_COMMAND:-}};" != *";_criticalup_hook;"* ]]; then
+  PROMPT_COMMAND="_criticalup_hook${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}"
+fi
+"#
+            ),
+            Shell::Zsh => format!(
+                r#"_criticalup_hook() {{
+  eval "$({binary} env --shell zsh --allow-no-project)"
+}}
+autoload -Uz add-zsh-hook
+add-zsh-hook chpwd _criticalup_hook
+_criticalup_hook
+"#
+            ),
+            Shell::Fish => format!(
+                r#"function __criticalup_hook --on-variable PWD
+    {binary} env --shell fish --allow-no-project | source
+end
+__criticalup_hook
+"#
+            ),
+            Shell::Powershell => format!(
+                r#"$global:CriticalupHookLocation = $null
+$global:CriticalupOriginalPrompt = $function:prompt
+function global:prompt {{
+    if ($PWD.Path -ne $global:CriticalupHookLocation) {{
+        $global:CriticalupHookLocation = $PWD.Path
+        (& {binary} env --shell powershell --allow-no-project) -join "`n" | Invoke-Expression
+    }}
+    & $global:CriticalupOriginalPrompt
+}}
+"#
+            ),
+        }
+    }
+}
+
+/// Computes the variables to set (or unset, when `None`) for the given installations, starting
+/// from the current environment. The directories added by a previous `criticalup env` are removed
+/// first, so that evaluating the output repeatedly does not grow the search paths.
+fn environment(installations: &[PathBuf]) -> Result<Vec<(&'static str, Option<OsString>)>, Error> {
+    let mut bin_paths: Vec<PathBuf> = installations
+        .iter()
+        .map(|installation| installation.join("bin"))
+        .filter(|path| path.is_dir())
+        .collect();
+    let lib_paths: Vec<PathBuf> = installations
+        .iter()
+        .map(|installation| installation.join("lib"))
+        .filter(|path| path.is_dir())
+        .collect();
+
+    let mut vars = Vec::new();
+    match LIBRARY_PATH_VAR {
+        Some(library_path_var) => update_search_path(
+            &mut vars,
+            library_path_var,
+            ADDED_LIBRARY_PATH_VAR,
+            &lib_paths,
+        )?,
+        None => bin_paths.extend(lib_paths),
+    }
+    update_search_path(&mut vars, "PATH", ADDED_PATH_VAR, &bin_paths)?;
+
+    let find = |relative: &str| {
+        installations
+            .iter()
+            .map(|installation| installation.join(relative))
+            .find(|path| path.exists())
+            .map(OsString::from)
+    };
+    let tools = [
+        find(&format!("bin/rustc{EXE_SUFFIX}")),
+        find(&format!("bin/cargo{EXE_SUFFIX}")),
+        find("lib/rustlib/src/rust/library"),
+    ];
+    for (name, value) in TOOL_VARS.into_iter().zip(tools) {
+        // Outside of a project, only undo what a previous `criticalup env` did.
+        if value.is_some() || std::env::var_os(ADDED_PATH_VAR).is_some() {
+            vars.push((name, value));
+        }
+    }
+    Ok(vars)
+}
+
+/// Prepends `paths` to the search path in `var`, after removing the paths listed in `added_var`,
+/// and records them in `added_var`. Search paths that neither get nor lose entries are untouched.
+fn update_search_path(
+    vars: &mut Vec<(&'static str, Option<OsString>)>,
+    var: &'static str,
+    added_var: &'static str,
+    paths: &[PathBuf],
+) -> Result<(), Error> {
+    let previously_added = std::env::var_os(added_var);
+    if paths.is_empty() && previously_added.is_none() {
+        return Ok(());
+    }
+    let previously_added: HashSet<PathBuf> = previously_added
+        .map(|added| std::env::split_paths(&added).collect())
+        .unwrap_or_default();
+
+    let existing = std::env::var_os(var).unwrap_or_default();
+    let updated = paths
+        .iter()
+        .cloned()
+        .chain(
+            std::env::split_paths(&existing)
+                .filter(|path| !path.as_os_str().is_empty() && !previously_added.contains(path)),
+        )
+        .collect::<Vec<_>>();
+    vars.push((var, join_paths(&updated)?));
+    vars.push((added_var, join_paths(paths)?));
+    Ok(())
+}
+
+fn join_paths(paths: &[PathBuf]) -> Result<Option<OsString>, Error> {
+    if paths.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(std::env::join_paths(paths)?))
+    }
+}
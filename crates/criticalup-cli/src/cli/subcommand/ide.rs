@@ -0,0 +1,279 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::env::consts::EXE_SUFFIX;
+use std::env::current_dir;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use criticalup_core::project_manifest::ProjectManifest;
+use serde_json::{json, Map, Value};
+
+use crate::cli::CommandExecute;
+use crate::errors::Error;
+use crate::Context;
+
+/// Settings of rust-analyzer set in the VS Code settings.
+const VSCODE_SETTINGS: [&str; 4] = [
+    "rust-analyzer.server.path",
+    "rust-analyzer.cargo.sysroot",
+    "rust-analyzer.cargo.sysrootSrc",
+    "rust-analyzer.cargo.target",
+];
+
+/// Print or write the editor configuration pointing rust-analyzer to the project's toolchain
+#[derive(Debug, Parser)]
+pub(crate) struct Ide {
+    /// Editor to generate the configuration for
+    #[arg(long, value_enum, default_value_t)]
+    editor: Editor,
+    /// Path to the manifest `criticalup.toml`
+    #[arg(long)]
+    project: Option<PathBuf>,
+    /// Write the configuration into the project, where `install` keeps it up to date
+    #[arg(long)]
+    write: bool,
+}
+
+impl CommandExecute for Ide {
+    #[tracing::instrument(level = "debug", skip_all, fields(editor = %self.editor))]
+    async fn execute(self, ctx: &Context) -> Result<(), Error> {
+        let project = match self.project {
+            Some(project) => project,
+            None => ProjectManifest::discover(&current_dir()?)?,
+        };
+        let toolchain = Toolchain::locate(ctx, &project)?;
+
+        if self.write {
+            let path = self.editor.write(&project, &toolchain)?;
+            tracing::info!(
+                "Wrote the {} configuration to {}",
+                self.editor,
+                path.display()
+            );
+        } else {
+            let config = match self.editor {
+                Editor::Vscode => Value::Object(toolchain.vscode_settings()),
+                Editor::Lsp => toolchain.lsp_config(),
+            };
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&config)
+                    .expect("serializing the configuration failed")
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
+enum Editor {
+    /// Visual Studio Code, through `.vscode/settings.json`
+    #[default]
+    Vscode,
+    /// Any other LSP client, through `.criticalup/ide.json`
+    Lsp,
+}
+
+impl std::fmt::Display for Editor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let editor = match self {
+            Editor::Vscode => "vscode",
+            Editor::Lsp => "lsp",
+        };
+        write!(f, "{editor}")
+    }
+}
+
+impl Editor {
+    /// Where the configuration of the project lives.
+    fn path(&self, project: &Path) -> PathBuf {
+        let project_dir = project.parent().unwrap_or(Path::new("."));
+        match self {
+            Editor::Vscode => project_dir.join(".vscode").join("settings.json"),
+            Editor::Lsp => project_dir.join(".criticalup").join("ide.json"),
+        }
+    }
+
+    /// Writes the configuration, keeping the unrelated VS Code settings.
+    fn write(&self, project: &Path, toolchain: &Toolchain) -> Result<PathBuf, Error> {
+        let path = self.path(project);
+        let config = match self {
+            Editor::Vscode => {
+                let mut settings = read_vscode_settings(&path)?.unwrap_or_default();
+                for key in VSCODE_SETTINGS {
+                    settings.remove(key);
+                }
+                settings.extend(toolchain.vscode_settings());
+                Value::Object(settings)
+            }
+            Editor::Lsp => toolchain.lsp_config(),
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut contents =
+            serde_json::to_string_pretty(&config).expect("serializing the configuration failed");
+        contents.push('\n');
+        std::fs::write(&path, contents)?;
+        Ok(path)
+    }
+
+    /// Whether the project has a configuration previously written by `criticalup ide --write`.
+    fn is_written(&self, ctx: &Context, project: &Path) -> bool {
+        let path = self.path(project);
+        match self {
+            Editor::Vscode => read_vscode_settings(&path)
+                .ok()
+                .flatten()
+                .and_then(|settings| {
+                    settings
+                        .get("rust-analyzer.cargo.sysroot")
+                        .and_then(Value::as_str)
+                        .map(|sysroot| {
+                            Path::new(sysroot).starts_with(&ctx.config.paths.installation_dir)
+                        })
+                })
+                .unwrap_or(false),
+            Editor::Lsp => path.is_file(),
+        }
+    }
+}
+
+/// Rewrites the editor configurations written by `criticalup ide --write` for the project, after
+/// its installation changed.
+pub(crate) fn refresh(ctx: &Context, project: &Path) {
+    for editor in [Editor::Vscode, Editor::Lsp] {
+        if !editor.is_written(ctx, project) {
+            continue;
+        }
+        let result =
+            Toolchain::locate(ctx, project).and_then(|toolchain| editor.write(project, &toolchain));
+        match result {
+            Ok(path) => tracing::info!("Updated the {editor} configuration at {}", path.display()),
+            Err(err) => tracing::warn!("Failed to update the {editor} configuration: {err}"),
+        }
+    }
+}
+
+fn read_vscode_settings(path: &Path) -> Result<Option<Map<String, Value>>, Error> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let invalid = |kind| Error::InvalidEditorSettings {
+        path: path.into(),
+        kind,
+    };
+    match serde_json::from_str(&contents).map_err(|err| invalid(Some(err)))? {
+        Value::Object(settings) => Ok(Some(settings)),
+        _ => Err(invalid(None)),
+    }
+}
+
+/// The parts of an installation an editor needs to know about.
+pub(crate) struct Toolchain {
+    pub(crate) sysroot: PathBuf,
+    rust_analyzer: Option<PathBuf>,
+    rust_src: Option<PathBuf>,
+    targets: Vec<String>,
+}
+
+impl Toolchain {
+    /// Finds the installation providing `rustc` for the project.
+    pub(crate) fn locate(ctx: &Context, project: &Path) -> Result<Self, Error> {
+        let manifest = ProjectManifest::load(project)?;
+        let installations: Vec<PathBuf> = manifest
+            .products()
+            .iter()
+            .map(|product| {
+                ctx.config
+                    .paths
+                    .installation_dir
+                    .join(product.installation_id())
+            })
+            .filter(|installation| installation.is_dir())
+            .collect();
+        let sysroot = installations
+            .iter()
+            .find(|installation| {
+                installation
+                    .join("bin")
+                    .join(format!("rustc{EXE_SUFFIX}"))
+                    .is_file()
+            })
+            .or(installations.first())
+            .ok_or_else(|| Error::InstallationNotFound(project.into()))?
+            .clone();
+
+        let rust_analyzer = sysroot
+            .join("bin")
+            .join(format!("rust-analyzer{EXE_SUFFIX}"));
+        let rust_src = sysroot.join("lib/rustlib/src/rust/library");
+
+        Ok(Toolchain {
+            rust_analyzer: rust_analyzer.is_file().then_some(rust_analyzer),
+            rust_src: rust_src.is_dir().then_some(rust_src),
+            targets: installed_targets(&sysroot),
+            sysroot,
+        })
+    }
+
+    /// The `cargo` settings of rust-analyzer. Only a single target can be checked at once, so the
+    /// target is only set when the toolchain has exactly one.
+    fn cargo_settings(&self) -> Vec<(&'static str, Value)> {
+        let mut settings = vec![("sysroot", json!(self.sysroot))];
+        if let Some(rust_src) = &self.rust_src {
+            settings.push(("sysrootSrc", json!(rust_src)));
+        }
+        if let [target] = self.targets.as_slice() {
+            settings.push(("target", json!(target)));
+        }
+        settings
+    }
+
+    fn vscode_settings(&self) -> Map<String, Value> {
+        let mut settings = Map::new();
+        if let Some(rust_analyzer) = &self.rust_analyzer {
+            settings.insert("rust-analyzer.server.path".into(), json!(rust_analyzer));
+        }
+        for (key, value) in self.cargo_settings() {
+            settings.insert(format!("rust-analyzer.cargo.{key}"), value);
+        }
+        settings
+    }
+
+    /// Configuration for generic LSP clients: the paths to pass to the client, and the
+    /// `initializationOptions` to send to rust-analyzer.
+    fn lsp_config(&self) -> Value {
+        let cargo: Map<String, Value> = self
+            .cargo_settings()
+            .into_iter()
+            .map(|(key, value)| (key.into(), value))
+            .collect();
+        json!({
+            "sysroot": self.sysroot,
+            "rust-src": self.rust_src,
+            "rust-analyzer": self.rust_analyzer,
+            "targets": self.targets,
+            "initializationOptions": { "cargo": cargo },
+        })
+    }
+}
+
+/// Lists the targets whose standard library is installed in `sysroot`, each of which has a
+/// `lib/rustlib/<target>/lib` directory.
+pub(crate) fn installed_targets(sysroot: &Path) -> Vec<String> {
+    let mut targets = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(sysroot.join("lib/rustlib")) {
+        for entry in entries.flatten() {
+            if entry.path().join("lib").is_dir() {
+                targets.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+    }
+    targets.sort();
+    targets
+}
@@ -1,11 +1,21 @@
 // SPDX-FileCopyrightText: The Ferrocene Developers
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::cli::CommandExecute;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::cli::connectivity::{download_server_client, Network};
+use crate::cli::subcommand::install::Install;
+use crate::cli::subcommand::target::check_targets_available;
+use crate::cli::{is_tty, CommandExecute};
 use crate::errors::Error;
 use crate::Context;
-use clap::Parser;
-use criticalup_core::project_manifest::v1::sample_manifest;
+use clap::{Parser, ValueEnum};
+use criticalup_core::download_server_client::Connectivity;
+use criticalup_core::errors::{DownloadServerError, Error as LibError};
+use criticalup_core::project_manifest::rust_toolchain::{self, RustToolchain};
+use criticalup_core::project_manifest::{v2, ProjectManifest};
+use criticalup_core::state::State;
 use tokio::fs::File;
 use tokio::io::{AsyncWriteExt, BufWriter};
 
@@ -14,21 +24,140 @@ use tokio::io::{AsyncWriteExt, BufWriter};
 pub(crate) struct Init {
     /// Release version of Ferrocene from https://releases.ferrocene.dev/ferrocene/index.html
     #[arg(long)]
-    release: String,
+    release: Option<String>,
     /// Only print the contents of manifest instead of saving to file
     #[arg(long)]
     print: bool,
+    /// Translate the channel, components and targets of a `rust-toolchain.toml` file into
+    /// Ferrocene packages, by default the one in the current directory
+    #[arg(long, value_name = "PATH", num_args = 0..=1, conflicts_with_all = ["template", "packages", "targets"])]
+    from_rust_toolchain: Option<Option<PathBuf>>,
+    /// Set of packages to start from
+    #[arg(long, value_enum)]
+    template: Option<Template>,
+    /// Additional package to include, can be repeated
+    #[arg(long = "package", value_name = "PACKAGE")]
+    packages: Vec<String>,
+    /// Target to install the standard library for, can be repeated
+    #[arg(long = "target", value_name = "TARGET")]
+    targets: Vec<String>,
+    /// Install the toolchain right after creating the manifest
+    #[arg(long, conflicts_with = "print")]
+    install: bool,
+    #[clap(flatten)]
+    network: Network,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+enum Template {
+    /// Compiler, Cargo, Clippy, rustfmt and the sources of the standard library for the host
+    #[default]
+    Host,
+    /// The host packages, along with the standard library of the targets passed with `--target`
+    Embedded,
+    /// The host packages and the Ferrocene documentation
+    Docs,
+    /// Only the compiler, Cargo and the standard library, enough to build and test in CI
+    MinimalCi,
+}
+
+impl std::fmt::Display for Template {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let template = match self {
+            Template::Host => "host",
+            Template::Embedded => "embedded",
+            Template::Docs => "docs",
+            Template::MinimalCi => "minimal-ci",
+        };
+        write!(f, "{template}")
+    }
+}
+
+impl Template {
+    fn packages(&self) -> Vec<String> {
+        let minimal = ["cargo-${rustc-host}", "rustc-${rustc-host}"];
+        let host = [
+            "cargo-${rustc-host}",
+            "rustc-${rustc-host}",
+            "clippy-${rustc-host}",
+            "rust-std-${rustc-host}",
+            "rustfmt-${rustc-host}",
+            "rust-src",
+        ];
+        let packages: &[&str] = match self {
+            Template::Host | Template::Embedded => &host,
+            Template::Docs => &[&host[..], &["ferrocene-docs"]].concat(),
+            Template::MinimalCi => &[&minimal[..], &["rust-std-${rustc-host}"]].concat(),
+        };
+        packages.iter().map(|p| p.to_string()).collect()
+    }
 }
 
 impl CommandExecute for Init {
-    async fn execute(self, _ctx: &Context) -> Result<(), Error> {
+    #[tracing::instrument(level = "debug", skip_all)]
+    async fn execute(mut self, ctx: &Context) -> Result<(), Error> {
         let current_dir = std::env::current_dir()?;
         let manifest_file_name = "criticalup.toml".to_string();
         let manifest_path = current_dir.join(manifest_file_name);
 
-        let manifest_content = sample_manifest(self.release);
+        let interactive = is_tty(ctx, &std::io::stdin()) && is_tty(ctx, &std::io::stderr());
+
+        let manifest_content = match self.from_rust_toolchain.take() {
+            Some(path) => {
+                let path = match path {
+                    Some(path) => path,
+                    None => rust_toolchain::find(&current_dir).ok_or_else(|| {
+                        LibError::Read(
+                            current_dir.join("rust-toolchain.toml"),
+                            std::io::ErrorKind::NotFound.into(),
+                        )
+                    })?,
+                };
+                let (manifest_content, unknown_components) =
+                    RustToolchain::load(&path)?.to_manifest(&path, self.release.take())?;
+                for component in unknown_components {
+                    tracing::warn!(
+                        "Component '{component}' has no Ferrocene package and was not added"
+                    );
+                }
+                manifest_content
+            }
+            None => {
+                let release = match self.release.take() {
+                    Some(release) => release,
+                    None if interactive => prompt_release()?,
+                    None => return Err(Error::MissingRelease),
+                };
+                let template = match self.template {
+                    Some(template) => template,
+                    None if interactive => prompt_template()?,
+                    None => Template::default(),
+                };
+                if template == Template::Embedded && self.targets.is_empty() {
+                    if interactive {
+                        let targets = prompt("Targets, separated by spaces")?;
+                        self.targets = targets.split_whitespace().map(String::from).collect();
+                    }
+                    if self.targets.is_empty() {
+                        return Err(Error::TemplateRequiresTargets(template.to_string()));
+                    }
+                }
+
+                let mut packages = template.packages();
+                for package in &self.packages {
+                    if !packages.contains(package) {
+                        packages.push(package.clone());
+                    }
+                }
+                v2::manifest(release, packages, self.targets.clone())
+            }
+        };
         let manifest_content_serialized = toml_edit::ser::to_string_pretty(&manifest_content)?;
 
+        let manifest =
+            ProjectManifest::from_contents(&manifest_path, &manifest_content_serialized)?;
+        validate_release(ctx, &manifest, self.network.connectivity).await?;
+
         if self.print {
             println!("{manifest_content_serialized}");
         } else {
@@ -42,8 +171,121 @@ impl CommandExecute for Init {
                 .await?;
             manifest_file.flush().await?;
             tracing::info!("Created project manifest at {}", &manifest_path.display());
+
+            if self.install {
+                Install::for_project(manifest_path, self.network)
+                    .execute(ctx)
+                    .await?;
+            }
         }
 
         Ok(())
     }
 }
+
+/// Checks the release, packages and targets of the manifest are offered by the download server.
+/// This is skipped when offline or not authenticated, as `init` is usually run before `auth set`,
+/// and only warned about when the download server can't be reached: only a release the server
+/// does not know about is an error.
+async fn validate_release(
+    ctx: &Context,
+    manifest: &ProjectManifest,
+    connectivity: Connectivity,
+) -> Result<(), Error> {
+    let state = State::load(&ctx.config).await?;
+    if connectivity == Connectivity::Offline || state.authentication_token().await.is_none() {
+        tracing::debug!("Not validating the release, as the download server can't be used");
+        return Ok(());
+    }
+
+    for product in manifest.products() {
+        let client = download_server_client(ctx, &state, connectivity, Some(product)).await;
+
+        let release_manifest = match client
+            .product_release_manifest(product.name(), product.release())
+            .await
+        {
+            Ok(release_manifest) => release_manifest,
+            Err(LibError::DownloadServerError {
+                kind: DownloadServerError::NotFound,
+                ..
+            }) => return Err(Error::ReleaseNotFound(product.release().into())),
+            Err(err) => {
+                warn_not_validated(product.release(), &err);
+                continue;
+            }
+        };
+        let keys = match client.keys().await {
+            Ok(keys) => keys,
+            Err(err) => {
+                warn_not_validated(product.release(), &err);
+                continue;
+            }
+        };
+        let release = release_manifest.signed.into_verified(&keys)?;
+
+        check_targets_available(product.targets(), &release)?;
+        for package in product.packages() {
+            if !release.packages.iter().any(|p| &p.package == package) {
+                return Err(Error::PackageNotAvailable {
+                    package: package.clone(),
+                    release: release.release.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn warn_not_validated(release: &str, err: &LibError) {
+    tracing::warn!(
+        "Could not check release '{release}' with the download server, creating the manifest \
+        anyway: {err}"
+    );
+}
+
+fn prompt_release() -> Result<String, Error> {
+    loop {
+        let answer = prompt("Ferrocene release")?;
+        if !answer.is_empty() {
+            return Ok(answer);
+        }
+        eprintln!("a release is required, for example '25.02.0'");
+    }
+}
+
+fn prompt_template() -> Result<Template, Error> {
+    let names: Vec<String> = Template::value_variants()
+        .iter()
+        .map(|t| t.to_string())
+        .collect();
+    loop {
+        let answer = prompt(&format!(
+            "Template ({}) [{}]",
+            names.join(", "),
+            Template::default()
+        ))?;
+        if answer.is_empty() {
+            return Ok(Template::default());
+        }
+        match Template::from_str(&answer, true) {
+            Ok(template) => return Ok(template),
+            Err(_) => eprintln!("unknown template '{answer}'"),
+        }
+    }
+}
+
+fn prompt(question: &str) -> Result<String, Error> {
+    let ask = || -> Result<String, std::io::Error> {
+        let mut stderr = std::io::stderr();
+        write!(stderr, "{question}: ")?;
+        stderr.flush()?;
+
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer)? == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(answer.trim().to_string())
+    };
+    ask().map_err(Error::CantReadAnswerFromStdin)
+}
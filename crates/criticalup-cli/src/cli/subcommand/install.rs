@@ -2,9 +2,15 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use std::env::current_dir;
+use std::io::Read;
+#[cfg(not(windows))]
+use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
 
 use crate::cli::connectivity::Network;
+use crate::cli::subcommand::ide::refresh as refresh_ide_config;
+use crate::cli::subcommand::link::relink;
+use crate::cli::subcommand::target::check_targets_available;
 use crate::cli::CommandExecute;
 use crate::errors::Error;
 use crate::errors::Error::{IntegrityErrorsWhileInstallation, PackageDependenciesNotSupported};
@@ -13,11 +19,13 @@ use clap::Parser;
 use criticaltrust::integrity::IntegrityVerifier;
 use criticaltrust::manifests::{Release, ReleaseArtifactFormat};
 use criticalup_core::download_server_client::DownloadServerClient;
+use criticalup_core::object_store::ObjectStore;
 use criticalup_core::project_manifest::{ProjectManifest, ProjectManifestProduct};
 use criticalup_core::state::State;
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
 use tracing::{Instrument, Span};
+use walkdir::WalkDir;
 
 pub const DEFAULT_RELEASE_ARTIFACT_FORMAT: ReleaseArtifactFormat = ReleaseArtifactFormat::TarXz;
 
@@ -37,6 +45,18 @@ pub(crate) struct Install {
     download_server_url: Option<String>,
 }
 
+impl Install {
+    /// Installs the given project, as if running `criticalup install --project <project>`.
+    pub(crate) fn for_project(project: PathBuf, network: Network) -> Self {
+        Install {
+            project: Some(project),
+            reinstall: false,
+            network,
+            download_server_url: None,
+        }
+    }
+}
+
 impl CommandExecute for Install {
     #[tracing::instrument(level = "debug", skip_all, fields(
         project,
@@ -56,15 +76,6 @@ impl CommandExecute for Install {
 
         let state = State::load(&ctx.config).await?;
 
-        // set to mutable,  in case a new download_server_url is set.
-        let mut client = DownloadServerClient::new(&ctx.config, &state, self.network.connectivity);
-
-        if let Some(download_server_url) = self.download_server_url {
-            client.set_base_url(download_server_url);
-        };
-        // shadow binding, setting it back to immutable
-        let client = client;
-
         // Parse and serialize the project manifest.
         let project_manifest = ProjectManifest::load(&project)?;
 
@@ -73,6 +84,19 @@ impl CommandExecute for Install {
         for product in project_manifest.products() {
             let abs_installation_dir_path = installation_dir.join(product.installation_id());
 
+            // set to mutable, in case the product or a new download_server_url changes the settings.
+            let mut client =
+                DownloadServerClient::new(&ctx.config, &state, self.network.connectivity);
+            client.set_product_settings(product);
+
+            if let Some(download_server_url) = &self.download_server_url {
+                client.set_base_url(download_server_url.clone());
+            };
+
+            // shadow binding, setting it back to immutable
+            let client = client;
+            client.warn_if_token_expires_soon().await;
+
             if !abs_installation_dir_path.exists() {
                 install_product_afresh(ctx, &state, &client, &project, product).await?;
             } else {
@@ -101,6 +125,8 @@ impl CommandExecute for Install {
 
         criticalup_core::binary_proxies::update(&ctx.config, &state, &std::env::current_exe()?)
             .await?;
+        relink(ctx, &state, &project).await;
+        refresh_ide_config(ctx, &project);
 
         Ok(())
     }
@@ -112,17 +138,49 @@ impl CommandExecute for Install {
     release = %product.release(),
     product = %product.name(),
 ))]
-async fn install_product_afresh(
+pub(crate) async fn install_product_afresh(
+    ctx: &Context,
+    state: &State,
+    client: &DownloadServerClient,
+    manifest_path: &Path,
+    product: &ProjectManifestProduct,
+) -> Result<(), Error> {
+    install_product(ctx, state, client, manifest_path, product, None).await
+}
+
+/// Installs `product` by copying the existing installation of `previous` and only downloading and
+/// unpacking the packages `previous` does not have.
+#[tracing::instrument(level = "debug", skip_all, fields(
+    manifest_path = %manifest_path.display(),
+    installation_id = %product.installation_id(),
+    previous_installation_id = %previous.installation_id(),
+    release = %product.release(),
+    product = %product.name(),
+))]
+pub(crate) async fn install_product_incrementally(
+    ctx: &Context,
+    state: &State,
+    client: &DownloadServerClient,
+    manifest_path: &Path,
+    product: &ProjectManifestProduct,
+    previous: &ProjectManifestProduct,
+) -> Result<(), Error> {
+    install_product(ctx, state, client, manifest_path, product, Some(previous)).await
+}
+
+async fn install_product(
     ctx: &Context,
     state: &State,
     client: &DownloadServerClient,
     manifest_path: &Path,
     product: &ProjectManifestProduct,
+    previous: Option<&ProjectManifestProduct>,
 ) -> Result<(), Error> {
     let product_name = product.name();
     let release = product.release();
     let installation_dir = &ctx.config.paths.installation_dir;
     let abs_installation_dir_path = installation_dir.join(product.installation_id());
+    let format = product.format().unwrap_or(DEFAULT_RELEASE_ARTIFACT_FORMAT);
     let keys = client.keys().await?;
 
     tracing::info!(
@@ -142,14 +200,33 @@ async fn install_product_afresh(
     // We have to use manifest's Release because the information about dependencies
     // only lives in it and not in product's packages which is only a name/String.
     check_for_package_dependencies(&verified_release_manifest)?;
+    check_targets_available(product.targets(), &verified_release_manifest)?;
 
     let release_name = verified_release_manifest.release.as_str();
 
     product
         .create_product_dir(&ctx.config.paths.installation_dir)
         .await?;
+
+    // Every installed file along with its mode and SHA-256, to link it into the object store.
+    let mut installed_files = Vec::new();
+    let mut packages: Vec<&String> = product.packages().iter().collect();
+    if let Some(previous) = previous {
+        let previous_dir = installation_dir.join(previous.installation_id());
+        tracing::info!(
+            "Reusing the packages of installation '{}'",
+            previous.installation_id()
+        );
+        for (path, mode) in copy_installation(&previous_dir, &abs_installation_dir_path)? {
+            let buffer = &tokio::fs::read(&path).await?;
+            integrity_verifier.add(path.as_ref(), mode, buffer);
+            installed_files.push((path, mode, ObjectStore::hash(buffer)));
+        }
+        packages.retain(|package| !previous.packages().contains(package));
+    }
+
     // Finish channel must be opened in advance and be ready to rx; if not, the code remains sequential.
-    let (finish_tx, mut finish_rx) = mpsc::channel(product.packages().len());
+    let (finish_tx, mut finish_rx) = mpsc::channel(packages.len().max(1));
     let (product_name_clone, release_clone): (String, String) =
         (product_name.to_owned(), release.to_owned());
 
@@ -159,7 +236,7 @@ async fn install_product_afresh(
             tracing::info!(
                 "Installing component '{package_name}' for '{product_name_clone}' ({release_clone})",
             );
-            let files = install_one_package(&abs_installation_dir_path, package_data).await;
+            let files = install_one_package(&abs_installation_dir_path, package_data, format).await;
             finish_tx.send(files).await.map_err(|_| Error::Send("Failed to send installation complete message".into()))?;
         }
         // Tx must be dropped to indicate the end of the operation.
@@ -168,14 +245,9 @@ async fn install_product_afresh(
         Ok(())
     }.instrument(Span::current()));
 
-    for package in product.packages() {
+    for package in packages {
         let package_data = client
-            .package(
-                product_name,
-                release_name,
-                package,
-                DEFAULT_RELEASE_ARTIFACT_FORMAT,
-            )
+            .package(product_name, release_name, package, format)
             .await?;
         install_tx
             .send((package.to_owned(), package_data))
@@ -190,6 +262,7 @@ async fn install_product_afresh(
         for (path, mode) in files {
             let buffer = &tokio::fs::read(&path).await?;
             integrity_verifier.add(path.as_ref(), mode, buffer);
+            installed_files.push((path, mode, ObjectStore::hash(buffer)));
         }
     }
     handle.await??; // Ensure we exit with any odd errors.
@@ -198,6 +271,19 @@ async fn install_product_afresh(
         .verify()
         .map_err(IntegrityErrorsWhileInstallation)?;
 
+    // Only verified files enter the object store, so objects always have the contents listed in
+    // the signed package manifests. Sharing is an optimization: failing to link a file (for
+    // example on filesystems without hardlinks) leaves the installed copy in place.
+    let object_store = ObjectStore::new(&ctx.config);
+    for (path, mode, sha256) in installed_files {
+        if let Err(err) = object_store.link(&path, &sha256, mode) {
+            tracing::debug!(
+                "Not sharing {} through the object store: {err}",
+                path.display()
+            );
+        }
+    }
+
     state.add_installation(
         &product.installation_id(),
         &verified_packages,
@@ -208,6 +294,44 @@ async fn install_product_afresh(
     Ok(())
 }
 
+/// Copies the files of an existing installation into `to`, returning the copied files along with
+/// their mode. Files are hardlinked when possible, so both installations share them.
+fn copy_installation(from: &Path, to: &Path) -> Result<Vec<(PathBuf, u32)>, Error> {
+    let mut files = Vec::new();
+
+    for entry in WalkDir::new(from) {
+        let entry = entry?;
+        let relative = entry
+            .path()
+            .strip_prefix(from)
+            .expect("walkdir only yields paths inside the root");
+        let destination = to.join(relative);
+
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&destination)?;
+        } else if entry.file_type().is_symlink() {
+            #[cfg(not(windows))]
+            std::os::unix::fs::symlink(std::fs::read_link(entry.path())?, &destination)?;
+            #[cfg(windows)]
+            std::fs::copy(entry.path(), &destination)?;
+        } else {
+            if std::fs::hard_link(entry.path(), &destination).is_err() {
+                std::fs::copy(entry.path(), &destination)?;
+            }
+
+            #[cfg(not(windows))]
+            let mode = entry.metadata()?.mode();
+            // Windows does not have the same concept of permissions, we just no-op mode.
+            #[cfg(windows)]
+            let mode = 0;
+
+            files.push((destination, mode));
+        }
+    }
+
+    Ok(files)
+}
+
 fn check_for_package_dependencies(verified_release_manifest: &Release) -> Result<(), Error> {
     for package in verified_release_manifest.packages.iter() {
         if !package.dependencies.is_empty() {
@@ -220,8 +344,17 @@ fn check_for_package_dependencies(verified_release_manifest: &Release) -> Result
 async fn install_one_package(
     abs_installation_dir_path: &Path,
     package_data: Vec<u8>,
+    format: ReleaseArtifactFormat,
 ) -> Result<Vec<(PathBuf, u32)>, Error> {
-    let decoder = xz2::read::XzDecoder::new(package_data.as_slice());
+    let decoder: Box<dyn Read> = match format {
+        ReleaseArtifactFormat::TarXz => {
+            Box::new(xz2::read::XzDecoder::new(package_data.as_slice()))
+        }
+        ReleaseArtifactFormat::TarZst => {
+            Box::new(zstd::stream::read::Decoder::new(package_data.as_slice())?)
+        }
+        ReleaseArtifactFormat::Unknown => return Err(Error::UnsupportedArtifactFormat(format)),
+    };
     let mut archive = tar::Archive::new(decoder);
     archive.set_preserve_permissions(true);
     archive.set_preserve_mtime(true);
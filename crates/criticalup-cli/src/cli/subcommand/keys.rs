@@ -0,0 +1,116 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::cli::connectivity::{download_server_client, Network};
+use crate::cli::CommandExecute;
+use crate::errors::Error;
+use crate::Context;
+use clap::Parser;
+use criticaltrust::signatures::KeyStatus;
+use criticalup_core::state::State;
+use serde::Serialize;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// List the keys trusted to verify releases, and why the other keys are not trusted
+#[derive(Debug, Parser)]
+pub(crate) struct Keys {
+    #[clap(flatten)]
+    network: Network,
+}
+
+impl CommandExecute for Keys {
+    #[tracing::instrument(level = "debug", skip_all, fields(
+        %connectivity = self.network.connectivity
+    ))]
+    async fn execute(self, ctx: &Context) -> Result<(), Error> {
+        let state = State::load(&ctx.config).await?;
+        let download_server =
+            download_server_client(ctx, &state, self.network.connectivity, None).await;
+        let report = download_server.keys_report().await?;
+
+        let mut rows = vec![[
+            "KEY ID".to_string(),
+            "ROLE".to_string(),
+            "ALGORITHM".to_string(),
+            "EXPIRES AT".to_string(),
+            "STATUS".to_string(),
+        ]];
+        for entry in &report.keys {
+            let status = match &entry.status {
+                KeyStatus::TrustRoot => "trust root".into(),
+                KeyStatus::Trusted => "trusted".into(),
+                KeyStatus::Expired(_) => "expired".into(),
+                KeyStatus::Unsupported => "unsupported".into(),
+                KeyStatus::NotLoaded => "not loaded".into(),
+                KeyStatus::Untrusted(err) => format!("untrusted ({err})"),
+            };
+            rows.push(match &entry.key {
+                Some(key) => [
+                    key.calculate_id().to_string(),
+                    serde_name(&key.role),
+                    serde_name(&key.algorithm),
+                    key.expiry.map(format_time).unwrap_or_else(|| "none".into()),
+                    status,
+                ],
+                None => [
+                    "(malformed key)".into(),
+                    "-".into(),
+                    "-".into(),
+                    "-".into(),
+                    status,
+                ],
+            });
+        }
+
+        let mut widths = [0; 5];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+        for row in rows {
+            let line = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            println!("{}", line.trim_end());
+        }
+
+        if let Some(revocation_info) = &report.revocation_info {
+            eprintln!();
+            eprintln!(
+                "revocation information expires at {}",
+                format_time(revocation_info.expires_at)
+            );
+        }
+        if let Some(err) = &report.error {
+            eprintln!();
+            eprintln!("error: failed to load the keys, verifying releases will fail");
+            let mut err = err as &dyn std::error::Error;
+            eprintln!("  caused by: {err}");
+            while let Some(source) = err.source() {
+                err = source;
+                eprintln!("  caused by: {source}");
+            }
+            return Err(Error::Exit(1));
+        }
+
+        Ok(())
+    }
+}
+
+/// Name of roles and algorithms as they appear in the keys manifest.
+fn serde_name<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => "unknown".into(),
+    }
+}
+
+fn format_time(time: OffsetDateTime) -> String {
+    time.format(&Rfc3339)
+        .unwrap_or_else(|_| "(invalid time)".into())
+}
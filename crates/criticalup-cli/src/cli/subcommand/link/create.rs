@@ -1,50 +1,106 @@
 // SPDX-FileCopyrightText: The Ferrocene Developers
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use std::io::ErrorKind;
+use std::env::current_dir;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
 
+use super::rustup;
+use crate::cli::subcommand::ide::Toolchain;
 use crate::cli::CommandExecute;
 use crate::errors::Error;
 use crate::Context;
 use clap::Parser;
-use tokio::process::Command;
+use criticalup_core::project_manifest::rust_toolchain::{self, RustToolchain};
+use criticalup_core::project_manifest::ProjectManifest;
+use criticalup_core::state::{State, StateLink};
 
-/// Run a `rustup toolchain link` command to create a `ferrocene` toolchain
+/// Run a `rustup toolchain link` command to create a `ferrocene` or named toolchain
 #[derive(Debug, Parser)]
-pub(crate) struct LinkCreate;
+pub(crate) struct LinkCreate {
+    /// Name of a toolchain linked directly to the installation of the project, instead of the
+    /// `ferrocene` toolchain using the binary proxies
+    #[arg(long)]
+    name: Option<String>,
+    /// Path to the manifest `criticalup.toml`
+    #[arg(long)]
+    project: Option<PathBuf>,
+    /// Also write a `rust-toolchain.toml` next to the manifest, selecting the linked toolchain
+    #[arg(long)]
+    rust_toolchain: bool,
+}
 
 impl CommandExecute for LinkCreate {
     #[tracing::instrument(level = "debug", skip_all)]
     async fn execute(self, ctx: &Context) -> Result<(), Error> {
-        let proxy_dir = &ctx.config.paths.proxy_dir;
-
-        if !proxy_dir.exists() {
-            return Err(Error::NoProxyDirectory);
-        }
+        let project = || match &self.project {
+            Some(project) => Ok::<_, Error>(project.clone()),
+            None => Ok(ProjectManifest::discover(&current_dir()?)?),
+        };
 
-        let mut rustup_command = Command::new("rustup");
-        rustup_command.arg("toolchain");
-        rustup_command.arg("link");
-        rustup_command.arg("ferrocene");
-        rustup_command.arg(proxy_dir);
+        let name = match &self.name {
+            None => {
+                let proxy_dir = &ctx.config.paths.proxy_dir;
 
-        tracing::debug!("Running `{:?}`", rustup_command.as_std());
-        let res = rustup_command.output().await;
+                if !proxy_dir.exists() {
+                    return Err(Error::NoProxyDirectory);
+                }
 
-        match res {
-            Ok(output) if !output.status.success() => {
-                let command_string = format!("{:?}", rustup_command.as_std());
-                Err(Error::CommandExitNonzero(command_string, output))
-            }
-            Err(err) if err.kind() == ErrorKind::NotFound => Err(Error::RustupMissing),
-            Err(err) => {
-                let command_string = format!("{:?}", rustup_command.as_std());
-                Err(Error::CommandFailed(command_string, err))
-            }
-            Ok(_) => {
+                rustup([
+                    OsStr::new("toolchain"),
+                    "link".as_ref(),
+                    "ferrocene".as_ref(),
+                    proxy_dir.as_os_str(),
+                ])
+                .await?;
                 tracing::info!("You can now use `ferrocene` as a rustup toolchain, for example, `cargo +ferrocene build`");
-                Ok(())
+                "ferrocene"
+            }
+            Some(name) => {
+                let project = project()?;
+                let toolchain = Toolchain::locate(ctx, &project)?;
+                rustup([
+                    OsStr::new("toolchain"),
+                    "link".as_ref(),
+                    name.as_ref(),
+                    toolchain.sysroot.as_os_str(),
+                ])
+                .await?;
+
+                let state = State::load(&ctx.config).await?;
+                state.set_link(
+                    name,
+                    Some(StateLink {
+                        manifest: project.canonicalize()?,
+                        path: toolchain.sysroot,
+                    }),
+                );
+                state.persist().await?;
+
+                tracing::info!(
+                    "You can now use `{name}` as a rustup toolchain, for example, `cargo +{name} build`. \
+                    It is linked again whenever `criticalup install` changes the installation of {}",
+                    project.display()
+                );
+                name
             }
+        };
+
+        if self.rust_toolchain {
+            write_rust_toolchain(name, &project()?)?;
         }
+        Ok(())
     }
 }
+
+/// Writes the `rust-toolchain.toml` of the project, so that rustup selects the `name` toolchain.
+/// The components and targets are those of the installation the toolchain links to.
+fn write_rust_toolchain(name: &str, project: &Path) -> Result<(), Error> {
+    let project_dir = project.parent().unwrap_or(Path::new("."));
+    let path = rust_toolchain::find(project_dir)
+        .unwrap_or_else(|| project_dir.join("rust-toolchain.toml"));
+
+    RustToolchain::linked(name).write(&path)?;
+    tracing::info!("Wrote {}, selecting the `{name}` toolchain", path.display());
+    Ok(())
+}
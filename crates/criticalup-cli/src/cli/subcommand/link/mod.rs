@@ -5,10 +5,19 @@ mod create;
 mod remove;
 mod show;
 
+use std::ffi::OsStr;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+use crate::cli::subcommand::ide::Toolchain;
 use crate::cli::CommandExecute;
 use crate::errors::Error;
 use crate::Context;
 use clap::{Parser, Subcommand};
+use criticalup_core::project_manifest::InstallationId;
+use criticalup_core::state::{State, StateLink};
+use tokio::process::Command;
 
 use create::LinkCreate;
 use remove::LinkRemove;
@@ -38,3 +47,157 @@ impl CommandExecute for Link {
         }
     }
 }
+
+/// Runs `rustup` with the given arguments, failing if it does not exit successfully.
+async fn rustup<I, S>(args: I) -> Result<Output, Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut rustup_command = Command::new("rustup");
+    rustup_command.args(args);
+
+    tracing::debug!("Running `{:?}`", rustup_command.as_std());
+    let res = rustup_command.output().await;
+
+    match res {
+        Ok(output) if !output.status.success() => {
+            let command_string = format!("{:?}", rustup_command.as_std());
+            Err(Error::CommandExitNonzero(command_string, output))
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => Err(Error::RustupMissing),
+        Err(err) => {
+            let command_string = format!("{:?}", rustup_command.as_std());
+            Err(Error::CommandFailed(command_string, err))
+        }
+        Ok(output) => Ok(output),
+    }
+}
+
+/// Parses the output of `rustup toolchain list -v` into the name and path of each toolchain.
+pub(crate) fn parse_toolchain_list(stdout: &str) -> Vec<(String, PathBuf)> {
+    // Lines look like `ferrocene /path/to/toolchain`, optionally with markers like `(default)`
+    // between the name and the path.
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(char::is_whitespace)?;
+            let path = rest.rsplit_once(')').map_or(rest, |(_, path)| path);
+            Some((name.to_string(), PathBuf::from(path.trim())))
+        })
+        .collect()
+}
+
+/// Links the rustup toolchains created for the project to its current installation, after the
+/// installation changed.
+pub(crate) async fn relink(ctx: &Context, state: &State, project: &Path) {
+    if let Err(err) = try_relink(ctx, state, project).await {
+        tracing::warn!("Failed to link the rustup toolchains of the project again: {err}");
+    }
+}
+
+async fn try_relink(ctx: &Context, state: &State, project: &Path) -> Result<(), Error> {
+    let Ok(manifest) = project.canonicalize() else {
+        return Ok(());
+    };
+    let links: Vec<(String, StateLink)> = state
+        .links()
+        .into_iter()
+        .filter(|(_, link)| link.manifest == manifest)
+        .collect();
+    if links.is_empty() {
+        return Ok(());
+    }
+
+    let toolchain = Toolchain::locate(ctx, project)?;
+    for (name, link) in links {
+        if link.path == toolchain.sysroot {
+            continue;
+        }
+        let path = toolchain.sysroot.as_os_str();
+        rustup([
+            OsStr::new("toolchain"),
+            "link".as_ref(),
+            name.as_ref(),
+            path,
+        ])
+        .await?;
+        tracing::info!(
+            "Linked the `{name}` rustup toolchain to {}",
+            toolchain.sysroot.display()
+        );
+        state.set_link(
+            &name,
+            Some(StateLink {
+                manifest: manifest.clone(),
+                path: toolchain.sysroot.clone(),
+            }),
+        );
+    }
+    state.persist().await?;
+    Ok(())
+}
+
+/// Links the rustup toolchains created for projects to the current installation of their project
+/// after some installations were deleted, or removes them when the project is no longer installed.
+pub(crate) async fn unlink_deleted_installations(
+    ctx: &Context,
+    state: &State,
+    deleted: &[InstallationId],
+) {
+    if let Err(err) = try_unlink_deleted_installations(ctx, state, deleted).await {
+        tracing::warn!(
+            "Failed to update the rustup toolchains of the deleted installations: {err}"
+        );
+    }
+}
+
+async fn try_unlink_deleted_installations(
+    ctx: &Context,
+    state: &State,
+    deleted: &[InstallationId],
+) -> Result<(), Error> {
+    let installation_dir = &ctx.config.paths.installation_dir;
+    for (name, link) in state.links() {
+        if !deleted
+            .iter()
+            .any(|id| link.path == installation_dir.join(&id.0))
+        {
+            continue;
+        }
+
+        match Toolchain::locate(ctx, &link.manifest) {
+            Ok(toolchain) => {
+                let path = toolchain.sysroot.as_os_str();
+                rustup([
+                    OsStr::new("toolchain"),
+                    "link".as_ref(),
+                    name.as_ref(),
+                    path,
+                ])
+                .await?;
+                tracing::info!(
+                    "Linked the `{name}` rustup toolchain to {}",
+                    toolchain.sysroot.display()
+                );
+                state.set_link(
+                    &name,
+                    Some(StateLink {
+                        manifest: link.manifest,
+                        path: toolchain.sysroot,
+                    }),
+                );
+            }
+            Err(_) => {
+                rustup(["toolchain", "remove", &name]).await?;
+                tracing::info!(
+                    "Removed the `{name}` rustup toolchain, as the installation it linked to was \
+                    deleted"
+                );
+                state.set_link(&name, None);
+            }
+        }
+        state.persist().await?;
+    }
+    Ok(())
+}
@@ -1,51 +1,42 @@
 // SPDX-FileCopyrightText: The Ferrocene Developers
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use std::io::ErrorKind;
-
+use super::rustup;
 use crate::cli::CommandExecute;
 use crate::errors::Error;
 use crate::Context;
 use clap::Parser;
-use tokio::process::Command;
+use criticalup_core::state::State;
 
-/// Run a `rustup toolchain link` command to remove any `ferrocene` toolchain
+/// Run a `rustup toolchain remove` command to remove the `ferrocene` or a named toolchain
 #[derive(Debug, Parser)]
-pub(crate) struct LinkRemove;
+pub(crate) struct LinkRemove {
+    /// Name of the toolchain to remove
+    #[arg(long, default_value = "ferrocene")]
+    name: String,
+}
 
 impl CommandExecute for LinkRemove {
     #[tracing::instrument(level = "debug", skip_all)]
     async fn execute(self, ctx: &Context) -> Result<(), Error> {
-        let proxy_dir = &ctx.config.paths.proxy_dir;
+        let state = State::load(&ctx.config).await?;
+        let is_named_link = state.links().contains_key(&self.name);
 
-        if !proxy_dir.exists() {
+        if !is_named_link && !ctx.config.paths.proxy_dir.exists() {
             return Err(Error::NoProxyDirectory);
         }
 
-        let mut rustup_command = Command::new("rustup");
-        rustup_command.arg("toolchain");
-        rustup_command.arg("remove");
-        rustup_command.arg("ferrocene");
+        rustup(["toolchain", "remove", &self.name]).await?;
 
-        tracing::debug!("Running `{:?}`", rustup_command.as_std());
-        let res = rustup_command.output().await;
-
-        match res {
-            Ok(output) if !output.status.success() => {
-                let command_string = format!("{:?}", rustup_command.as_std());
-                Err(Error::CommandExitNonzero(command_string, output))
-            }
-            Err(err) if err.kind() == ErrorKind::NotFound => Err(Error::RustupMissing),
-            Err(err) => {
-                let command_string = format!("{:?}", rustup_command.as_std());
-                Err(Error::CommandFailed(command_string, err))
-            }
-            Ok(_) => {
-                tracing::info!(
-                    "The `ferrocene` rustup toolchain has been removed, or did not exist"
-                );
-                Ok(())
-            }
+        if is_named_link {
+            state.set_link(&self.name, None);
+            state.persist().await?;
         }
+
+        tracing::info!(
+            "The `{}` rustup toolchain has been removed, or did not exist",
+            self.name
+        );
+        Ok(())
     }
 }
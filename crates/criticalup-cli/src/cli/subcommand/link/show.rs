@@ -1,12 +1,15 @@
 // SPDX-FileCopyrightText: The Ferrocene Developers
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use super::{parse_toolchain_list, rustup};
+use crate::cli::subcommand::ide::installed_targets;
 use crate::cli::CommandExecute;
 use crate::errors::Error;
 use crate::Context;
 use clap::Parser;
+use criticalup_core::state::State;
 
-/// Output the path of the binary proxies
+/// Output the path of the binary proxies, and list the linked rustup toolchains
 #[derive(Debug, Parser)]
 pub(crate) struct LinkShow;
 
@@ -20,6 +23,29 @@ impl CommandExecute for LinkShow {
         }
 
         println!("{}", proxy_dir.display());
+
+        // The list goes to stderr, keeping stdout usable for `PATH`.
+        let state = State::load(&ctx.config).await?;
+        let proxy_link = match rustup(["toolchain", "list", "-v"]).await {
+            Ok(output) => parse_toolchain_list(&String::from_utf8_lossy(&output.stdout))
+                .into_iter()
+                .find(|(_, path)| path == proxy_dir),
+            Err(err) => {
+                tracing::debug!("Listing the rustup toolchains failed: {err}");
+                None
+            }
+        };
+        if let Some((name, _)) = proxy_link {
+            eprintln!("{name}: binary proxies, using the project of the current directory");
+        }
+        for (name, link) in state.links() {
+            eprintln!("{name}: {}", link.path.display());
+            eprintln!("  project: {}", link.manifest.display());
+            let targets = installed_targets(&link.path);
+            if !targets.is_empty() {
+                eprintln!("  targets: {}", targets.join(", "));
+            }
+        }
         Ok(())
     }
 }
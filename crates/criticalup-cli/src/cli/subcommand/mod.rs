@@ -5,24 +5,36 @@ use archive::Archive;
 use auth::Auth;
 use clap::Subcommand;
 use clean::Clean;
+use component::Component;
 use doc::Doc;
+use doctor::Doctor;
+use env::Env;
+use ide::Ide;
 use init::Init;
 use install::Install;
+use keys::Keys;
 use link::Link;
 use remove::Remove;
 use run::Run;
+use target::Target;
 use verify::Verify;
 use which::Which;
 
 pub(crate) mod archive;
 pub(crate) mod auth;
 pub(crate) mod clean;
+pub(crate) mod component;
 pub(crate) mod doc;
+pub(crate) mod doctor;
+pub(crate) mod env;
+pub(crate) mod ide;
 pub(crate) mod init;
 pub(crate) mod install;
+pub(crate) mod keys;
 pub(crate) mod link;
 pub(crate) mod remove;
 pub(crate) mod run;
+pub(crate) mod target;
 pub(crate) mod verify;
 pub(crate) mod which;
 
@@ -31,12 +43,18 @@ pub(crate) enum CriticalupSubcommand {
     Archive(Archive),
     Auth(Auth),
     Clean(Clean),
+    Component(Component),
     Doc(Doc),
+    Doctor(Doctor),
+    Env(Env),
+    Ide(Ide),
     Init(Init),
     Install(Install),
+    Keys(Keys),
     Link(Link),
     Remove(Remove),
     Run(Run),
+    Target(Target),
     Verify(Verify),
     Which(Which),
 }
@@ -1,6 +1,7 @@
 // SPDX-FileCopyrightText: The Ferrocene Developers
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use crate::cli::subcommand::link::unlink_deleted_installations;
 use crate::cli::CommandExecute;
 use crate::errors::Error;
 use crate::Context;
@@ -49,6 +50,8 @@ impl CommandExecute for Remove {
         if installations_from_which_manifest_was_deleted.is_empty() {
             tracing::info!("No existing installations found to be deleted",);
         }
+        unlink_deleted_installations(ctx, &state, &installations_from_which_manifest_was_deleted)
+            .await;
 
         Ok(())
     }
@@ -0,0 +1,182 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::env::current_dir;
+use std::path::PathBuf;
+
+use crate::cli::connectivity::{download_server_client, Network};
+use crate::cli::CommandExecute;
+use crate::errors::Error;
+use crate::Context;
+use clap::{Parser, Subcommand};
+use criticaltrust::manifests::Release;
+use criticalup_core::project_manifest::{target_package, ProjectManifest, ProjectManifestEditor};
+use criticalup_core::state::State;
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum TargetSubcommand {
+    Add(TargetAdd),
+    Remove(TargetRemove),
+    List(TargetList),
+}
+
+/// Manage the compilation targets of the manifest `criticalup.toml`
+#[derive(Debug, Parser)]
+pub(crate) struct Target {
+    #[command(subcommand)]
+    command: TargetSubcommand,
+}
+
+impl CommandExecute for Target {
+    #[tracing::instrument(level = "debug", skip_all)]
+    async fn execute(self, ctx: &Context) -> Result<(), Error> {
+        match self.command {
+            TargetSubcommand::Add(add) => add.execute(ctx).await,
+            TargetSubcommand::Remove(remove) => remove.execute(ctx).await,
+            TargetSubcommand::List(list) => list.execute(ctx).await,
+        }
+    }
+}
+
+/// Checks that the release offers the standard library of every target.
+pub(crate) fn check_targets_available<'a>(
+    targets: impl IntoIterator<Item = &'a String>,
+    release: &Release,
+) -> Result<(), Error> {
+    for target in targets {
+        let package = target_package(target);
+        if !release.packages.iter().any(|p| p.package == package) {
+            return Err(Error::TargetNotAvailable {
+                target: target.clone(),
+                release: release.release.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct TargetAdd {
+    /// Targets to add, for example `aarch64-unknown-none`
+    #[arg(required = true)]
+    targets: Vec<String>,
+    /// Path to the manifest `criticalup.toml`
+    #[arg(long)]
+    project: Option<PathBuf>,
+    /// Product to add the targets to, instead of the first one of the manifest
+    #[arg(long)]
+    product: Option<String>,
+    #[clap(flatten)]
+    network: Network,
+}
+
+impl CommandExecute for TargetAdd {
+    #[tracing::instrument(level = "debug", skip_all)]
+    async fn execute(self, ctx: &Context) -> Result<(), Error> {
+        let project = match self.project {
+            Some(project) => project,
+            None => ProjectManifest::discover(&current_dir()?)?,
+        };
+
+        let mut editor = ProjectManifestEditor::open(&project)?;
+        let product_name = editor.product_name(self.product.as_deref())?;
+
+        // Validate the targets against the release before touching the manifest.
+        let project_manifest = ProjectManifest::load(&project)?;
+        if let Some(product) = project_manifest
+            .products()
+            .iter()
+            .find(|p| p.name() == product_name)
+        {
+            let state = State::load(&ctx.config).await?;
+            let client =
+                download_server_client(ctx, &state, self.network.connectivity, Some(product)).await;
+
+            let keys = client.keys().await?;
+            let release = client
+                .product_release_manifest(product.name(), product.release())
+                .await?
+                .signed
+                .into_verified(&keys)?;
+            check_targets_available(&self.targets, &release)?;
+        }
+
+        editor.upgrade_version();
+        for target in &self.targets {
+            if editor.add_to_list(&product_name, "targets", target)? {
+                tracing::info!("Added target '{target}' to product '{product_name}'");
+            } else {
+                tracing::info!("Target '{target}' is already part of product '{product_name}'");
+            }
+        }
+        // Fails if the packages use `${target}` and no longer have a single target, before the
+        // manifest is saved.
+        editor.manifest()?;
+        editor.save()?;
+
+        tracing::info!("Run 'criticalup install' to install the new targets.");
+        Ok(())
+    }
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct TargetRemove {
+    /// Targets to remove, for example `aarch64-unknown-none`
+    #[arg(required = true)]
+    targets: Vec<String>,
+    /// Path to the manifest `criticalup.toml`
+    #[arg(long)]
+    project: Option<PathBuf>,
+    /// Product to remove the targets from, instead of the first one of the manifest
+    #[arg(long)]
+    product: Option<String>,
+}
+
+impl CommandExecute for TargetRemove {
+    #[tracing::instrument(level = "debug", skip_all)]
+    async fn execute(self, _ctx: &Context) -> Result<(), Error> {
+        let project = match self.project {
+            Some(project) => project,
+            None => ProjectManifest::discover(&current_dir()?)?,
+        };
+
+        let mut editor = ProjectManifestEditor::open(&project)?;
+        let product_name = editor.product_name(self.product.as_deref())?;
+
+        for target in &self.targets {
+            if editor.remove_from_list(&product_name, "targets", target)? {
+                tracing::info!("Removed target '{target}' from product '{product_name}'");
+            } else {
+                tracing::warn!("Target '{target}' is not part of product '{product_name}'");
+            }
+        }
+        // Fails if the packages use `${target}` or `${targets}` and no target is left, before the
+        // manifest is saved.
+        editor.manifest()?;
+        editor.save()?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct TargetList {
+    /// Path to the manifest `criticalup.toml`
+    #[arg(long)]
+    project: Option<PathBuf>,
+}
+
+impl CommandExecute for TargetList {
+    #[tracing::instrument(level = "debug", skip_all)]
+    async fn execute(self, _ctx: &Context) -> Result<(), Error> {
+        let project_manifest = ProjectManifest::get(self.project).await?;
+
+        for product in project_manifest.products() {
+            for target in product.targets() {
+                println!("{target}");
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -11,7 +11,6 @@ use std::{
 use clap::Parser;
 use criticaltrust::{integrity::IntegrityVerifier, signatures::Keychain};
 use criticalup_core::{
-    download_server_client::DownloadServerClient,
     project_manifest::{ProjectManifest, ProjectManifestProduct},
     state::State,
 };
@@ -19,7 +18,10 @@ use tracing::Span;
 use walkdir::WalkDir;
 
 use crate::{
-    cli::{connectivity::Network, CommandExecute},
+    cli::{
+        connectivity::{download_server_client, Network},
+        CommandExecute,
+    },
     errors::Error,
     Context,
 };
@@ -50,28 +52,29 @@ impl CommandExecute for Verify {
         span.record("project", tracing::field::display(project.display()));
 
         let state = State::load(&ctx.config).await?;
-        let client: DownloadServerClient =
-            DownloadServerClient::new(&ctx.config, &state, self.network.connectivity);
-        let keys = client.keys().await?;
-
         let project_manifest = ProjectManifest::load(&project)?;
 
+        // Each product may come from a different download server, trusting different keys.
+        let mut products = Vec::with_capacity(project_manifest.products().len());
+        for product in project_manifest.products() {
+            let client =
+                download_server_client(ctx, &state, self.network.connectivity, Some(product)).await;
+            products.push((product, client.keys().await?));
+        }
+
         let installation_dir = &ctx.config.paths.installation_dir;
 
-        verify(&keys, installation_dir, &project_manifest).await
+        verify(&products, installation_dir).await
     }
 }
 
 async fn verify(
-    keys: &Keychain,
+    products: &[(&ProjectManifestProduct, Keychain)],
     installation_dir: &Path,
-    project_manifest: &ProjectManifest,
 ) -> Result<(), Error> {
-    let products = project_manifest.products();
-
     // We don't actually care the order of verification of products, simply that they are verified.
     let mut working_set = Vec::with_capacity(products.len());
-    for product in project_manifest.products() {
+    for (product, keys) in products {
         working_set.push(verify_product(keys, installation_dir, product));
     }
     futures::future::join_all(working_set)
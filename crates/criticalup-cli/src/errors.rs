@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use criticaltrust::integrity::IntegrityError;
+use criticaltrust::manifests::ReleaseArtifactFormat;
 pub(crate) use criticaltrust::Error as TrustError;
 pub(crate) use criticalup_core::errors::BinaryProxyUpdateError;
 pub(crate) use criticalup_core::errors::Error as LibError;
@@ -40,6 +41,12 @@ pub enum Error {
     CantReadTokenFromStdin(#[source] std::io::Error),
     #[error("Invalid authentication token provided.")]
     InvalidAuthenticationToken,
+    #[error("The authentication token provided has expired.")]
+    ExpiredAuthenticationToken,
+    #[error("The authentication token provided has been revoked.")]
+    RevokedAuthenticationToken,
+    #[error("The authentication token provided is malformed.")]
+    MalformedAuthenticationToken,
 
     #[error("Some files did not pass the integrity checks after the download.\n \
         Please clean your installation directory and re-install the project again.\n \
@@ -101,6 +108,24 @@ pub enum Error {
     )]
     PackageDependenciesNotSupported(String),
 
+    #[error("Release '{0}' does not exist on the download server.")]
+    ReleaseNotFound(String),
+    #[error("The '{0}' template needs at least one target; please pass them with `--target`.")]
+    TemplateRequiresTargets(String),
+    #[error("The release is required; please pass it with `--release`.")]
+    MissingRelease,
+    #[error("Failed to read the answer from stdin.")]
+    CantReadAnswerFromStdin(#[source] std::io::Error),
+
+    #[error("Package '{package}' is not available in release '{release}' of the product.")]
+    PackageNotAvailable { package: String, release: String },
+
+    #[error("Target '{target}' is not available in release '{release}' of the product.")]
+    TargetNotAvailable { target: String, release: String },
+
+    #[error("Packages in the '{0}' format cannot be installed by this criticalup release.")]
+    UnsupportedArtifactFormat(ReleaseArtifactFormat),
+
     #[error("There was an error while trying to delete the unused installation directory at {}.", path.display())]
     DeletingUnusedInstallationDir {
         path: PathBuf,
@@ -115,6 +140,30 @@ pub enum Error {
         kind: std::io::Error,
     },
 
+    #[error("There was an error while trying to delete the cached artifact at {}.", path.display())]
+    DeletingCachedArtifact {
+        path: PathBuf,
+        #[source]
+        kind: std::io::Error,
+    },
+
+    #[error("There was an error while trying to delete the unused object at {}.", path.display())]
+    DeletingUnusedObject {
+        path: PathBuf,
+        #[source]
+        kind: std::io::Error,
+    },
+
+    #[error(
+        "The editor settings at {} are not a JSON object, please merge the output of \
+    'criticalup ide' into them manually.", path.display()
+    )]
+    InvalidEditorSettings {
+        path: PathBuf,
+        #[source]
+        kind: Option<serde_json::Error>,
+    },
+
     #[error("Parsing tracing directives.")]
     EnvFilter(
         #[from]
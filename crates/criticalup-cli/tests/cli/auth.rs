@@ -3,6 +3,7 @@
 
 use crate::assert_output;
 use crate::utils::{TestEnvironment, MOCK_AUTH_TOKENS};
+use mock_download_server::TokenRejection;
 
 #[tokio::test]
 async fn help_message() {
@@ -28,6 +29,18 @@ async fn invalid_token() {
     assert_eq!(2, test_env.requests_served_by_mock_download_server().await);
 }
 
+#[tokio::test]
+async fn revoked_token() {
+    let test_env = TestEnvironment::prepare().await;
+    set_token(&test_env, MOCK_AUTH_TOKENS[2].0).await;
+    test_env
+        .reject_token(MOCK_AUTH_TOKENS[2].0, TokenRejection::Revoked)
+        .await;
+
+    assert_output!(test_env.cmd().arg("auth"));
+    assert_eq!(2, test_env.requests_served_by_mock_download_server().await);
+}
+
 #[tokio::test]
 async fn token_without_expiry() {
     let test_env = TestEnvironment::prepare().await;
@@ -0,0 +1,41 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::assert_output;
+use crate::utils::{TestEnvironment, MOCK_AUTH_TOKENS};
+
+#[tokio::test]
+async fn help_message() {
+    let test_env = TestEnvironment::prepare().await;
+    assert_output!(test_env.cmd().args(["auth", "list", "--help"]));
+}
+
+#[tokio::test]
+async fn no_tokens() {
+    let test_env = TestEnvironment::prepare().await;
+
+    assert_output!(test_env.cmd().args(["auth", "list"]));
+    assert_eq!(0, test_env.requests_served_by_mock_download_server().await);
+}
+
+#[tokio::test]
+async fn default_and_named_profiles() {
+    let test_env = TestEnvironment::prepare().await;
+    set_token(&test_env, MOCK_AUTH_TOKENS[0].0, None).await;
+    set_token(&test_env, MOCK_AUTH_TOKENS[1].0, Some("acme")).await;
+    set_token(&test_env, MOCK_AUTH_TOKENS[2].0, Some("beta")).await;
+    test_env.revoke_token(MOCK_AUTH_TOKENS[2].0).await;
+
+    assert_output!(test_env.cmd().args(["auth", "list"]));
+    assert_eq!(6, test_env.requests_served_by_mock_download_server().await);
+}
+
+async fn set_token(test_env: &TestEnvironment, token: &str, profile: Option<&str>) {
+    let mut cmd = test_env.cmd();
+    cmd.args(["auth", "set", token]);
+    if let Some(profile) = profile {
+        cmd.args(["--profile", profile]);
+    }
+    let output = cmd.output().await.expect("failed to set the token");
+    assert!(output.status.success());
+}
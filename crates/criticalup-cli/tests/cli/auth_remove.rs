@@ -47,3 +47,46 @@ async fn token_present() {
             .unwrap();
     assert!(state.authentication_token.is_none());
 }
+
+#[tokio::test]
+async fn profile_token_present() {
+    #[derive(Deserialize)]
+    struct State {
+        authentication_token: Option<String>,
+        #[serde(default)]
+        authentication_profiles: std::collections::BTreeMap<String, String>,
+    }
+
+    let test_env = TestEnvironment::prepare().await;
+    for args in [
+        vec!["auth", "set", MOCK_AUTH_TOKENS[0].0],
+        vec!["auth", "set", "--profile", "acme", MOCK_AUTH_TOKENS[1].0],
+    ] {
+        assert!(test_env
+            .cmd()
+            .args(args)
+            .output()
+            .await
+            .expect("failed to set token")
+            .status
+            .success());
+    }
+
+    assert!(test_env
+        .cmd()
+        .args(["auth", "remove", "--profile", "acme"])
+        .output()
+        .await
+        .expect("failed to remove token")
+        .status
+        .success());
+
+    let state: State =
+        serde_json::from_slice(&std::fs::read(test_env.root().join("state.json")).unwrap())
+            .unwrap();
+    assert_eq!(
+        Some(MOCK_AUTH_TOKENS[0].0),
+        state.authentication_token.as_deref()
+    );
+    assert!(state.authentication_profiles.is_empty());
+}
@@ -181,6 +181,65 @@ fn build_command(test_env: &TestEnvironment, variant: Variant, token: &str) -> C
     cmd
 }
 
+#[tokio::test]
+async fn set_token_in_profile() {
+    #[derive(Deserialize)]
+    struct State {
+        authentication_token: Option<String>,
+        authentication_profiles: std::collections::BTreeMap<String, String>,
+    }
+
+    let test_env = TestEnvironment::prepare().await;
+    set_token(&test_env, TOKEN_A).await;
+
+    let out = test_env
+        .cmd()
+        .args(["auth", "set", "--profile", "acme", TOKEN_B])
+        .output()
+        .await
+        .unwrap();
+    assert!(out.status.success());
+
+    let state: State =
+        serde_json::from_slice(&std::fs::read(test_env.root().join("state.json")).unwrap())
+            .unwrap();
+    assert_eq!(Some(TOKEN_A), state.authentication_token.as_deref());
+    assert_eq!(
+        Some(TOKEN_B),
+        state
+            .authentication_profiles
+            .get("acme")
+            .map(|t| t.as_str())
+    );
+}
+
+#[tokio::test]
+async fn set_invalid_token_in_profile_with_env_token() {
+    #[derive(Deserialize)]
+    struct State {
+        #[serde(default)]
+        authentication_profiles: std::collections::BTreeMap<String, String>,
+    }
+
+    let test_env = TestEnvironment::prepare().await;
+    set_token(&test_env, TOKEN_A).await;
+
+    // The token being set is validated, not the one in the environment.
+    let out = test_env
+        .cmd()
+        .args(["auth", "set", "--profile", "acme", TOKEN_INVALID])
+        .env("CRITICALUP_TOKEN", TOKEN_B)
+        .output()
+        .await
+        .unwrap();
+    assert!(!out.status.success());
+
+    let state: State =
+        serde_json::from_slice(&std::fs::read(test_env.root().join("state.json")).unwrap())
+            .unwrap();
+    assert!(state.authentication_profiles.is_empty());
+}
+
 #[track_caller]
 fn assert_token(test_env: &TestEnvironment, expected: Option<&str>) {
     #[derive(Deserialize)]
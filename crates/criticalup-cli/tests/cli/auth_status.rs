@@ -0,0 +1,121 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::assert_output;
+use crate::utils::{TestEnvironment, MOCK_AUTH_TOKENS};
+use mock_download_server::TokenRejection;
+
+#[tokio::test]
+async fn help_message() {
+    let test_env = TestEnvironment::prepare().await;
+    assert_output!(test_env.cmd().args(["auth", "status", "--help"]));
+}
+
+#[tokio::test]
+async fn no_token() {
+    let test_env = TestEnvironment::prepare().await;
+
+    assert_output!(test_env.cmd().args(["auth", "status"]));
+    assert_eq!(0, test_env.requests_served_by_mock_download_server().await);
+}
+
+#[tokio::test]
+async fn missing_profile() {
+    let test_env = TestEnvironment::prepare().await;
+    set_token(&test_env, MOCK_AUTH_TOKENS[0].0).await;
+
+    assert_output!(test_env.cmd().args(["auth", "status", "--profile", "acme"]));
+    assert_eq!(1, test_env.requests_served_by_mock_download_server().await);
+}
+
+#[tokio::test]
+async fn profile_with_env_token() {
+    let test_env = TestEnvironment::prepare().await;
+    let output = test_env
+        .cmd()
+        .args(["auth", "set", "--profile", "acme", MOCK_AUTH_TOKENS[1].0])
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+
+    // The profile is checked instead of the valid token in the environment.
+    assert_output!(test_env
+        .cmd()
+        .args(["auth", "status", "--profile", "acme"])
+        .env("CRITICALUP_TOKEN", MOCK_AUTH_TOKENS[0].0));
+    assert_output!(test_env
+        .cmd()
+        .args(["auth", "status", "--profile", "missing"])
+        .env("CRITICALUP_TOKEN", MOCK_AUTH_TOKENS[0].0));
+}
+
+#[tokio::test]
+async fn valid_token() {
+    let test_env = TestEnvironment::prepare().await;
+    set_token(&test_env, MOCK_AUTH_TOKENS[0].0).await;
+
+    assert_output!(test_env.cmd().args(["auth", "status"]));
+}
+
+#[tokio::test]
+async fn token_expires_soon() {
+    let test_env = TestEnvironment::prepare().await;
+    set_token(&test_env, MOCK_AUTH_TOKENS[3].0).await;
+
+    assert_output!(test_env.cmd().args(["auth", "status"]));
+    assert_output!(test_env
+        .cmd()
+        .args(["auth", "status", "--warn-days", "1000000"]));
+}
+
+#[tokio::test]
+async fn token_past_expiry() {
+    let test_env = TestEnvironment::prepare().await;
+    set_token(&test_env, MOCK_AUTH_TOKENS[1].0).await;
+
+    assert_output!(test_env.cmd().args(["auth", "status"]));
+}
+
+#[tokio::test]
+async fn token_rejected_as_expired() {
+    let test_env = TestEnvironment::prepare().await;
+    set_token(&test_env, MOCK_AUTH_TOKENS[2].0).await;
+    test_env
+        .reject_token(MOCK_AUTH_TOKENS[2].0, TokenRejection::Expired)
+        .await;
+
+    assert_output!(test_env.cmd().args(["auth", "status"]));
+}
+
+#[tokio::test]
+async fn token_rejected_as_revoked() {
+    let test_env = TestEnvironment::prepare().await;
+    set_token(&test_env, MOCK_AUTH_TOKENS[2].0).await;
+    test_env
+        .reject_token(MOCK_AUTH_TOKENS[2].0, TokenRejection::Revoked)
+        .await;
+
+    assert_output!(test_env.cmd().args(["auth", "status"]));
+}
+
+#[tokio::test]
+async fn token_rejected_as_malformed() {
+    let test_env = TestEnvironment::prepare().await;
+    set_token(&test_env, MOCK_AUTH_TOKENS[2].0).await;
+    test_env
+        .reject_token(MOCK_AUTH_TOKENS[2].0, TokenRejection::Malformed)
+        .await;
+
+    assert_output!(test_env.cmd().args(["auth", "status"]));
+}
+
+async fn set_token(test_env: &TestEnvironment, token: &str) {
+    let output = test_env
+        .cmd()
+        .args(["auth", "set", token])
+        .output()
+        .await
+        .expect("failed to set the token");
+    assert!(output.status.success());
+}
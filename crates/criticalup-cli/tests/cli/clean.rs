@@ -2,12 +2,15 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::assert_output;
-use crate::utils::TestEnvironment;
+use crate::utils::{project_manifests, publish_release, TestEnvironment, PROJECT_MANIFEST};
 use serde_json::{json, Value};
 use std::fs;
 use std::fs::File;
 use std::io::{BufReader, Write};
 use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+use tokio::process::Command;
 
 #[tokio::test]
 async fn help_message() {
@@ -21,7 +24,7 @@ async fn clean_deletes_only_unused_installations() {
     let root = test_env.root();
     let toolchains_dir = root.join("toolchains");
     fs::create_dir_all(&toolchains_dir).unwrap();
-    let installation_id_1 = "installation_id_1";
+    let (installation_id_1, manifests) = project_manifests(&test_env);
     let installation_id_2 = "installation_id_2";
     let installation_id_3 = "installation_id_3";
 
@@ -32,14 +35,11 @@ async fn clean_deletes_only_unused_installations() {
         "version": 1,
         "authentication_token": "criticalup_token_45_hahaha",
         "installations": {
-            installation_id_1: {
+            &installation_id_1: {
                 "binary_proxies": {
                     "cargo": "/path/toolchains/bin/cargo"
                 },
-                "manifests": [
-                    "/path/to/proj/1/criticalup.toml",
-                    "/path/to/proj/2/criticalup.toml"
-                ]
+                "manifests": manifests
             },
             installation_id_2: {
                 "binary_proxies": {
@@ -58,11 +58,11 @@ async fn clean_deletes_only_unused_installations() {
     .to_string();
     state_file.write_all(content.as_bytes()).unwrap();
 
-    fs::create_dir_all(toolchains_dir.join(installation_id_1)).unwrap();
+    fs::create_dir_all(toolchains_dir.join(&installation_id_1)).unwrap();
     fs::create_dir_all(toolchains_dir.join(installation_id_2)).unwrap();
     fs::create_dir_all(toolchains_dir.join(installation_id_3)).unwrap();
 
-    assert!(toolchains_dir.join(installation_id_1).exists());
+    assert!(toolchains_dir.join(&installation_id_1).exists());
     assert!(toolchains_dir.join(installation_id_2).exists());
     assert!(toolchains_dir.join(installation_id_3).exists());
 
@@ -92,10 +92,7 @@ async fn clean_deletes_only_unused_installations() {
             "binary_proxies": {
                 "cargo": "/path/toolchains/bin/cargo"
             },
-            "manifests": [
-                "/path/to/proj/1/criticalup.toml",
-                "/path/to/proj/2/criticalup.toml"
-            ]
+            "manifests": manifests
         })
     );
 }
@@ -107,7 +104,7 @@ async fn clean_deletes_only_unused_installations_also_from_disk() {
     let toolchains_dir = root.join("toolchains");
     fs::create_dir_all(&toolchains_dir).unwrap();
 
-    let installation_id_1 = "installation_id_1";
+    let (installation_id_1, manifests) = project_manifests(&test_env);
     let installation_id_2 = "installation_id_2";
     let installation_id_3 = "installation_id_3";
 
@@ -117,14 +114,11 @@ async fn clean_deletes_only_unused_installations_also_from_disk() {
         "version": 1,
         "authentication_token": "criticalup_token_45_hahaha",
         "installations": {
-            installation_id_1: {
+            &installation_id_1: {
                 "binary_proxies": {
                     "cargo": "/path/toolchains/bin/cargo"
                 },
-                "manifests": [
-                    "/path/to/proj/1/criticalup.toml",
-                    "/path/to/proj/2/criticalup.toml"
-                ]
+                "manifests": manifests
             },
             installation_id_2: {
                 "binary_proxies": {
@@ -145,11 +139,11 @@ async fn clean_deletes_only_unused_installations_also_from_disk() {
 
     // Create the corresponding physical directories of installations.
     // TODO: We have to generate these by running `install` command, once tests for those are setup.
-    fs::create_dir_all(toolchains_dir.join(installation_id_1)).unwrap();
+    fs::create_dir_all(toolchains_dir.join(&installation_id_1)).unwrap();
     fs::create_dir_all(toolchains_dir.join(installation_id_2)).unwrap();
     fs::create_dir_all(toolchains_dir.join(installation_id_3)).unwrap();
 
-    assert!(toolchains_dir.join(installation_id_1).exists());
+    assert!(toolchains_dir.join(&installation_id_1).exists());
     assert!(toolchains_dir.join(installation_id_2).exists());
     assert!(toolchains_dir.join(installation_id_3).exists());
 
@@ -174,20 +168,17 @@ async fn clean_deletes_only_unused_installations_also_from_disk() {
     // "installation_id_1" is still present with correct values.
     assert_eq!(
         state_file_actual
-            .pointer("/installations/installation_id_1")
+            .pointer(format!("/installations/{installation_id_1}").as_str())
             .unwrap(),
         &json!({
             "binary_proxies": {
                 "cargo": "/path/toolchains/bin/cargo"
             },
-            "manifests": [
-                "/path/to/proj/1/criticalup.toml",
-                "/path/to/proj/2/criticalup.toml"
-            ]
+            "manifests": manifests
         })
     );
 
-    assert!(toolchains_dir.join(installation_id_1).exists());
+    assert!(toolchains_dir.join(&installation_id_1).exists());
     assert!(!toolchains_dir.join(installation_id_2).exists()); // Does not exist.
     assert!(!toolchains_dir.join(installation_id_3).exists()); // Does not exist.
 }
@@ -199,7 +190,7 @@ async fn removes_unused_installations_from_disk_that_do_not_have_state() {
     let toolchains_dir = root.join("toolchains");
     fs::create_dir_all(&toolchains_dir).unwrap();
 
-    let installation_id_1 = "installation_id_1";
+    let (installation_id_1, manifests) = project_manifests(&test_env);
     let installation_id_2 = "installation_id_2";
     let installation_id_3 = "installation_id_3"; // No State, only directory
 
@@ -209,14 +200,11 @@ async fn removes_unused_installations_from_disk_that_do_not_have_state() {
         "version": 1,
         "authentication_token": "criticalup_token_45_hahaha",
         "installations": {
-            installation_id_1: {
+            &installation_id_1: {
                 "binary_proxies": {
                     "cargo": "/path/toolchains/bin/cargo"
                 },
-                "manifests": [
-                    "/path/to/proj/1/criticalup.toml",
-                    "/path/to/proj/2/criticalup.toml"
-                ]
+                "manifests": manifests
             },
             installation_id_2: {
                 "binary_proxies": {
@@ -231,11 +219,11 @@ async fn removes_unused_installations_from_disk_that_do_not_have_state() {
 
     // Create the corresponding physical directories of installations.
     // TODO: We have to generate these by running `install` command, once tests for those are setup.
-    fs::create_dir_all(toolchains_dir.join(installation_id_1)).unwrap();
+    fs::create_dir_all(toolchains_dir.join(&installation_id_1)).unwrap();
     fs::create_dir_all(toolchains_dir.join(installation_id_2)).unwrap();
     fs::create_dir_all(toolchains_dir.join(installation_id_3)).unwrap();
 
-    assert!(toolchains_dir.join(installation_id_1).exists());
+    assert!(toolchains_dir.join(&installation_id_1).exists());
     assert!(toolchains_dir.join(installation_id_2).exists());
     assert!(toolchains_dir.join(installation_id_3).exists());
 
@@ -255,20 +243,17 @@ async fn removes_unused_installations_from_disk_that_do_not_have_state() {
     // "installation_id_1" is still present with correct values.
     assert_eq!(
         state_file_actual
-            .pointer("/installations/installation_id_1")
+            .pointer(format!("/installations/{installation_id_1}").as_str())
             .unwrap(),
         &json!({
             "binary_proxies": {
                 "cargo": "/path/toolchains/bin/cargo"
             },
-            "manifests": [
-                "/path/to/proj/1/criticalup.toml",
-                "/path/to/proj/2/criticalup.toml"
-            ]
+            "manifests": manifests
         })
     );
 
-    assert!(toolchains_dir.join(installation_id_1).exists());
+    assert!(toolchains_dir.join(&installation_id_1).exists());
     assert!(!toolchains_dir.join(installation_id_2).exists()); // Does not exist.
     assert!(!toolchains_dir.join(installation_id_3).exists()); // Does not exist.
 }
@@ -282,7 +267,7 @@ async fn clean_deletes_only_unused_installations_that_are_not_on_disk() {
     let toolchains_dir = root.join("toolchains");
     fs::create_dir_all(&toolchains_dir).unwrap();
 
-    let installation_id_1 = "installation_id_1";
+    let (installation_id_1, manifests) = project_manifests(&test_env);
     let installation_id_2 = "installation_id_2";
     let installation_id_3 = "installation_id_3";
 
@@ -292,14 +277,11 @@ async fn clean_deletes_only_unused_installations_that_are_not_on_disk() {
         "version": 1,
         "authentication_token": "criticalup_token_45_hahaha",
         "installations": {
-            installation_id_1: {
+            &installation_id_1: {
                 "binary_proxies": {
                     "cargo": "/path/toolchains/bin/cargo"
                 },
-                "manifests": [
-                    "/path/to/proj/1/criticalup.toml",
-                    "/path/to/proj/2/criticalup.toml"
-                ]
+                "manifests": manifests
             },
             installation_id_2: {
                 "binary_proxies": {
@@ -321,9 +303,9 @@ async fn clean_deletes_only_unused_installations_that_are_not_on_disk() {
     // Create only one of the corresponding physical directories of installations. In this case we
     // create only Installation ID 1.
     // TODO: We have to generate these by running `install` command, once tests for those are setup.
-    fs::create_dir_all(toolchains_dir.join(installation_id_1)).unwrap();
+    fs::create_dir_all(toolchains_dir.join(&installation_id_1)).unwrap();
 
-    assert!(toolchains_dir.join(installation_id_1).exists());
+    assert!(toolchains_dir.join(&installation_id_1).exists());
     assert!(!toolchains_dir.join(installation_id_2).exists());
     assert!(!toolchains_dir.join(installation_id_3).exists());
 
@@ -348,20 +330,17 @@ async fn clean_deletes_only_unused_installations_that_are_not_on_disk() {
     // "installation_id_1" is still present with correct values.
     assert_eq!(
         state_file_actual
-            .pointer("/installations/installation_id_1")
+            .pointer(format!("/installations/{installation_id_1}").as_str())
             .unwrap(),
         &json!({
             "binary_proxies": {
                 "cargo": "/path/toolchains/bin/cargo"
             },
-            "manifests": [
-                "/path/to/proj/1/criticalup.toml",
-                "/path/to/proj/2/criticalup.toml"
-            ]
+            "manifests": manifests
         })
     );
 
-    assert!(toolchains_dir.join(installation_id_1).exists());
+    assert!(toolchains_dir.join(&installation_id_1).exists());
 }
 
 async fn delete_cache_dir() {
@@ -372,3 +351,148 @@ async fn delete_cache_dir() {
         tokio::fs::remove_dir_all(&cache_dir_test).await.unwrap();
     }
 }
+
+#[tokio::test]
+async fn clean_keeps_cached_artifacts_of_installations() {
+    let mut test_env = TestEnvironment::prepare().await;
+    let (work_dir, _) = install_project(&mut test_env).await;
+    let cache = test_env.server_cache_dir(&work_dir.path().join("cache"));
+    let releases = cache.join("artifacts/products/ferrocene/releases");
+    let stale = releases.join("24.08.0").join("rustc.tar.xz");
+    fs::create_dir_all(stale.parent().unwrap()).unwrap();
+    fs::write(&stale, "stale").unwrap();
+
+    let output = clean(&test_env, &work_dir, &["--dry-run"]).await;
+    assert!(output.contains("Would delete cached artifact"));
+    assert!(output.contains("Cleaning would free 5 B"));
+    assert!(stale.exists());
+
+    clean(&test_env, &work_dir, &[]).await;
+    assert!(!releases.join("24.08.0").exists());
+    assert!(releases.join("25.02.0").join("manifest.json").is_file());
+    assert!(releases.join("25.02.0").join("rustc.tar.xz").is_file());
+    assert!(cache.join("keys.json").is_file());
+
+    // The cached keys are kept regardless of the size budget.
+    clean(&test_env, &work_dir, &["--max-cache-size", "0"]).await;
+    assert!(!releases.exists());
+    assert!(cache.join("keys.json").is_file());
+}
+
+#[tokio::test]
+async fn clean_deletes_old_cached_artifacts() {
+    let mut test_env = TestEnvironment::prepare().await;
+    let (work_dir, _) = install_project(&mut test_env).await;
+    let release = test_env
+        .server_cache_dir(&work_dir.path().join("cache"))
+        .join("artifacts/products/ferrocene/releases/25.02.0");
+    File::options()
+        .write(true)
+        .open(release.join("rustc.tar.xz"))
+        .unwrap()
+        .set_modified(SystemTime::now() - Duration::from_secs(30 * 24 * 60 * 60))
+        .unwrap();
+
+    clean(&test_env, &work_dir, &["--max-cache-age", "7"]).await;
+    assert!(!release.join("rustc.tar.xz").exists());
+    assert!(release.join("manifest.json").is_file());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn clean_deletes_unused_objects() {
+    let mut test_env = TestEnvironment::prepare().await;
+    let (work_dir, manifest_path) = install_project(&mut test_env).await;
+    let objects = test_env.root().join("objects");
+    let count_objects = || {
+        fs::read_dir(&objects)
+            .unwrap()
+            .map(|shard| fs::read_dir(shard.unwrap().path()).unwrap().count())
+            .sum::<usize>()
+    };
+    assert_ne!(0, count_objects());
+
+    let output = cmd(&test_env, &work_dir)
+        .args(["remove", "--project", manifest_path.to_str().unwrap()])
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+
+    let output = clean(&test_env, &work_dir, &["--dry-run"]).await;
+    assert!(!output.contains("Cleaning would free 0 B"));
+    assert_ne!(0, count_objects());
+
+    clean(&test_env, &work_dir, &[]).await;
+    assert_eq!(0, count_objects());
+}
+
+#[tokio::test]
+async fn clean_deletes_installations_of_stale_manifests() {
+    let test_env = TestEnvironment::prepare().await;
+    let toolchains_dir = test_env.root().join("toolchains");
+    let (installation_id, manifests) = project_manifests(&test_env);
+    fs::create_dir_all(toolchains_dir.join(&installation_id)).unwrap();
+    let state = json!({
+        "version": 1,
+        "installations": {
+            &installation_id: {
+                "binary_proxies": {},
+                "manifests": manifests
+            }
+        }
+    });
+    fs::write(test_env.root().join("state.json"), state.to_string()).unwrap();
+
+    // One project was deleted, the other now uses a different release.
+    fs::remove_file(&manifests[0]).unwrap();
+    fs::write(
+        &manifests[1],
+        PROJECT_MANIFEST.replace("25.02.0", "25.05.0"),
+    )
+    .unwrap();
+
+    let output = test_env.cmd().arg("clean").output().await.unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("as it no longer exists"));
+    assert!(stderr.contains("as it no longer matches the installation"));
+    assert!(stderr.contains(&format!("Deleting unused installation {installation_id}")));
+
+    assert!(!toolchains_dir.join(&installation_id).exists());
+    let state: Value =
+        serde_json::from_slice(&fs::read(test_env.root().join("state.json")).unwrap()).unwrap();
+    assert_eq!(Some(&json!({})), state.pointer("/installations"));
+}
+
+/// Installs a project with a dedicated cache directory in the returned temporary directory.
+async fn install_project(test_env: &mut TestEnvironment) -> (TempDir, PathBuf) {
+    let work_dir = publish_release(test_env, "25.02.0", &["rustc"]).await;
+    let manifest_path = work_dir.path().join("criticalup.toml");
+    fs::write(&manifest_path, PROJECT_MANIFEST).unwrap();
+    let output = cmd(test_env, &work_dir)
+        .args(["install", "--project", manifest_path.to_str().unwrap()])
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+
+    (work_dir, manifest_path)
+}
+
+async fn clean(test_env: &TestEnvironment, work_dir: &TempDir, args: &[&str]) -> String {
+    let output = cmd(test_env, work_dir)
+        .arg("clean")
+        .args(args)
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stderr).unwrap()
+}
+
+fn cmd(test_env: &TestEnvironment, work_dir: &TempDir) -> Command {
+    let mut cmd = test_env.cmd();
+    cmd.env("CRITICALUP_CACHE_DIR", work_dir.path().join("cache"));
+    cmd
+}
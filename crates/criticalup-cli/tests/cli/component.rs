@@ -0,0 +1,139 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::assert_output;
+use crate::utils::{
+    construct_toolchains_product_path, prepare_release, TestEnvironment, EDITABLE_PROJECT_MANIFEST,
+};
+use criticalup_core::project_manifest::ProjectManifest;
+
+/// Packages of the release, of which the manifest only uses `rustc`.
+const PACKAGES: &[&str] = &["rustc", "rust-src"];
+
+#[tokio::test]
+async fn help_message() {
+    let test_env = TestEnvironment::prepare().await;
+    assert_output!(test_env.cmd().args(["component", "--help"]));
+}
+
+#[tokio::test]
+async fn add_installs_only_the_new_package() {
+    let mut test_env = TestEnvironment::prepare().await;
+    let (_work_dir, manifest_path) = prepare_release(&mut test_env, PACKAGES).await;
+    let manifest = manifest_path.to_str().unwrap();
+
+    let output = test_env
+        .cmd()
+        .args(["install", "--project", manifest])
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+    #[cfg(unix)]
+    let previous_id =
+        ProjectManifest::load(&manifest_path).unwrap().products()[0].installation_id();
+
+    assert_output!(test_env
+        .cmd()
+        .args(["component", "add", "rust-src", "--project", manifest]));
+    assert_eq!(
+        EDITABLE_PROJECT_MANIFEST.replace(r#"["rustc"]"#, r#"["rustc", "rust-src"]"#),
+        std::fs::read_to_string(&manifest_path).unwrap()
+    );
+
+    // The package that was already installed is not downloaded again.
+    let history = test_env.server().history().await;
+    let downloads = |package: &str| {
+        let uri = format!("/v1/releases/ferrocene/25.02.0/download/{package}/tar.xz");
+        history
+            .iter()
+            .filter(|(req, _)| req.uri() == uri.as_str())
+            .count()
+    };
+    assert_eq!(1, downloads("rustc"));
+    assert_eq!(1, downloads("rust-src"));
+    drop(history);
+
+    let installation_id =
+        ProjectManifest::load(&manifest_path).unwrap().products()[0].installation_id();
+    let installation = construct_toolchains_product_path(&test_env, &installation_id);
+    assert!(installation.join("lib").join("rustc").is_file());
+    assert!(installation.join("lib").join("rust-src").is_file());
+
+    // Both installations share the bytes of the package they have in common.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let previous = construct_toolchains_product_path(&test_env, &previous_id);
+        let previous = std::fs::metadata(previous.join("lib").join("rustc")).unwrap();
+        let current = std::fs::metadata(installation.join("lib").join("rustc")).unwrap();
+        assert_eq!(previous.ino(), current.ino());
+        assert!(test_env.root().join("objects").is_dir());
+    }
+}
+
+#[tokio::test]
+async fn add_unavailable_package() {
+    let mut test_env = TestEnvironment::prepare().await;
+    let (_work_dir, manifest_path) = prepare_release(&mut test_env, PACKAGES).await;
+
+    assert_output!(test_env.cmd().args([
+        "component",
+        "add",
+        "clippy",
+        "--project",
+        manifest_path.to_str().unwrap()
+    ]));
+    assert_eq!(
+        EDITABLE_PROJECT_MANIFEST,
+        std::fs::read_to_string(&manifest_path).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn add_with_unavailable_target() {
+    let mut test_env = TestEnvironment::prepare().await;
+    let (_work_dir, manifest_path) = prepare_release(&mut test_env, PACKAGES).await;
+    let manifest = EDITABLE_PROJECT_MANIFEST
+        .replace("manifest-version = 1", "manifest-version = 2")
+        .replace(
+            r#"packages = ["rustc"]"#,
+            "packages = [\"rustc\"]\ntargets = [\"aarch64-unknown-none\"]",
+        );
+    std::fs::write(&manifest_path, &manifest).unwrap();
+
+    assert_output!(test_env.cmd().args([
+        "component",
+        "add",
+        "rust-src",
+        "--project",
+        manifest_path.to_str().unwrap()
+    ]));
+    assert_eq!(manifest, std::fs::read_to_string(&manifest_path).unwrap());
+}
+
+#[tokio::test]
+async fn remove_package() {
+    let mut test_env = TestEnvironment::prepare().await;
+    let (_work_dir, manifest_path) = prepare_release(&mut test_env, PACKAGES).await;
+    let manifest = manifest_path.to_str().unwrap();
+    std::fs::write(
+        &manifest_path,
+        EDITABLE_PROJECT_MANIFEST.replace(r#"["rustc"]"#, r#"["rustc", "rust-src"]"#),
+    )
+    .unwrap();
+
+    assert_output!(test_env
+        .cmd()
+        .args(["component", "remove", "rust-src", "--project", manifest]));
+    assert_eq!(
+        EDITABLE_PROJECT_MANIFEST,
+        std::fs::read_to_string(&manifest_path).unwrap()
+    );
+
+    let installation_id =
+        ProjectManifest::load(&manifest_path).unwrap().products()[0].installation_id();
+    let installation = construct_toolchains_product_path(&test_env, &installation_id);
+    assert!(installation.join("lib").join("rustc").is_file());
+    assert!(!installation.join("lib").join("rust-src").exists());
+}
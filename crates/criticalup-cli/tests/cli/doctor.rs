@@ -0,0 +1,227 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::assert_output;
+use crate::utils::{auth_set_with_valid_token, project_manifests, TestEnvironment};
+use criticalup_core::project_manifest::ProjectManifest;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use tokio::process::Command;
+
+#[tokio::test]
+async fn help_message() {
+    let test_env = TestEnvironment::prepare().await;
+    assert_output!(test_env.cmd().args(["doctor", "--help"]));
+}
+
+#[tokio::test]
+async fn reports_stale_manifests() {
+    let test_env = TestEnvironment::prepare().await;
+    let (installation_id, manifests) = project_manifests(&test_env);
+    fs::create_dir_all(test_env.root().join("toolchains").join(&installation_id)).unwrap();
+    let state = json!({
+        "version": 1,
+        "installations": {
+            &installation_id: {
+                "binary_proxies": {},
+                "manifests": manifests
+            },
+            "installation_id_2": {
+                "binary_proxies": {},
+                "manifests": ["/path/to/proj/3/criticalup.toml"]
+            }
+        }
+    });
+    let state_path = test_env.root().join("state.json");
+    fs::write(&state_path, state.to_string()).unwrap();
+    fs::remove_file(&manifests[1]).unwrap();
+
+    let checks = doctor(&test_env, &[]).await;
+    assert!(checks.contains(&json!({
+        "name": "project-manifests",
+        "status": "warning",
+        "message": format!(
+            "Project manifest {} of installation {installation_id} no longer exists",
+            manifests[1]
+        ),
+        "fix": "Run `criticalup clean` to forget it",
+    })));
+    assert!(checks.contains(&json!({
+        "name": "installations",
+        "status": "warning",
+        "message": "Installation installation_id_2 is no longer used by any project",
+        "fix": "Run `criticalup clean` to delete it",
+    })));
+    assert!(!checks.iter().any(|check| check["message"]
+        .as_str()
+        .unwrap()
+        .contains(&format!("Installation {installation_id}"))));
+
+    // Nothing is changed by the doctor.
+    assert_eq!(
+        state,
+        serde_json::from_slice::<Value>(&fs::read(&state_path).unwrap()).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn checks_auth_profiles_of_projects() {
+    let test_env = TestEnvironment::prepare().await;
+    let (_, manifests) = project_manifests(&test_env);
+    let manifest = fs::read_to_string(&manifests[0]).unwrap();
+    fs::write(
+        &manifests[0],
+        manifest
+            .replace("manifest-version = 1", "manifest-version = 2")
+            .replace("[products.", "profile = \"acme\"\n\n[products."),
+    )
+    .unwrap();
+    let installation_id = ProjectManifest::load(Path::new(&manifests[0]))
+        .unwrap()
+        .products()[0]
+        .installation_id()
+        .0;
+    fs::create_dir_all(test_env.root().join("toolchains").join(&installation_id)).unwrap();
+    let state = json!({
+        "version": 1,
+        "installations": {
+            &installation_id: {
+                "binary_proxies": {},
+                "manifests": [manifests[0]]
+            }
+        }
+    });
+    fs::write(test_env.root().join("state.json"), state.to_string()).unwrap();
+    auth_set_with_valid_token(&test_env).await;
+
+    let checks = doctor(&test_env, &[]).await;
+    let authentication: Vec<_> = checks
+        .iter()
+        .filter(|check| check["name"] == "authentication")
+        .collect();
+    assert_eq!(2, authentication.len(), "{authentication:?}");
+    assert_eq!("ok", authentication[0]["status"]);
+    assert_eq!("warning", authentication[1]["status"]);
+    assert_eq!(
+        json!("Run `criticalup auth set --profile acme` with a token from the customer portal"),
+        authentication[1]["fix"]
+    );
+}
+
+#[tokio::test]
+async fn leaves_cache_untouched() {
+    let test_env = TestEnvironment::prepare().await;
+    auth_set_with_valid_token(&test_env).await;
+    // A cache shared by every download server, which other commands migrate.
+    let cache_dir = test_env.root().join("cache");
+    fs::create_dir_all(&cache_dir).unwrap();
+    fs::write(cache_dir.join("keys.json"), "keys").unwrap();
+
+    let output = doctor_cmd(&test_env, &[])
+        .env("CRITICALUP_CACHE_DIR", &cache_dir)
+        .output()
+        .await
+        .unwrap();
+    let checks = parse_report(&output.stdout);
+    assert_eq!("ok", find(&checks, "download-server")["status"]);
+
+    let entries: Vec<_> = fs::read_dir(&cache_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(vec!["keys.json"], entries);
+    assert_eq!(
+        "keys",
+        fs::read_to_string(cache_dir.join("keys.json")).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn reports_corrupt_state() {
+    let test_env = TestEnvironment::prepare().await;
+    fs::write(test_env.root().join("state.json"), "not json").unwrap();
+
+    let output = doctor_cmd(&test_env, &[]).output().await.unwrap();
+    assert_eq!(Some(1), output.status.code());
+    let checks = parse_report(&output.stdout);
+    let state = find(&checks, "state");
+    assert_eq!("error", state["status"]);
+    assert!(state["message"]
+        .as_str()
+        .unwrap()
+        .contains("Failed to parse the criticalup state file"));
+}
+
+#[tokio::test]
+async fn reports_missing_token_and_path() {
+    let test_env = TestEnvironment::prepare().await;
+
+    let checks = doctor(&test_env, &[]).await;
+    assert_eq!("ok", find(&checks, "state")["status"]);
+    assert_eq!("ok", find(&checks, "download-server")["status"]);
+    assert_eq!("warning", find(&checks, "path")["status"]);
+    assert_eq!(
+        json!("Run `criticalup auth set` with a token from the customer portal"),
+        find(&checks, "authentication")["fix"]
+    );
+    assert_eq!("skipped", find(&checks, "rustup-link")["status"]);
+
+    let checks = doctor(&test_env, &["--offline"]).await;
+    assert_eq!("skipped", find(&checks, "download-server")["status"]);
+}
+
+#[tokio::test]
+async fn reports_valid_setup() {
+    let test_env = TestEnvironment::prepare().await;
+    auth_set_with_valid_token(&test_env).await;
+    let bin_dir = test_env.root().join("proxy").join("bin");
+    fs::create_dir_all(&bin_dir).unwrap();
+
+    let output = doctor_cmd(&test_env, &[])
+        .env("PATH", &bin_dir)
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+    let checks = parse_report(&output.stdout);
+    for check in &checks {
+        assert_ne!("error", check["status"], "{check}");
+        assert_ne!("warning", check["status"], "{check}");
+    }
+    assert!(find(&checks, "authentication")["message"]
+        .as_str()
+        .unwrap()
+        .contains("is valid"));
+}
+
+async fn doctor(test_env: &TestEnvironment, args: &[&str]) -> Vec<Value> {
+    let output = doctor_cmd(test_env, args).output().await.unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    parse_report(&output.stdout)
+}
+
+fn doctor_cmd(test_env: &TestEnvironment, args: &[&str]) -> Command {
+    let mut cmd = test_env.cmd();
+    // Keep rustup out of reach, so that the report does not depend on the machine.
+    cmd.env("PATH", test_env.root())
+        .args(["doctor", "--json"])
+        .args(args);
+    cmd
+}
+
+fn parse_report(stdout: &[u8]) -> Vec<Value> {
+    let report: Value = serde_json::from_slice(stdout).unwrap();
+    report["checks"].as_array().unwrap().clone()
+}
+
+fn find<'a>(checks: &'a [Value], name: &str) -> &'a Value {
+    checks
+        .iter()
+        .find(|check| check["name"] == name)
+        .unwrap_or_else(|| panic!("no {name} check"))
+}
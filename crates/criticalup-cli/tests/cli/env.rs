@@ -0,0 +1,94 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::assert_output;
+use crate::utils::{construct_toolchains_product_path, TestEnvironment};
+use criticalup_core::project_manifest::ProjectManifest;
+use std::fs::File;
+
+#[tokio::test]
+async fn help_message() {
+    let test_env = TestEnvironment::prepare().await;
+    assert_output!(test_env.cmd().args(["env", "--help"]));
+}
+
+#[tokio::test]
+async fn hook_bash() {
+    let test_env = TestEnvironment::prepare().await;
+    assert_output!(test_env.cmd().args(["env", "--hook", "--shell", "bash"]));
+}
+
+#[tokio::test]
+async fn hook_fish() {
+    let test_env = TestEnvironment::prepare().await;
+    assert_output!(test_env.cmd().args(["env", "--hook", "--shell", "fish"]));
+}
+
+#[tokio::test]
+async fn exports_installation() {
+    let test_env = TestEnvironment::prepare().await;
+
+    let manifest_path = std::env::current_dir()
+        .unwrap()
+        .join("tests/resources/criticalup-which.toml");
+    let project = ProjectManifest::load(&manifest_path).unwrap();
+    let installation_id = project.products()[0].installation_id().0;
+
+    let installation = construct_toolchains_product_path(&test_env, &installation_id);
+    let bin = installation.join("bin");
+    std::fs::create_dir_all(&bin).unwrap();
+    std::fs::create_dir_all(installation.join("lib/rustlib/src/rust/library")).unwrap();
+    File::create(bin.join(format!("rustc{}", std::env::consts::EXE_SUFFIX))).unwrap();
+
+    let output = test_env
+        .cmd()
+        .args(["env", "--shell", "bash", "--project"])
+        .arg(&manifest_path)
+        .env("PATH", "/usr/bin")
+        .env("CRITICALUP_ENV_PATH", "/old/bin")
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    let rustc = bin.join(format!("rustc{}", std::env::consts::EXE_SUFFIX));
+    let bin = bin.to_str().unwrap();
+    assert!(stdout.contains(&format!("export CRITICALUP_ENV_PATH='{bin}';")));
+    assert!(stdout.contains(&format!("export RUSTC='{}';", rustc.display())));
+    assert!(stdout.contains(&format!(
+        "export RUST_SRC_PATH='{}';",
+        installation.join("lib/rustlib/src/rust/library").display()
+    )));
+    // `cargo` is not part of the installation.
+    assert!(stdout.contains("unset CARGO;"));
+    #[cfg(unix)]
+    assert!(stdout.contains(&format!("export PATH='{bin}:/usr/bin';")));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn reverts_outside_of_project() {
+    let test_env = TestEnvironment::prepare().await;
+    let outside = tempfile::tempdir().unwrap();
+
+    let run = |previous: Option<&str>| {
+        let mut cmd = test_env.cmd();
+        cmd.args(["env", "--shell", "fish", "--allow-no-project"])
+            .current_dir(outside.path())
+            .env("PATH", "/toolchain/bin:/usr/bin")
+            .env_remove("CRITICALUP_ENV_PATH")
+            .env_remove("CRITICALUP_ENV_LIBRARY_PATH");
+        if let Some(previous) = previous {
+            cmd.env("CRITICALUP_ENV_PATH", previous);
+        }
+        cmd
+    };
+
+    assert_output!(run(Some("/toolchain/bin")));
+
+    // Nothing is printed when no environment was set up before.
+    let output = run(None).output().await.unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
@@ -0,0 +1,138 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::assert_output;
+use crate::utils::{construct_toolchains_product_path, prepare_release, TestEnvironment};
+use criticalup_core::project_manifest::ProjectManifest;
+use serde_json::{json, Value};
+use std::path::Path;
+
+#[tokio::test]
+async fn help_message() {
+    let test_env = TestEnvironment::prepare().await;
+    assert_output!(test_env.cmd().args(["ide", "--help"]));
+}
+
+#[tokio::test]
+async fn prints_lsp_config() {
+    let test_env = TestEnvironment::prepare().await;
+    let project = tempfile::tempdir().unwrap();
+    let manifest_path = project.path().join("criticalup.toml");
+    std::fs::copy("tests/resources/criticalup-which.toml", &manifest_path).unwrap();
+
+    let installation = fake_installation(&test_env, &manifest_path);
+    let output = test_env
+        .cmd()
+        .args(["ide", "--editor", "lsp", "--project"])
+        .arg(&manifest_path)
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+
+    let config: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let rust_src = installation.join("lib/rustlib/src/rust/library");
+    assert_eq!(
+        json!({
+            "sysroot": installation,
+            "rust-src": rust_src,
+            "rust-analyzer": Value::Null,
+            "targets": ["aarch64-unknown-none"],
+            "initializationOptions": {
+                "cargo": {
+                    "sysroot": installation,
+                    "sysrootSrc": rust_src,
+                    "target": "aarch64-unknown-none",
+                }
+            }
+        }),
+        config
+    );
+}
+
+#[tokio::test]
+async fn write_keeps_other_vscode_settings() {
+    let test_env = TestEnvironment::prepare().await;
+    let project = tempfile::tempdir().unwrap();
+    let manifest_path = project.path().join("criticalup.toml");
+    std::fs::copy("tests/resources/criticalup-which.toml", &manifest_path).unwrap();
+    let settings_path = project.path().join(".vscode").join("settings.json");
+    std::fs::create_dir_all(settings_path.parent().unwrap()).unwrap();
+    std::fs::write(&settings_path, r#"{"editor.tabSize": 4}"#).unwrap();
+
+    let installation = fake_installation(&test_env, &manifest_path);
+    let output = test_env
+        .cmd()
+        .args(["ide", "--write", "--project"])
+        .arg(&manifest_path)
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+
+    let settings: Value =
+        serde_json::from_str(&std::fs::read_to_string(&settings_path).unwrap()).unwrap();
+    assert_eq!(4, settings["editor.tabSize"]);
+    assert_eq!(json!(installation), settings["rust-analyzer.cargo.sysroot"]);
+    assert_eq!(
+        json!(installation.join("lib/rustlib/src/rust/library")),
+        settings["rust-analyzer.cargo.sysrootSrc"]
+    );
+}
+
+#[tokio::test]
+async fn install_updates_written_config() {
+    let mut test_env = TestEnvironment::prepare().await;
+    let (_work_dir, manifest_path) = prepare_release(&mut test_env, &["rustc", "rust-src"]).await;
+    let manifest = manifest_path.to_str().unwrap();
+
+    let output = test_env
+        .cmd()
+        .args(["install", "--project", manifest])
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+    let output = test_env
+        .cmd()
+        .args(["ide", "--editor", "lsp", "--write", "--project", manifest])
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+
+    let output = test_env
+        .cmd()
+        .args(["component", "add", "rust-src", "--project", manifest])
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+
+    let installation_id =
+        ProjectManifest::load(&manifest_path).unwrap().products()[0].installation_id();
+    let installation = construct_toolchains_product_path(&test_env, &installation_id);
+    let config_path = manifest_path.parent().unwrap().join(".criticalup/ide.json");
+    let config: Value =
+        serde_json::from_str(&std::fs::read_to_string(config_path).unwrap()).unwrap();
+    assert_eq!(json!(installation), config["sysroot"]);
+}
+
+/// Creates the installation of the manifest, with `rustc`, `rust-src` and one target.
+fn fake_installation(test_env: &TestEnvironment, manifest_path: &Path) -> std::path::PathBuf {
+    let installation_id = ProjectManifest::load(manifest_path).unwrap().products()[0]
+        .installation_id()
+        .0;
+    let installation = construct_toolchains_product_path(test_env, &installation_id);
+    std::fs::create_dir_all(installation.join("bin")).unwrap();
+    std::fs::write(
+        installation
+            .join("bin")
+            .join(format!("rustc{}", std::env::consts::EXE_SUFFIX)),
+        "",
+    )
+    .unwrap();
+    std::fs::create_dir_all(installation.join("lib/rustlib/src/rust/library")).unwrap();
+    std::fs::create_dir_all(installation.join("lib/rustlib/aarch64-unknown-none/lib")).unwrap();
+    installation
+}
@@ -2,9 +2,10 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::assert_output;
-use crate::utils::TestEnvironment;
+use crate::utils::{construct_toolchains_product_path, publish_release, stdin, TestEnvironment};
+use criticalup_core::project_manifest::ProjectManifest;
 use std::fs::File;
-use tempfile::tempdir;
+use tempfile::{tempdir, TempDir};
 
 #[tokio::test]
 async fn help_message() {
@@ -57,3 +58,200 @@ async fn error_on_missing_required_arg() {
     let test_env = TestEnvironment::prepare().await;
     assert_output!(test_env.cmd().args(["init"]));
 }
+
+#[tokio::test]
+async fn prints_manifest_from_rust_toolchain() {
+    let test_env = TestEnvironment::prepare().await;
+    let current_dir = tempdir().unwrap();
+    std::fs::write(
+        current_dir.path().join("rust-toolchain.toml"),
+        r#"[toolchain]
+channel = "stable-25.02.0"
+components = ["rust-src", "miri"]
+targets = ["aarch64-unknown-none"]
+"#,
+    )
+    .unwrap();
+
+    assert_output!(test_env
+        .cmd()
+        .args(["init", "--from-rust-toolchain", "--print"])
+        .current_dir(current_dir.path()));
+}
+
+#[tokio::test]
+async fn rust_toolchain_release_override() {
+    let test_env = TestEnvironment::prepare().await;
+    let current_dir = tempdir().unwrap();
+    std::fs::write(current_dir.path().join("rust-toolchain"), "1.85.0\n").unwrap();
+
+    assert_output!(test_env
+        .cmd()
+        .args([
+            "init",
+            "--from-rust-toolchain",
+            "--release",
+            "stable-25.02.0"
+        ])
+        .current_dir(current_dir.path()));
+    let manifest = std::fs::read_to_string(current_dir.path().join("criticalup.toml")).unwrap();
+    assert!(manifest.contains(r#"release = "stable-25.02.0""#));
+}
+
+#[tokio::test]
+async fn prints_embedded_template() {
+    let test_env = TestEnvironment::prepare().await;
+    assert_output!(test_env.cmd().args([
+        "init",
+        "--release",
+        "25.02.0",
+        "--template",
+        "embedded",
+        "--target",
+        "aarch64-unknown-none",
+        "--package",
+        "llvm-tools-${rustc-host}",
+        "--print"
+    ]));
+}
+
+#[tokio::test]
+async fn embedded_template_requires_targets() {
+    let test_env = TestEnvironment::prepare().await;
+    assert_output!(test_env.cmd().args([
+        "init",
+        "--release",
+        "25.02.0",
+        "--template",
+        "embedded",
+        "--print"
+    ]));
+}
+
+#[tokio::test]
+async fn prompts_for_missing_answers() {
+    let test_env = TestEnvironment::prepare().await;
+    assert_output!(test_env
+        .cmd()
+        .args(["init", "--print"])
+        .env("CRITICALUP_TEST_MOCK_TTY", "1")
+        .stdin(stdin("25.02.0\nembedded\naarch64-unknown-none\n")));
+}
+
+#[tokio::test]
+async fn prompt_rejects_empty_release() {
+    let test_env = TestEnvironment::prepare().await;
+    assert_output!(test_env
+        .cmd()
+        .args(["init", "--print", "--template", "host"])
+        .env("CRITICALUP_TEST_MOCK_TTY", "1")
+        .stdin(stdin("\n25.02.0\n")));
+}
+
+#[tokio::test]
+async fn rust_toolchain_conflicts_with_targets() {
+    let test_env = TestEnvironment::prepare().await;
+    assert_output!(test_env.cmd().args([
+        "init",
+        "--from-rust-toolchain",
+        "--target",
+        "aarch64-unknown-none",
+        "--print"
+    ]));
+}
+
+#[tokio::test]
+async fn unreachable_download_server_only_warns() {
+    let mut test_env = TestEnvironment::prepare().await;
+    let _work_dir = publish_minimal_release(&mut test_env).await;
+
+    let output = test_env
+        .cmd()
+        .args(["init", "--release", "25.02.0", "--print"])
+        .env("CRITICALUP_TEST_DOWNLOAD_SERVER_URL", "http://127.0.0.1:1")
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Could not check release '25.02.0' with the download server"),
+        "{stderr}"
+    );
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains(r#"release = "25.02.0""#));
+}
+
+#[tokio::test]
+async fn validates_release_with_download_server() {
+    let mut test_env = TestEnvironment::prepare().await;
+    let _work_dir = publish_minimal_release(&mut test_env).await;
+
+    assert_output!(test_env
+        .cmd()
+        .args(["init", "--release", "26.01.0", "--print"]));
+    assert_output!(test_env.cmd().args([
+        "init",
+        "--release",
+        "25.02.0",
+        "--template",
+        "minimal-ci",
+        "--package",
+        "miri",
+        "--print"
+    ]));
+}
+
+#[tokio::test]
+async fn installs_after_creating_manifest() {
+    let mut test_env = TestEnvironment::prepare().await;
+    let work_dir = publish_minimal_release(&mut test_env).await;
+    let manifest_path = work_dir.path().join("criticalup.toml");
+
+    let output = test_env
+        .cmd()
+        .args([
+            "init",
+            "--release",
+            "25.02.0",
+            "--template",
+            "minimal-ci",
+            "--install",
+        ])
+        .current_dir(work_dir.path())
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+
+    let manifest = ProjectManifest::load(&manifest_path).unwrap();
+    let product = &manifest.products()[0];
+    let installation = construct_toolchains_product_path(&test_env, &product.installation_id().0);
+    for package in product.packages() {
+        assert!(installation.join("lib").join(package).is_file());
+    }
+}
+
+/// Publishes release 25.02.0 with the packages of the `minimal-ci` template, and authenticates.
+async fn publish_minimal_release(test_env: &mut TestEnvironment) -> TempDir {
+    // Loading a manifest is the simplest way to learn the packages built for the host.
+    let scratch_dir = tempdir().unwrap();
+    let host_manifest = scratch_dir.path().join("host.toml");
+    std::fs::write(
+        &host_manifest,
+        r#"manifest-version = 1
+
+[products.ferrocene]
+release = "25.02.0"
+packages = ["cargo-${rustc-host}", "rustc-${rustc-host}", "rust-std-${rustc-host}"]
+"#,
+    )
+    .unwrap();
+    let packages = ProjectManifest::load(&host_manifest).unwrap().products()[0]
+        .packages()
+        .to_vec();
+
+    let packages: Vec<&str> = packages.iter().map(String::as_str).collect();
+    publish_release(test_env, "25.02.0", &packages).await
+}
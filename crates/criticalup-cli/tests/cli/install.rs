@@ -3,7 +3,8 @@
 
 use crate::assert_output;
 use crate::utils::{
-    auth_set_with_valid_token, construct_toolchains_product_path, Server, TestEnvironment,
+    auth_set_with_valid_token, construct_toolchains_product_path, publish_release, Server,
+    TestEnvironment, MOCK_AUTH_TOKENS,
 };
 use hyper::StatusCode;
 use mock_download_server::MockServer;
@@ -246,6 +247,116 @@ async fn run_install_with_generic_file_server_successfully() {
     .await;
 }
 
+#[tokio::test]
+async fn run_install_with_v2_product_settings_successfully() {
+    let mut test_env = TestEnvironment::prepare().await;
+
+    // Create a release with one package.
+    let package_ref = "rustc";
+    let product_ref = "ferrocene";
+    let release_ref = "stable-25.02.0";
+
+    let work_dir_binding = tempdir().unwrap();
+    let work_dir = work_dir_binding.path();
+    let output_dir = work_dir.join("output");
+    tokio::fs::create_dir_all(&output_dir).await.unwrap();
+
+    let input_dir = work_dir.join("input");
+    tokio::fs::create_dir_all(input_dir.join("bin"))
+        .await
+        .unwrap();
+    tokio::fs::write(input_dir.join("bin").join("rustc"), "hello")
+        .await
+        .unwrap();
+
+    let server: &mut MockServer = test_env.server();
+    let server_url = server.url();
+
+    server
+        .create_package(package_ref, product_ref, &input_dir, &output_dir)
+        .await
+        .unwrap();
+    server
+        .create_release(product_ref, release_ref, vec![package_ref], &output_dir)
+        .await
+        .unwrap();
+
+    let manifest = toml::toml! {
+        manifest-version = 2
+
+        [products.ferrocene]
+        channel = "stable"
+        release = "25.02.0"
+        download-server = server_url
+        format = "tar.xz"
+        packages = [
+            package_ref,
+        ]
+    }
+    .to_string();
+
+    let manifest_path = work_dir.join("criticalup.toml");
+    tokio::fs::write(&manifest_path, manifest).await.unwrap();
+
+    auth_set_with_valid_token(&test_env).await;
+
+    // The default download server is unreachable, so the product must be fetched from the
+    // server configured in the manifest.
+    let output = test_env
+        .cmd()
+        .env("CRITICALUP_TEST_DOWNLOAD_SERVER_URL", "http://127.0.0.1:1")
+        .args(["install", "--project", manifest_path.to_str().unwrap()])
+        .output()
+        .await
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    assert_url_called_n_times_and_returns_status_code(
+        &mut test_env,
+        1,
+        "/v1/releases/ferrocene/stable-25.02.0/download/rustc/tar.xz",
+        StatusCode::OK,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn run_install_with_token_env_var_and_missing_profile() {
+    let mut test_env = TestEnvironment::prepare().await;
+    let work_dir = publish_release(&mut test_env, "25.02.0", &["rustc"]).await;
+
+    let manifest = toml::toml! {
+        manifest-version = 2
+        profile = "missing"
+
+        [products.ferrocene]
+        release = "25.02.0"
+        packages = ["rustc"]
+    }
+    .to_string();
+    let manifest_path = work_dir.path().join("criticalup.toml");
+    tokio::fs::write(&manifest_path, manifest).await.unwrap();
+
+    // `CRITICALUP_TOKEN` takes precedence over the profiles, so the missing profile is not an
+    // error.
+    let output = test_env
+        .cmd()
+        .env("CRITICALUP_TOKEN", MOCK_AUTH_TOKENS[0].0)
+        .args(["install", "--project", manifest_path.to_str().unwrap()])
+        .output()
+        .await
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
 async fn assert_url_called_n_times_and_returns_status_code(
     test_env: &mut TestEnvironment,
     n: u8,
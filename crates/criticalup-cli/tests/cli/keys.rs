@@ -0,0 +1,55 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::assert_output;
+use crate::utils::{TestEnvironment, MOCK_AUTH_TOKENS};
+
+#[tokio::test]
+async fn help_message() {
+    let test_env = TestEnvironment::prepare().await;
+    assert_output!(test_env.cmd().args(["keys", "--help"]));
+}
+
+#[tokio::test]
+async fn list_keys() {
+    let test_env = TestEnvironment::prepare().await;
+
+    // Keys are generated for each test, so their IDs and the revocation expiry change every run.
+    let mut settings = insta::Settings::clone_current();
+    settings.add_filter(
+        r"[A-Za-z0-9+/]{43}=",
+        "[KEY ID]                                    ",
+    );
+    settings.add_filter(r"expires at \S+", "expires at [TIMESTAMP]");
+    let _guard = settings.bind_to_scope();
+
+    assert_output!(test_env.cmd().args(["keys"]));
+}
+
+#[tokio::test]
+async fn warns_about_expired_token() {
+    let mut test_env = TestEnvironment::prepare().await;
+    let set = test_env
+        .cmd()
+        .args(["auth", "set", MOCK_AUTH_TOKENS[1].0])
+        .output()
+        .await
+        .unwrap();
+    assert!(set.status.success());
+
+    let output = test_env.cmd().arg("keys").output().await.unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("The authentication token 'dummy token 2' expired on"),
+        "{stderr}"
+    );
+
+    // One request from `auth set`, and one from `keys`.
+    let history = test_env.server().history().await;
+    let token_requests = history
+        .iter()
+        .filter(|(req, _)| req.uri() == "/v1/tokens/current")
+        .count();
+    assert_eq!(2, token_requests);
+}
@@ -0,0 +1,203 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::assert_output;
+use crate::utils::TestEnvironment;
+
+#[tokio::test]
+async fn create_help_message() {
+    let test_env = TestEnvironment::prepare().await;
+    assert_output!(test_env.cmd().args(["link", "create", "--help"]));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn named_link_follows_the_installation() {
+    use crate::utils::{construct_toolchains_product_path, prepare_release};
+    use criticalup_core::project_manifest::ProjectManifest;
+    use std::path::Path;
+
+    let mut test_env = TestEnvironment::prepare().await;
+    let (work_dir, manifest_path) = prepare_release(&mut test_env, &["rustc", "rust-src"]).await;
+    let manifest = manifest_path.to_str().unwrap();
+    let (bin_dir, log) = fake_rustup(work_dir.path());
+
+    let run = |args: &[&str]| {
+        let mut cmd = test_env.cmd();
+        cmd.args(args).env("PATH", &bin_dir);
+        cmd
+    };
+    let installation = |manifest_path: &Path| {
+        let id = ProjectManifest::load(manifest_path).unwrap().products()[0].installation_id();
+        construct_toolchains_product_path(&test_env, &id)
+    };
+
+    let output = run(&["install", "--project", manifest])
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+    let output = run(&[
+        "link",
+        "create",
+        "--name",
+        "project",
+        "--project",
+        manifest,
+        "--rust-toolchain",
+    ])
+    .output()
+    .await
+    .unwrap();
+    assert!(output.status.success());
+    let first = installation(&manifest_path);
+    assert_eq!(
+        "[toolchain]\nchannel = \"project\"\n",
+        std::fs::read_to_string(work_dir.path().join("rust-toolchain.toml")).unwrap()
+    );
+
+    // Changing the installation links the toolchain again.
+    let output = run(&["component", "add", "rust-src", "--project", manifest])
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+    let second = installation(&manifest_path);
+    assert_ne!(first, second);
+    assert_eq!(
+        format!(
+            "toolchain link project {}\ntoolchain link project {}\n",
+            first.display(),
+            second.display()
+        ),
+        std::fs::read_to_string(&log).unwrap()
+    );
+
+    let output = run(&["link", "show"]).output().await.unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(&format!("project: {}", second.display())));
+
+    let output = run(&["link", "remove", "--name", "project"])
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+    assert!(std::fs::read_to_string(&log)
+        .unwrap()
+        .ends_with("toolchain remove project\n"));
+    let state = std::fs::read_to_string(test_env.root().join("state.json")).unwrap();
+    assert!(!state.contains("\"links\""));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn named_link_is_removed_with_the_installation() {
+    use crate::utils::prepare_release;
+
+    let mut test_env = TestEnvironment::prepare().await;
+    let (work_dir, manifest_path) = prepare_release(&mut test_env, &["rustc", "rust-src"]).await;
+    let manifest = manifest_path.to_str().unwrap();
+    let (bin_dir, log) = fake_rustup(work_dir.path());
+    let run = |args: &[&str]| {
+        let mut cmd = test_env.cmd();
+        cmd.args(args).env("PATH", &bin_dir);
+        cmd
+    };
+    let link = || async {
+        for args in [
+            &["install", "--project", manifest][..],
+            &["link", "create", "--name", "project", "--project", manifest],
+        ] {
+            let output = run(args).output().await.unwrap();
+            assert!(output.status.success());
+        }
+    };
+    let state = || std::fs::read_to_string(test_env.root().join("state.json")).unwrap();
+
+    // Removing the project removes its toolchain.
+    link().await;
+    let output = run(&["remove", "--project", manifest])
+        .output()
+        .await
+        .unwrap();
+    assert!(output.status.success());
+    assert!(std::fs::read_to_string(&log)
+        .unwrap()
+        .ends_with("toolchain remove project\n"));
+    assert!(!state().contains("\"links\""));
+
+    // So does cleaning up the installation after the project changed.
+    link().await;
+    std::fs::write(
+        &manifest_path,
+        std::fs::read_to_string(&manifest_path)
+            .unwrap()
+            .replace("rustc", "rust-src"),
+    )
+    .unwrap();
+    let output = run(&["clean"]).output().await.unwrap();
+    assert!(output.status.success());
+    assert!(std::fs::read_to_string(&log)
+        .unwrap()
+        .ends_with("toolchain remove project\n"));
+    assert!(!state().contains("\"links\""));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn doctor_checks_named_links() {
+    use crate::utils::prepare_release;
+
+    let mut test_env = TestEnvironment::prepare().await;
+    let (work_dir, manifest_path) = prepare_release(&mut test_env, &["rustc", "rust-src"]).await;
+    let manifest = manifest_path.to_str().unwrap();
+    let (bin_dir, _log) = fake_rustup(work_dir.path());
+    let run = |args: &[&str]| {
+        let mut cmd = test_env.cmd();
+        cmd.args(args).env("PATH", &bin_dir);
+        cmd
+    };
+    for args in [
+        &["install", "--project", manifest][..],
+        &["link", "create", "--name", "project", "--project", manifest],
+    ] {
+        let output = run(args).output().await.unwrap();
+        assert!(output.status.success());
+    }
+
+    // The fake rustup lists no toolchains, as if the link was removed behind criticalup's back.
+    let output = run(&["doctor", "--json"]).output().await.unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(report["checks"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!({
+            "name": "rustup-link",
+            "status": "warning",
+            "message": "The `project` rustup toolchain is no longer linked",
+            "fix": format!(
+                "Run `criticalup install --project {}` to link it again, or `criticalup link \
+                remove --name project` to remove it",
+                manifest_path.canonicalize().unwrap().display()
+            ),
+        })));
+}
+
+/// Writes a fake rustup into `dir`, logging the commands it receives. Returns the directory to
+/// put in `PATH` and the log.
+#[cfg(unix)]
+fn fake_rustup(dir: &std::path::Path) -> (std::path::PathBuf, std::path::PathBuf) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let bin_dir = dir.join("bin");
+    let log = dir.join("rustup.log");
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    std::fs::write(
+        bin_dir.join("rustup"),
+        format!("#!/bin/sh\necho \"$@\" >> '{}'\n", log.display()),
+    )
+    .unwrap();
+    std::fs::set_permissions(bin_dir.join("rustup"), PermissionsExt::from_mode(0o755)).unwrap();
+    (bin_dir, log)
+}
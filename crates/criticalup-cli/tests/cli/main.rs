@@ -4,15 +4,24 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 mod auth;
+mod auth_list;
 mod auth_remove;
 mod auth_set;
+mod auth_status;
 mod binary_proxies;
 mod clean;
+mod component;
 mod doc;
+mod doctor;
+mod env;
+mod ide;
 mod init;
 mod install;
+mod keys;
+mod link;
 mod remove;
 mod root;
 mod run;
+mod target;
 mod utils;
 mod which;
@@ -0,0 +1,116 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::assert_output;
+use crate::utils::{prepare_release, TestEnvironment, EDITABLE_PROJECT_MANIFEST};
+
+/// Packages of the release, offering the standard library of `aarch64-unknown-none`.
+const PACKAGES: &[&str] = &["rustc", "rust-std-aarch64-unknown-none"];
+
+#[tokio::test]
+async fn help_message() {
+    let test_env = TestEnvironment::prepare().await;
+    assert_output!(test_env.cmd().args(["target", "--help"]));
+}
+
+#[tokio::test]
+async fn add_list_and_remove_targets() {
+    let mut test_env = TestEnvironment::prepare().await;
+    let (_work_dir, manifest_path) = prepare_release(&mut test_env, PACKAGES).await;
+    let manifest = manifest_path.to_str().unwrap();
+
+    assert_output!(test_env.cmd().args([
+        "target",
+        "add",
+        "aarch64-unknown-none",
+        "--project",
+        manifest
+    ]));
+    assert_eq!(
+        r#"manifest-version = 2
+
+# Toolchain used by the firmware.
+[products.ferrocene]
+release = "25.02.0" # pinned
+packages = ["rustc"]
+targets = ["aarch64-unknown-none"]
+"#,
+        std::fs::read_to_string(&manifest_path).unwrap()
+    );
+
+    assert_output!(test_env
+        .cmd()
+        .args(["target", "list", "--project", manifest]));
+
+    assert_output!(test_env.cmd().args([
+        "target",
+        "remove",
+        "aarch64-unknown-none",
+        "--project",
+        manifest
+    ]));
+    assert_eq!(
+        EDITABLE_PROJECT_MANIFEST.replace("manifest-version = 1", "manifest-version = 2"),
+        std::fs::read_to_string(&manifest_path).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn add_unavailable_target() {
+    let mut test_env = TestEnvironment::prepare().await;
+    let (_work_dir, manifest_path) = prepare_release(&mut test_env, PACKAGES).await;
+
+    assert_output!(test_env.cmd().args([
+        "target",
+        "add",
+        "thumbv7em-none-eabihf",
+        "--project",
+        manifest_path.to_str().unwrap()
+    ]));
+    assert_eq!(
+        EDITABLE_PROJECT_MANIFEST,
+        std::fs::read_to_string(&manifest_path).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn keep_manifest_when_target_substitution_breaks() {
+    let mut test_env = TestEnvironment::prepare().await;
+    let (_work_dir, manifest_path) = prepare_release(
+        &mut test_env,
+        &[
+            "rustc",
+            "rust-std-aarch64-unknown-none",
+            "rust-std-thumbv7em-none-eabihf",
+        ],
+    )
+    .await;
+    let contents = r#"manifest-version = 2
+
+[products.ferrocene]
+release = "25.02.0"
+packages = ["rustc", "rust-std-${target}"]
+targets = ["aarch64-unknown-none"]
+"#;
+    std::fs::write(&manifest_path, contents).unwrap();
+    let manifest = manifest_path.to_str().unwrap();
+
+    // `${target}` requires exactly one target.
+    assert_output!(test_env.cmd().args([
+        "target",
+        "add",
+        "thumbv7em-none-eabihf",
+        "--project",
+        manifest
+    ]));
+    assert_eq!(contents, std::fs::read_to_string(&manifest_path).unwrap());
+
+    assert_output!(test_env.cmd().args([
+        "target",
+        "remove",
+        "aarch64-unknown-none",
+        "--project",
+        manifest
+    ]));
+    assert_eq!(contents, std::fs::read_to_string(&manifest_path).unwrap());
+}
@@ -4,7 +4,9 @@
 use criticaltrust::keys::{EphemeralKeyPair, KeyAlgorithm, KeyPair, KeyRole, PublicKey};
 use criticaltrust::manifests::{Release, ReleaseManifest};
 use criticaltrust::signatures::SignedPayload;
-use mock_download_server::{file_server_routes, AuthenticationToken, Builder, MockServer};
+use mock_download_server::{
+    file_server_routes, AuthenticationToken, Builder, MockServer, TokenRejection,
+};
 use std::borrow::Cow;
 use std::io::{Seek, Write};
 use std::path::{Path, PathBuf};
@@ -37,6 +39,14 @@ pub(crate) const MOCK_AUTH_TOKENS: &[(&str, AuthenticationToken)] = &[
             expires_at: Some(Cow::Borrowed("2022-01-01T00:00:00+00:00")),
         },
     ),
+    (
+        "criticalup_token_333333333",
+        AuthenticationToken {
+            name: Cow::Borrowed("dummy token 4"),
+            organization_name: Cow::Borrowed("ferrous-systems"),
+            expires_at: Some(Cow::Borrowed("2999-01-01T00:00:00+00:00")),
+        },
+    ),
 ];
 
 // This can't be a const since we call `new()`
@@ -101,6 +111,11 @@ impl TestEnvironment {
         self.root.path()
     }
 
+    /// Directory in `cache_dir` caching the data of the mock download server.
+    pub(crate) fn server_cache_dir(&self, cache_dir: &Path) -> PathBuf {
+        criticalup_core::cache::server_cache_dir(cache_dir, &self.server.url())
+    }
+
     pub(crate) fn cmd(&self) -> Command {
         let mut command = Command::new(env!("CARGO_BIN_EXE_criticalup-test"));
         command.env("CRITICALUP_ROOT", self.root.path());
@@ -135,6 +150,14 @@ impl TestEnvironment {
             .await;
     }
 
+    pub(crate) async fn reject_token(&self, token: &str, reason: TokenRejection) {
+        self.server
+            .edit_data(|mut data| {
+                data.rejected_tokens.insert(token.into(), reason);
+            })
+            .await;
+    }
+
     // Beware of the consumption.
     pub(crate) fn server(&mut self) -> &mut MockServer {
         &mut self.server
@@ -268,6 +291,9 @@ macro_rules! assert_output {
             "error: Failed to load the project manifest at /path/to/manifest/criticalup-empty-packages.toml",
         );
 
+        // The mock download server listens on a random port.
+        settings.add_filter(r"http://127\.0\.0\.1:[0-9]+", "http://127.0.0.1:PORT");
+
         #[cfg(target_os = "linux")]
         settings.add_filter(
             r"/tmp/.*/criticalup.toml",
@@ -349,6 +375,59 @@ pub(crate) async fn auth_set_with_valid_token(env: &TestEnvironment) {
         .success());
 }
 
+/// Publishes `release` of `ferrocene` with the given packages, each only containing the
+/// `lib/<package>` file, and authenticates with a valid token. The packages are built in the
+/// returned temporary directory, which tests can also use as their work directory.
+pub(crate) async fn publish_release(
+    test_env: &mut TestEnvironment,
+    release: &str,
+    packages: &[&str],
+) -> TempDir {
+    let work_dir = tempfile::tempdir().unwrap();
+    let output_dir = work_dir.path().join("output");
+    std::fs::create_dir_all(&output_dir).unwrap();
+
+    for package in packages {
+        let input_dir = work_dir.path().join("input").join(package);
+        std::fs::create_dir_all(input_dir.join("lib")).unwrap();
+        std::fs::write(input_dir.join("lib").join(package), "hello").unwrap();
+        test_env
+            .server()
+            .create_package(package, "ferrocene", &input_dir, &output_dir)
+            .await
+            .unwrap();
+    }
+    test_env
+        .server()
+        .create_release("ferrocene", release, packages.to_vec(), &output_dir)
+        .await
+        .unwrap();
+    auth_set_with_valid_token(test_env).await;
+    work_dir
+}
+
+/// Project manifest edited by the tests of the `target` and `component` subcommands, with comments
+/// to check they are preserved.
+pub(crate) const EDITABLE_PROJECT_MANIFEST: &str = r#"manifest-version = 1
+
+# Toolchain used by the firmware.
+[products.ferrocene]
+release = "25.02.0" # pinned
+packages = ["rustc"]
+"#;
+
+/// Publishes the `ferrocene` 25.02.0 release offering `packages`, and writes
+/// [`EDITABLE_PROJECT_MANIFEST`], only using `rustc`, in the returned work directory.
+pub(crate) async fn prepare_release(
+    test_env: &mut TestEnvironment,
+    packages: &[&str],
+) -> (TempDir, PathBuf) {
+    let work_dir = publish_release(test_env, "25.02.0", packages).await;
+    let manifest_path = work_dir.path().join("criticalup.toml");
+    std::fs::write(&manifest_path, EDITABLE_PROJECT_MANIFEST).unwrap();
+    (work_dir, manifest_path)
+}
+
 fn generate_key(role: KeyRole) -> EphemeralKeyPair {
     EphemeralKeyPair::generate(KeyAlgorithm::EcdsaP256Sha256Asn1SpkiDer, role, None).unwrap()
 }
@@ -378,3 +457,27 @@ async fn add_non_root_key_to_server(
 
     server_builder
 }
+
+/// Project manifest of the `ferrocene` 25.02.0 release with the `rustc` package.
+pub(crate) const PROJECT_MANIFEST: &str = r#"manifest-version = 1
+
+[products.ferrocene]
+release = "25.02.0"
+packages = ["rustc"]
+"#;
+
+/// Writes two project manifests with the same product, returning their installation ID and their
+/// canonical paths.
+pub(crate) fn project_manifests(test_env: &TestEnvironment) -> (String, Vec<String>) {
+    let mut manifests = Vec::new();
+    for project in ["1", "2"] {
+        let dir = test_env.root().join("projects").join(project);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("criticalup.toml");
+        std::fs::write(&path, PROJECT_MANIFEST).unwrap();
+        manifests.push(path.canonicalize().unwrap().to_str().unwrap().to_owned());
+    }
+    let manifest =
+        criticalup_core::project_manifest::ProjectManifest::load(Path::new(&manifests[0])).unwrap();
+    (manifest.products()[0].installation_id().0, manifests)
+}
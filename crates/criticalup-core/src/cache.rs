@@ -3,9 +3,14 @@
 
 use crate::errors::Error;
 use criticaltrust::manifests::ReleaseArtifactFormat;
+use sha2::{Digest, Sha256};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+/// Entries of the cache directory that used to be shared by all download servers, before each
+/// server got its own [`server_cache_dir`].
+const SHARED_CACHE_ENTRIES: &[&str] = &["keys.json", "root-rotations.json", "artifacts"];
+
 pub(crate) fn try_migrating_deprecated_path(
     deprecated_product_name_path: PathBuf,
     new_products_path: PathBuf,
@@ -31,11 +36,46 @@ pub(crate) fn try_migrating_deprecated_path(
     Ok(())
 }
 
-pub(crate) fn keys_cache_path(cache_dir: &Path) -> PathBuf {
+/// Directory caching the data of the download server at `base_url`. Every server gets its own
+/// directory, as different servers (for example mirrors) can trust different keys and serve
+/// different artifacts with the same names.
+///
+/// All the other functions of this module expect the directory returned by this function.
+pub fn server_cache_dir(cache_dir: &Path, base_url: &str) -> PathBuf {
+    let hash = Sha256::digest(base_url.trim_end_matches('/').as_bytes());
+    let id: String = hash[..8].iter().map(|b| format!("{b:02x}")).collect();
+    cache_dir.join("servers").join(id)
+}
+
+/// Moves the data cached before each download server got its own directory into the directory of
+/// the server it was downloaded from, which can only have been the default one. Entries already
+/// present in the server directory are not overwritten.
+pub(crate) fn migrate_shared_cache(cache_dir: &Path, server_cache_dir: &Path) -> Result<(), Error> {
+    for entry in SHARED_CACHE_ENTRIES {
+        let old = cache_dir.join(entry);
+        let new = server_cache_dir.join(entry);
+        if !matches!(old.try_exists(), Ok(true)) || matches!(new.try_exists(), Ok(true)) {
+            continue;
+        }
+        fs::create_dir_all(server_cache_dir)
+            .map_err(|e| Error::Create(server_cache_dir.into(), e))?;
+        tracing::debug!("Moving {} to {}", old.display(), new.display());
+        fs::rename(&old, &new).map_err(|e| Error::Write(new, e))?;
+    }
+    Ok(())
+}
+
+pub fn keys_cache_path(cache_dir: &Path) -> PathBuf {
     cache_dir.join("keys.json")
 }
 
-pub(crate) fn product_release_manifest_cache_path(
+/// Rotations of the root of trust accepted so far, kept even if the download server stops
+/// serving them to prevent rolling back to a previous root.
+pub fn root_rotations_cache_path(cache_dir: &Path) -> PathBuf {
+    cache_dir.join("root-rotations.json")
+}
+
+pub fn product_release_manifest_cache_path(
     cache_dir: &Path,
     product: &str,
     release: &str,
@@ -55,7 +95,7 @@ pub(crate) fn product_release_cache_path(
         .join(release)
 }
 
-pub(crate) fn package_cache_path(
+pub fn package_cache_path(
     cache_dir: &Path,
     product: &str,
     release: &str,
@@ -6,12 +6,16 @@ use crate::errors::Error;
 use std::env;
 use std::path::{Path, PathBuf};
 const DEFAULT_INSTALLATION_DIR_NAME: &str = "toolchains";
+const DEFAULT_OBJECTS_DIR_NAME: &str = "objects";
 
 #[cfg_attr(test, derive(Debug, PartialEq))]
 pub struct Paths {
     pub(crate) state_file: PathBuf,
     pub proxy_dir: PathBuf,
     pub installation_dir: PathBuf,
+    /// Content-addressed store the files of all installations are hardlinked from. It lives next
+    /// to the installations, as hardlinks can't cross filesystems.
+    pub objects_dir: PathBuf,
     pub cache_dir: PathBuf,
     pub root: PathBuf,
 }
@@ -43,6 +47,7 @@ impl Paths {
             state_file: root.join("state.json"),
             proxy_dir: root.join("proxy"),
             installation_dir: root.join(DEFAULT_INSTALLATION_DIR_NAME),
+            objects_dir: root.join(DEFAULT_OBJECTS_DIR_NAME),
             cache_dir,
             root,
         })
@@ -89,6 +94,7 @@ mod tests {
                 state_file: "/opt/criticalup/state.json".into(),
                 proxy_dir: "/opt/criticalup/proxy".into(),
                 installation_dir: "/opt/criticalup/toolchains".into(),
+                objects_dir: "/opt/criticalup/objects".into(),
                 cache_dir: "/cache/criticalup".into(),
                 root: "/opt/criticalup".into()
             },
@@ -2,16 +2,21 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::cache::{
-    keys_cache_path, package_cache_path, product_release_manifest_cache_path,
-    try_migrating_deprecated_path,
+    keys_cache_path, migrate_shared_cache, package_cache_path, product_release_manifest_cache_path,
+    root_rotations_cache_path, server_cache_dir, try_migrating_deprecated_path,
 };
 use crate::config::Config;
 use crate::envvars;
 use crate::errors::{DownloadServerError, Error};
+use crate::project_manifest::ProjectManifestProduct;
 use crate::state::{AuthenticationToken, State};
 use criticaltrust::keys::PublicKey;
-use criticaltrust::manifests::{ReleaseArtifactFormat, ReleaseManifest};
-use criticaltrust::signatures::Keychain;
+use criticaltrust::manifests::{
+    KeysManifest, ReleaseArtifactFormat, ReleaseManifest, RootRotation,
+};
+use criticaltrust::revocation_info::RevocationInfo;
+use criticaltrust::signatures::{KeyReport, Keychain, SignedPayload};
+use criticaltrust::Error as TrustError;
 use md5::Md5;
 use reqwest::header::{HeaderValue, AUTHORIZATION};
 use reqwest::{IntoUrl, Request, Response, StatusCode};
@@ -23,45 +28,43 @@ use sha2::Digest;
 use std::fmt::Display;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::time::Duration;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
 use tokio::fs as tokio_fs;
 
 const CLIENT_MAX_RETRIES: u32 = 5;
 
+/// How many days before the authentication token expires criticalup starts warning about it, unless
+/// overridden with the `CRITICALUP_TOKEN_EXPIRY_WARNING_DAYS` environment variable.
+pub const DEFAULT_TOKEN_EXPIRY_WARNING_DAYS: u32 = 14;
+
+/// Report produced by [`DownloadServerClient::keys_report`].
+pub struct KeysReport {
+    /// The trust root, followed by every key of the keys manifest.
+    pub keys: Vec<KeyReport>,
+    /// The revocation information, if it was successfully loaded.
+    pub revocation_info: Option<RevocationInfo>,
+    /// Why loading the keys manifest failed, if it did. Verifying anything fails in that case.
+    pub error: Option<TrustError>,
+}
+
 pub struct DownloadServerClient {
     pub(crate) cache_dir: PathBuf,
     base_url: String,
     pub(crate) client: ClientWithMiddleware,
     state: State,
+    auth_profile: Option<String>,
     trust_root: PublicKey,
     pub(crate) connectivity: Connectivity,
+    /// Whether the cache is left untouched, only reading from it.
+    read_only: bool,
 }
 
 impl DownloadServerClient {
     pub fn new(config: &Config, state: &State, connectivity: Connectivity) -> Self {
-        let retry_policy = ExponentialBackoff::builder().build_with_max_retries(CLIENT_MAX_RETRIES);
-        let client = reqwest::ClientBuilder::new()
-            .user_agent(config.whitelabel.http_user_agent)
-            .read_timeout(Duration::from_secs(90))
-            .connect_timeout(Duration::from_secs(90))
-            .pool_idle_timeout(Duration::from_secs(90))
-            // In rare cases we were encountering a hang in networking in Docker-in-Docker
-            // `docker buildx build` situations. This workaround seems to help.
-            // ref: https://github.com/hyperium/hyper/issues/2312#issuecomment-778005053
-            .pool_max_idle_per_host(0)
-            .build()
-            .expect("failed to configure http client");
-        let client = ClientBuilder::new(client)
-            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
-            .build();
-        let download_server_client = DownloadServerClient {
-            base_url: config.whitelabel.download_server_url.clone(),
-            client,
-            state: state.clone(),
-            trust_root: config.whitelabel.trust_root.clone(),
-            cache_dir: config.paths.cache_dir.clone(),
-            connectivity,
-        };
+        let download_server_client = Self::build(config, state, connectivity, false);
 
         // Trying migrating the obsolete cache.
         // old path: ~/.cache/criticalup/artifacts/ferrocene
@@ -89,15 +92,75 @@ impl DownloadServerClient {
             .inspect_err(|e| tracing::warn!("{:?}", e))
             .ok();
         }
+        // Then move the cache of the default download server into its own directory.
+        migrate_shared_cache(
+            &download_server_client.cache_dir,
+            &download_server_client.server_cache_dir(),
+        )
+        .inspect_err(|e| tracing::warn!("{:?}", e))
+        .ok();
 
         download_server_client
     }
 
+    /// Creates a client which reads the cache but never writes to it: the cache is not migrated,
+    /// and the downloaded data and accepted root key rotations are not persisted.
+    pub fn new_read_only(config: &Config, state: &State, connectivity: Connectivity) -> Self {
+        Self::build(config, state, connectivity, true)
+    }
+
+    fn build(config: &Config, state: &State, connectivity: Connectivity, read_only: bool) -> Self {
+        let retry_policy = ExponentialBackoff::builder().build_with_max_retries(CLIENT_MAX_RETRIES);
+        let client = reqwest::ClientBuilder::new()
+            .user_agent(config.whitelabel.http_user_agent)
+            .read_timeout(Duration::from_secs(90))
+            .connect_timeout(Duration::from_secs(90))
+            .pool_idle_timeout(Duration::from_secs(90))
+            // In rare cases we were encountering a hang in networking in Docker-in-Docker
+            // `docker buildx build` situations. This workaround seems to help.
+            // ref: https://github.com/hyperium/hyper/issues/2312#issuecomment-778005053
+            .pool_max_idle_per_host(0)
+            .build()
+            .expect("failed to configure http client");
+        let client = ClientBuilder::new(client)
+            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+            .build();
+        DownloadServerClient {
+            base_url: config.whitelabel.download_server_url.clone(),
+            client,
+            state: state.clone(),
+            auth_profile: None,
+            trust_root: config.whitelabel.trust_root.clone(),
+            cache_dir: config.paths.cache_dir.clone(),
+            connectivity,
+            read_only,
+        }
+    }
+
+    /// Directory caching the data of the download server this client currently uses.
+    pub(crate) fn server_cache_dir(&self) -> PathBuf {
+        server_cache_dir(&self.cache_dir, &self.base_url)
+    }
+
     pub async fn get_current_token_data(&self) -> Result<CurrentTokenData, Error> {
+        let auth_token = self.auth_token().await?;
+        self.token_data(auth_token).await
+    }
+
+    /// Retrieves the data of a specific authentication token, regardless of the token the client
+    /// would otherwise use.
+    pub async fn get_token_data(
+        &self,
+        token: &AuthenticationToken,
+    ) -> Result<CurrentTokenData, Error> {
+        self.token_data(Some(bearer_header(token)?)).await
+    }
+
+    async fn token_data(&self, auth_token: Option<HeaderValue>) -> Result<CurrentTokenData, Error> {
         let url = self.url("/v1/tokens/current");
 
         let mut req = self.client.get(&url);
-        if let Some(auth_token) = self.auth_token().await? {
+        if let Some(auth_token) = auth_token {
             req = req.header(AUTHORIZATION, auth_token);
         } else {
             return Err(Error::DownloadServerError {
@@ -116,21 +179,108 @@ impl DownloadServerClient {
                 let token_data = serde_json::from_slice(&data).map_err(Error::JsonSerialization)?;
                 Ok(token_data)
             }
-            _ => Err(unexpected_status(url, resp)),
+            _ => Err(unexpected_status(url, resp).await),
         }
     }
 
+    /// Warn if the authentication token used by this client has expired or is about to expire.
+    ///
+    /// This is a best effort check meant to be run by networked commands before doing their work:
+    /// it's skipped in offline mode, and failures to retrieve the token data are ignored, as the
+    /// command itself will report them when talking to the download server.
+    ///
+    /// Each token is only checked once per process for each download server, so commands can call
+    /// this for every client they create without sending redundant requests.
+    pub async fn warn_if_token_expires_soon(&self) {
+        static CHECKED: Mutex<Vec<(String, HeaderValue)>> = Mutex::new(Vec::new());
+
+        if self.connectivity == Connectivity::Offline {
+            return;
+        }
+        let auth_token = match self.auth_token().await {
+            Ok(Some(auth_token)) => auth_token,
+            Ok(None) => return,
+            Err(err) => {
+                tracing::trace!(%err, "Skipping the authentication token expiry check");
+                return;
+            }
+        };
+        {
+            let key = (self.base_url.clone(), auth_token.clone());
+            let mut checked = CHECKED.lock().unwrap_or_else(|e| e.into_inner());
+            if checked.contains(&key) {
+                return;
+            }
+            checked.push(key);
+        }
+
+        let data = match self.token_data(Some(auth_token)).await {
+            Ok(data) => data,
+            Err(err) => {
+                tracing::trace!(%err, "Skipping the authentication token expiry check");
+                return;
+            }
+        };
+
+        data.warn_if_expires_soon(token_expiry_warning_days());
+    }
+
     #[tracing::instrument(level = "trace", skip_all)]
     pub async fn keys(&self) -> Result<Keychain, Error> {
+        let (keychain, _, loaded) = self.load_keychain().await?;
+        loaded.map_err(Error::KeychainInitFailed)?;
+        Ok(keychain)
+    }
+
+    /// Report on the trust root and on every key served by the download server, including the
+    /// keys that are not trusted and why. Unlike [`keys`](Self::keys), failing to load the keys
+    /// manifest is included in the report rather than returned as an error.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub async fn keys_report(&self) -> Result<KeysReport, Error> {
+        let (keychain, keys_manifest, loaded) = self.load_keychain().await?;
+        Ok(KeysReport {
+            keys: keychain.report(&keys_manifest),
+            revocation_info: keychain.revocation_info().cloned(),
+            error: loaded.err(),
+        })
+    }
+
+    /// Build the keychain from the keys manifest, returning the outcome of loading the manifest
+    /// separately so that callers can still inspect a partially loaded keychain.
+    async fn load_keychain(
+        &self,
+    ) -> Result<(Keychain, KeysManifest, Result<(), TrustError>), Error> {
         let url = self.url("/v1/keys");
-        let cache_key = keys_cache_path(&self.cache_dir);
+        let cache_key = keys_cache_path(&self.server_cache_dir());
 
         let data: Vec<u8> = self.cacheable(url, cache_key).await?;
-        let keys_manifest = serde_json::from_slice(&data).map_err(Error::JsonSerialization)?;
+        let keys_manifest: KeysManifest =
+            serde_json::from_slice(&data).map_err(Error::JsonSerialization)?;
+
+        let rotations_path = root_rotations_cache_path(&self.server_cache_dir());
+        let accepted_rotations: Vec<SignedPayload<RootRotation>> = match fs::read(&rotations_path) {
+            Ok(data) => serde_json::from_slice(&data).map_err(Error::JsonSerialization)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(Error::Read(rotations_path, err)),
+        };
 
         let mut keychain = Keychain::new(&self.trust_root).map_err(Error::KeychainInitFailed)?;
-        let _ = keychain.load_all(&keys_manifest);
-        Ok(keychain)
+        keychain
+            .follow_root_rotations(&accepted_rotations)
+            .map_err(Error::KeychainInitFailed)?;
+        let loaded = keychain.load_all(&keys_manifest);
+
+        if !self.read_only
+            && loaded.is_ok()
+            && keychain.root_rotations().len() > accepted_rotations.len()
+        {
+            let data = serde_json::to_vec_pretty(&keychain.root_rotations())
+                .map_err(Error::JsonSerialization)?;
+            tokio_fs::write(&rotations_path, data)
+                .await
+                .map_err(|e| Error::Write(rotations_path, e))?;
+        }
+        Ok((keychain, keys_manifest, loaded))
     }
 
     #[tracing::instrument(level = "trace", skip_all, fields(
@@ -143,7 +293,8 @@ impl DownloadServerClient {
         release: &str,
     ) -> Result<ReleaseManifest, Error> {
         let url = self.url(&format!("/v1/releases/{product}/{release}"));
-        let cache_key = product_release_manifest_cache_path(&self.cache_dir, product, release);
+        let cache_key =
+            product_release_manifest_cache_path(&self.server_cache_dir(), product, release);
 
         let data = self.cacheable(url, cache_key).await?;
 
@@ -167,7 +318,8 @@ impl DownloadServerClient {
         let url = self.url(&format!(
             "/v1/releases/{product}/{release}/download/{package}/{artifact_format}"
         ));
-        let cache_key = package_cache_path(&self.cache_dir, product, release, package, format);
+        let cache_key =
+            package_cache_path(&self.server_cache_dir(), product, release, package, format);
         tracing::info!("Downloading component '{package}' for '{product}' ({release})",);
         let data = self.cacheable(url, cache_key).await?;
 
@@ -206,6 +358,9 @@ impl DownloadServerClient {
                 StatusCode::OK => {
                     tracing::trace!(status = %resp.status(), "Downloading");
                     let data = resp.bytes().await?;
+                    if self.read_only {
+                        return Ok(data.to_vec());
+                    }
                     if let Some(parent) = cache_key.parent() {
                         fs::create_dir_all(parent)
                             .map_err(|e| Error::Create(parent.to_path_buf(), e))?;
@@ -223,7 +378,7 @@ impl DownloadServerClient {
                 }
                 _ => {
                     tracing::trace!(status = %resp.status(), "Unexpected status");
-                    return Err(unexpected_status(url, resp));
+                    return Err(unexpected_status(url, resp).await);
                 }
             }
         };
@@ -262,33 +417,41 @@ impl DownloadServerClient {
     }
 
     pub(crate) async fn auth_token(&self) -> Result<Option<HeaderValue>, Error> {
-        let token_from_env: Option<AuthenticationToken> = envvars::EnvVars::new()
-            .criticalup_token
-            .map(|item| item.into());
-
-        let token_from_state = self.state.authentication_token().await;
+        // The token from the environment takes precedence over the profiles, so it is used even
+        // when the profile selected by the project manifest is missing.
+        if let Some(token) = envvars::EnvVars::new().criticalup_token {
+            tracing::trace!("Using token from `CRITICALUP_TOKEN` environment variable");
+            return bearer_header(&token.into()).map(Some);
+        }
 
-        // Set precedence for tokens.
-        let token = match (token_from_env, token_from_state) {
-            (Some(token), _) => {
-                tracing::trace!("Using token from `CRITICALUP_TOKEN` environment variable");
-                Some(token)
-            }
-            (_, Some(token)) => {
-                tracing::trace!("Using token from state");
-                Some(token)
-            }
-            _ => None,
+        let token = match &self.auth_profile {
+            Some(profile) => Some(
+                self.state
+                    .authentication_profile(profile)
+                    .ok_or_else(|| Error::AuthenticationProfileNotFound(profile.clone()))?,
+            ),
+            None => self.state.authentication_token().await,
         };
+        if token.is_some() {
+            tracing::trace!(profile = ?self.auth_profile, "Using token from state");
+        }
 
-        if let Some(token) = token {
-            Ok(Some(
-                HeaderValue::from_str(&format!("Bearer {}", token.unseal()))
-                    .map_err(|_| Error::InvalidAuthenicationToken)?,
-            ))
-        } else {
-            Ok(None)
+        token.as_ref().map(bearer_header).transpose()
+    }
+
+    /// Sets the named authentication profile this [`DownloadServerClient`] uses, instead of the
+    /// default authentication token.
+    pub fn set_auth_profile(&mut self, profile: Option<String>) {
+        self.auth_profile = profile;
+    }
+
+    /// Applies the download server and authentication profile the project manifest selected for
+    /// `product`, leaving the defaults in place for any setting the product does not override.
+    pub fn set_product_settings(&mut self, product: &ProjectManifestProduct) {
+        if let Some(download_server) = product.download_server() {
+            self.base_url = download_server.trim_end_matches('/').to_string();
         }
+        self.auth_profile = product.auth_profile().map(String::from);
     }
 
     /// Sets the base url of this [`DownloadServerClient`].
@@ -302,10 +465,17 @@ impl DownloadServerClient {
     }
 }
 
-pub(crate) fn unexpected_status(url: String, response: Response) -> Error {
+fn bearer_header(token: &AuthenticationToken) -> Result<HeaderValue, Error> {
+    HeaderValue::from_str(&format!("Bearer {}", token.unseal()))
+        .map_err(|_| Error::InvalidAuthenicationToken)
+}
+
+pub(crate) async fn unexpected_status(url: String, response: Response) -> Error {
     let kind = match response.status() {
         StatusCode::BAD_REQUEST => DownloadServerError::BadRequest,
-        StatusCode::FORBIDDEN => DownloadServerError::AuthenticationFailed,
+        StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => {
+            authentication_failure(response.bytes().await.ok().as_deref())
+        }
         StatusCode::NOT_FOUND => DownloadServerError::NotFound,
         StatusCode::TOO_MANY_REQUESTS => DownloadServerError::RateLimited,
 
@@ -315,6 +485,31 @@ pub(crate) fn unexpected_status(url: String, response: Response) -> Error {
     Error::DownloadServerError { url, kind }
 }
 
+/// The download server can explain why it rejected the authentication token in the body of the
+/// response. Older servers (or unknown reasons) fall back to a generic authentication failure.
+fn authentication_failure(body: Option<&[u8]>) -> DownloadServerError {
+    #[derive(Deserialize)]
+    struct ErrorResponse {
+        error: String,
+    }
+
+    let reason = body.and_then(|body| serde_json::from_slice::<ErrorResponse>(body).ok());
+    match reason.as_ref().map(|r| r.error.as_str()) {
+        Some("token-expired") => DownloadServerError::AuthenticationTokenExpired,
+        Some("token-revoked") => DownloadServerError::AuthenticationTokenRevoked,
+        Some("token-malformed") => DownloadServerError::AuthenticationTokenMalformed,
+        _ => DownloadServerError::AuthenticationFailed,
+    }
+}
+
+/// Number of days before the expiry of the authentication token when criticalup should start
+/// warning about it.
+pub fn token_expiry_warning_days() -> u32 {
+    envvars::EnvVars::new()
+        .token_expiry_warning_days
+        .unwrap_or(DEFAULT_TOKEN_EXPIRY_WARNING_DAYS)
+}
+
 #[derive(Default, Clone, Copy, Debug, PartialEq)]
 pub enum Connectivity {
     #[default]
@@ -340,6 +535,60 @@ pub struct CurrentTokenData {
     pub expires_at: Option<String>,
 }
 
+impl CurrentTokenData {
+    /// Parses the expiry timestamp returned by the download server, if any.
+    pub fn expires_at(&self) -> Option<OffsetDateTime> {
+        let expires_at = self.expires_at.as_deref()?;
+        match OffsetDateTime::parse(expires_at, &Rfc3339) {
+            Ok(parsed) => Some(parsed),
+            Err(err) => {
+                tracing::trace!(%err, %expires_at, "Failed to parse the token expiry");
+                None
+            }
+        }
+    }
+
+    /// Logs a warning if the token has expired or expires within `warning_days` days.
+    pub fn warn_if_expires_soon(&self, warning_days: u32) {
+        match self.expiry_status(warning_days, OffsetDateTime::now_utc()) {
+            TokenExpiryStatus::Expired(_) => tracing::warn!(
+                "The authentication token '{}' expired on {}; please run `criticalup auth set` \
+                with a new token.",
+                self.name,
+                self.expires_at.as_deref().unwrap_or_default(),
+            ),
+            TokenExpiryStatus::ExpiresSoon(_) => tracing::warn!(
+                "The authentication token '{}' expires on {}; please create a new token and run \
+                `criticalup auth set` before then.",
+                self.name,
+                self.expires_at.as_deref().unwrap_or_default(),
+            ),
+            TokenExpiryStatus::NoExpiry | TokenExpiryStatus::Valid(_) => {}
+        }
+    }
+
+    /// Classifies the expiry of the token at the `now` instant, considering the token as expiring
+    /// soon if it expires within `warning_days` days.
+    pub fn expiry_status(&self, warning_days: u32, now: OffsetDateTime) -> TokenExpiryStatus {
+        match self.expires_at() {
+            None => TokenExpiryStatus::NoExpiry,
+            Some(expires_at) if expires_at <= now => TokenExpiryStatus::Expired(expires_at),
+            Some(expires_at) if expires_at - now <= time::Duration::days(warning_days.into()) => {
+                TokenExpiryStatus::ExpiresSoon(expires_at)
+            }
+            Some(expires_at) => TokenExpiryStatus::Valid(expires_at),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenExpiryStatus {
+    NoExpiry,
+    Valid(OffsetDateTime),
+    ExpiresSoon(OffsetDateTime),
+    Expired(OffsetDateTime),
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -349,8 +598,8 @@ mod tests {
         TestEnvironment, SAMPLE_AUTH_TOKEN_CUSTOMER, SAMPLE_AUTH_TOKEN_EXPIRY,
         SAMPLE_AUTH_TOKEN_NAME,
     };
-    use criticaltrust::keys::KeyPair;
-    use criticaltrust::signatures::PublicKeysRepository;
+    use criticaltrust::keys::{EphemeralKeyPair, KeyPair};
+    use criticaltrust::signatures::{KeyStatus, PublicKeysRepository};
     use md5::Md5;
     use reqwest::header::IF_NONE_MATCH;
     use sha2::Digest;
@@ -401,15 +650,69 @@ mod tests {
         let test_env = TestEnvironment::with().download_server().prepare().await;
         let download_server_client = test_env.download_server();
         let res = product_release_cache_path(
-            &download_server_client.cache_dir,
+            &download_server_client.server_cache_dir(),
             "ferrocene",
             "stable-25.05.0",
         );
 
         let cache_dir: PathBuf = test_env.config().paths.cache_dir.clone();
         let expected = "artifacts/products/ferrocene/releases/stable-25.05.0";
-        let cache_dir = cache_dir.join(expected);
-        assert_eq!(cache_dir, res);
+        let server_dir = server_cache_dir(&cache_dir, download_server_client.base_url());
+        assert!(server_dir.starts_with(cache_dir.join("servers")));
+        assert_eq!(server_dir.join(expected), res);
+    }
+
+    #[tokio::test]
+    async fn test_servers_have_separate_caches() {
+        let test_env = TestEnvironment::with().download_server().prepare().await;
+        let mut mirror =
+            DownloadServerClient::new(test_env.config(), test_env.state(), Connectivity::Online);
+        mirror.set_base_url(format!("{}/mirror", test_env.download_server().base_url()));
+
+        let server_dir = test_env.download_server().server_cache_dir();
+        assert_ne!(server_dir, mirror.server_cache_dir());
+        // Trailing slashes do not change the server.
+        assert_eq!(
+            server_dir,
+            server_cache_dir(
+                &test_env.config().paths.cache_dir,
+                &format!("{}/", test_env.download_server().base_url())
+            )
+        );
+
+        // The keys downloaded from one server are not used by the other one, even offline.
+        test_env.download_server().keys().await.unwrap();
+        assert!(keys_cache_path(&server_dir).is_file());
+        assert!(!keys_cache_path(&mirror.server_cache_dir()).exists());
+        let mut offline_mirror =
+            DownloadServerClient::new(test_env.config(), test_env.state(), Connectivity::Offline);
+        offline_mirror.set_base_url(mirror.base_url().into());
+        assert!(offline_mirror.keys().await.is_err());
+    }
+
+    #[test]
+    fn test_shared_cache_is_migrated() {
+        let cache_dir = tempdir().unwrap();
+        fs::write(cache_dir.path().join("keys.json"), "keys").unwrap();
+        fs::create_dir_all(cache_dir.path().join("artifacts/products")).unwrap();
+        let server_dir = server_cache_dir(cache_dir.path(), "https://example.com");
+        fs::create_dir_all(&server_dir).unwrap();
+        fs::write(server_dir.join("root-rotations.json"), "new").unwrap();
+        fs::write(cache_dir.path().join("root-rotations.json"), "old").unwrap();
+
+        migrate_shared_cache(cache_dir.path(), &server_dir).unwrap();
+
+        assert_eq!(
+            "keys",
+            fs::read_to_string(server_dir.join("keys.json")).unwrap()
+        );
+        assert!(server_dir.join("artifacts/products").is_dir());
+        assert!(!cache_dir.path().join("keys.json").exists());
+        // Entries already in the server directory are not overwritten.
+        assert_eq!(
+            "new",
+            fs::read_to_string(server_dir.join("root-rotations.json")).unwrap()
+        );
     }
 
     #[tokio::test]
@@ -473,6 +776,47 @@ mod tests {
         assert_eq!(0, test_env.requests_served_by_mock_download_server().await);
     }
 
+    #[tokio::test]
+    async fn test_get_current_token_with_auth_profile() {
+        let test_env = TestEnvironment::with().download_server().prepare().await;
+        let state = test_env.state();
+        state.set_authentication_profile("acme", state.authentication_token().await);
+        state.set_authentication_token(Some(AuthenticationToken::seal("wrong")));
+
+        let mut client = DownloadServerClient::new(test_env.config(), state, Connectivity::Online);
+        client.set_auth_profile(Some("acme".into()));
+        assert_eq!(
+            SAMPLE_AUTH_TOKEN_NAME,
+            client.get_current_token_data().await.unwrap().name
+        );
+
+        client.set_auth_profile(Some("missing".into()));
+        assert!(matches!(
+            client.get_current_token_data().await.unwrap_err(),
+            Error::AuthenticationProfileNotFound(profile) if profile == "missing",
+        ));
+
+        assert_eq!(1, test_env.requests_served_by_mock_download_server().await);
+    }
+
+    #[tokio::test]
+    async fn test_get_token_data() {
+        let test_env = TestEnvironment::with().download_server().prepare().await;
+        let token = test_env.state().authentication_token().await.unwrap();
+        test_env.state().set_authentication_token(None);
+
+        assert_eq!(
+            SAMPLE_AUTH_TOKEN_CUSTOMER,
+            test_env
+                .download_server()
+                .get_token_data(&token)
+                .await
+                .unwrap()
+                .organization_name,
+        );
+        assert_eq!(1, test_env.requests_served_by_mock_download_server().await);
+    }
+
     #[tokio::test]
     async fn test_get_keys() {
         let test_env = TestEnvironment::with().download_server().prepare().await;
@@ -519,6 +863,153 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_get_keys_follows_root_rotation() {
+        let test_env = TestEnvironment::with().root_rotation().prepare().await;
+        let keys = test_env.keys();
+        let rotations_path =
+            root_rotations_cache_path(&test_env.download_server().server_cache_dir());
+
+        let keychain = test_env.download_server().keys().await.unwrap();
+
+        assert_eq!(keys.alternate_trust_root.public(), keychain.trust_root());
+        for expected_present in &[
+            &keys.alternate_trust_root,
+            &keys.alternate_root,
+            &keys.alternate_packages,
+        ] {
+            assert!(keychain
+                .get(&expected_present.public().calculate_id())
+                .is_some());
+        }
+        for expected_missing in &[&keys.trust_root, &keys.root, &keys.packages] {
+            assert!(keychain
+                .get(&expected_missing.public().calculate_id())
+                .is_none());
+        }
+
+        let persisted: Vec<SignedPayload<RootRotation>> =
+            serde_json::from_slice(&fs::read(rotations_path).unwrap()).unwrap();
+        assert_eq!(1, persisted.len());
+    }
+
+    #[tokio::test]
+    async fn test_get_keys_follows_persisted_root_rotation() {
+        // The download server doesn't serve the rotation anymore.
+        let test_env = TestEnvironment::with().download_server().prepare().await;
+        let keys = test_env.keys();
+
+        let rotations_path =
+            root_rotations_cache_path(&test_env.download_server().server_cache_dir());
+        fs::create_dir_all(rotations_path.parent().unwrap()).unwrap();
+        fs::write(
+            &rotations_path,
+            serde_json::to_vec(&[keys.root_rotation().await]).unwrap(),
+        )
+        .unwrap();
+
+        let keychain = test_env.download_server().keys().await.unwrap();
+
+        assert_eq!(keys.alternate_trust_root.public(), keychain.trust_root());
+        assert!(keychain
+            .get(&keys.alternate_packages.public().calculate_id())
+            .is_some());
+        assert!(keychain
+            .get(&keys.packages.public().calculate_id())
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_keys_report() {
+        let test_env = TestEnvironment::with().download_server().prepare().await;
+        let keys = test_env.keys();
+
+        let report = test_env.download_server().keys_report().await.unwrap();
+        assert!(report.error.is_none());
+        assert!(report.revocation_info.is_some());
+
+        let status = |key: &EphemeralKeyPair| {
+            &report
+                .keys
+                .iter()
+                .find(|entry| entry.key.as_ref() == Some(key.public()))
+                .unwrap()
+                .status
+        };
+        assert!(matches!(status(&keys.trust_root), KeyStatus::TrustRoot));
+        assert!(matches!(status(&keys.packages), KeyStatus::Trusted));
+        assert!(matches!(
+            status(&keys.alternate_root),
+            KeyStatus::Untrusted(_)
+        ));
+        assert!(matches!(
+            status(&keys.alternate_packages),
+            KeyStatus::Untrusted(_)
+        ));
+    }
+
+    #[test]
+    fn test_token_expiry_status() {
+        let now = OffsetDateTime::parse("2024-06-01T00:00:00+00:00", &Rfc3339).unwrap();
+        let token = |expires_at: Option<&str>| CurrentTokenData {
+            name: "name".into(),
+            organization_name: "organization".into(),
+            expires_at: expires_at.map(String::from),
+        };
+        let parse = |timestamp: &str| OffsetDateTime::parse(timestamp, &Rfc3339).unwrap();
+
+        assert_eq!(
+            TokenExpiryStatus::NoExpiry,
+            token(None).expiry_status(14, now)
+        );
+        assert_eq!(
+            TokenExpiryStatus::NoExpiry,
+            token(Some("not a timestamp")).expiry_status(14, now)
+        );
+        assert_eq!(
+            TokenExpiryStatus::Expired(parse("2024-05-31T00:00:00+00:00")),
+            token(Some("2024-05-31T00:00:00+00:00")).expiry_status(14, now)
+        );
+        assert_eq!(
+            TokenExpiryStatus::ExpiresSoon(parse("2024-06-10T00:00:00+00:00")),
+            token(Some("2024-06-10T00:00:00+00:00")).expiry_status(14, now)
+        );
+        assert_eq!(
+            TokenExpiryStatus::Valid(parse("2024-06-10T00:00:00+00:00")),
+            token(Some("2024-06-10T00:00:00+00:00")).expiry_status(7, now)
+        );
+    }
+
+    #[test]
+    fn test_authentication_failure_reasons() {
+        let reason = |body: &str| authentication_failure(Some(body.as_bytes()));
+
+        assert!(matches!(
+            reason(r#"{"error": "token-expired"}"#),
+            DownloadServerError::AuthenticationTokenExpired
+        ));
+        assert!(matches!(
+            reason(r#"{"error": "token-revoked"}"#),
+            DownloadServerError::AuthenticationTokenRevoked
+        ));
+        assert!(matches!(
+            reason(r#"{"error": "token-malformed"}"#),
+            DownloadServerError::AuthenticationTokenMalformed
+        ));
+        assert!(matches!(
+            reason(r#"{"error": "something-else"}"#),
+            DownloadServerError::AuthenticationFailed
+        ));
+        assert!(matches!(
+            reason(""),
+            DownloadServerError::AuthenticationFailed
+        ));
+        assert!(matches!(
+            authentication_failure(None),
+            DownloadServerError::AuthenticationFailed
+        ));
+    }
+
     async fn assert_auth_failed(test_env: &TestEnvironment) {
         assert!(matches!(
             test_env
@@ -4,10 +4,13 @@
 use std::env::VarError;
 
 pub const CRITICALUP_TOKEN_ENV_VAR_NAME: &str = "CRITICALUP_TOKEN";
+pub const CRITICALUP_TOKEN_EXPIRY_WARNING_DAYS_ENV_VAR_NAME: &str =
+    "CRITICALUP_TOKEN_EXPIRY_WARNING_DAYS";
 
 #[derive(Default)]
 pub struct EnvVars {
     pub criticalup_token: Option<String>,
+    pub token_expiry_warning_days: Option<u32>,
 }
 
 impl EnvVars {
@@ -31,6 +34,33 @@ impl EnvVars {
             }
         };
 
-        EnvVars { criticalup_token }
+        let token_expiry_warning_days =
+            match std::env::var(CRITICALUP_TOKEN_EXPIRY_WARNING_DAYS_ENV_VAR_NAME) {
+                Ok(value) if value.is_empty() => None,
+                Ok(value) => match value.parse() {
+                    Ok(days) => Some(days),
+                    Err(_) => {
+                        tracing::error!(
+                            "Environment variable {} must be a number of days, ignoring it.",
+                            CRITICALUP_TOKEN_EXPIRY_WARNING_DAYS_ENV_VAR_NAME
+                        );
+                        None
+                    }
+                },
+                Err(var_err) => {
+                    if let VarError::NotUnicode(_) = var_err {
+                        tracing::error!(
+                            "Environment variable {} is not Unicode.",
+                            CRITICALUP_TOKEN_EXPIRY_WARNING_DAYS_ENV_VAR_NAME
+                        );
+                    }
+                    None
+                }
+            };
+
+        EnvVars {
+            criticalup_token,
+            token_expiry_warning_days,
+        }
     }
 }
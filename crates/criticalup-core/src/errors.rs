@@ -31,6 +31,9 @@ pub enum Error {
     #[error("Invalid authentication token provided.")]
     InvalidAuthenicationToken,
 
+    #[error("Authentication profile '{0}' does not exist; add it with `criticalup auth set --profile {0}`.")]
+    AuthenticationProfileNotFound(String),
+
     #[error("Network access required, but in offline mode.")]
     OfflineMode,
 
@@ -65,6 +68,18 @@ pub enum Error {
         // Otherwise Clippy will tell you to try reducing the size of `errors::Error`.
         kind: Box<ProjectManifestLoadingError>,
     },
+    #[error("Failed to edit the project manifest at {}.", .path.display(),)]
+    ProjectManifestEditingFailed {
+        path: PathBuf,
+        #[source]
+        kind: ProjectManifestEditingError,
+    },
+    #[error("Failed to use the rustup toolchain file at {}.", .path.display(),)]
+    RustToolchainFileFailed {
+        path: PathBuf,
+        #[source]
+        kind: RustToolchainFileError,
+    },
     #[error("Failed to create product directory for product {} at {}.", .product, .path.display())]
     ProjectManifestProductDirCreationFailed {
         path: PathBuf,
@@ -128,6 +143,12 @@ pub enum WriteFileError {
 pub enum DownloadServerError {
     #[error("Failed to authenticate; missing or wrong authentication token.")]
     AuthenticationFailed,
+    #[error("Failed to authenticate; the authentication token has expired.")]
+    AuthenticationTokenExpired,
+    #[error("Failed to authenticate; the authentication token has been revoked.")]
+    AuthenticationTokenRevoked,
+    #[error("Failed to authenticate; the authentication token is malformed.")]
+    AuthenticationTokenMalformed,
     #[error("Resource not found.")]
     NotFound,
     #[error("Invalid request sent to the server.")]
@@ -190,10 +211,55 @@ pub enum ProjectManifestLoadingError {
     Please provide at least one package in the 'packages' list.", .product_name)]
     MissingPackagesInManifestProduct { product_name: String },
 
+    #[error("The 'format' of product '{}' in your project manifest is not supported; \
+    supported formats are 'tar.xz' and 'tar.zst'.", .product_name)]
+    UnsupportedArtifactFormat { product_name: String },
+
     #[error("Unknown substitution variable: ${{{0}}}.")]
     UnknownVariableInSubstitution(String),
     #[error("Unterminated substitution.")]
     UnterminatedVariableInSubstitution,
+    #[error("Environment variable {0} used in a substitution is not set (or is not Unicode).")]
+    UnsetEnvironmentVariableInSubstitution(String),
+    #[error("Substitution variable ${{{0}}} requires the product to have 'targets' configured.")]
+    NoTargetsForVariableInSubstitution(String),
+    #[error(
+        "Substitution variable ${{{0}}} requires the product to have exactly one target \
+        configured, but it has {1}; use ${{targets}} to refer to all of them."
+    )]
+    MultipleTargetsForVariableInSubstitution(String, usize),
+    #[error(
+        "Substitution variable ${{{0}}} expands to a list and can only be used in 'packages'."
+    )]
+    ListVariableNotAllowedInSubstitution(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProjectManifestEditingError {
+    #[error("Failed to parse.")]
+    FailedToParse(#[source] toml_edit::TomlError),
+    #[error("The project manifest has no product named '{0}'.")]
+    ProductNotFound(String),
+    #[error("The project manifest has no products.")]
+    NoProducts,
+    #[error("The '{0}' field of the project manifest has an unexpected type.")]
+    UnexpectedFieldType(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RustToolchainFileError {
+    #[error("Failed to parse.")]
+    FailedToParse(#[source] toml_edit::de::Error),
+    #[error("Failed to parse.")]
+    FailedToEdit(#[source] toml_edit::TomlError),
+    #[error("The toolchain file does not specify a channel.")]
+    MissingChannel,
+    #[error(
+        "The channel '{0}' is not a Ferrocene release; please pass the release with `--release`."
+    )]
+    ChannelNotARelease(String),
+    #[error("The 'toolchain' field of the toolchain file has an unexpected type.")]
+    UnexpectedToolchainType,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -7,6 +7,7 @@ pub mod config;
 pub mod download_server_client;
 pub mod envvars;
 pub mod errors;
+pub mod object_store;
 pub mod project_manifest;
 pub mod state;
 mod utils;
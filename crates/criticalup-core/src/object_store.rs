@@ -0,0 +1,189 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::config::Config;
+use crate::errors::Error;
+use sha2::{Digest, Sha256};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Content-addressed store shared by all installations.
+///
+/// Every installed file is hardlinked to an object named after the SHA-256 of its contents and its
+/// mode, so installations sharing packages also share the bytes on disk. Since files are only
+/// linked after the installation was verified, the SHA-256 is the same one listed for the file in
+/// the signed package manifest.
+pub struct ObjectStore {
+    dir: PathBuf,
+}
+
+impl ObjectStore {
+    pub fn new(config: &Config) -> Self {
+        ObjectStore {
+            dir: config.paths.objects_dir.clone(),
+        }
+    }
+
+    /// Hex-encoded SHA-256 of `contents`, the key of the object storing them.
+    pub fn hash(contents: &[u8]) -> String {
+        Sha256::digest(contents)
+            .into_iter()
+            .map(|v| format!("{:02x}", v))
+            .collect()
+    }
+
+    /// Replaces the installed file at `path` with a hardlink to the object with the same contents
+    /// and mode, adding the file to the store first if no such object exists yet.
+    ///
+    /// An existing object is hashed again before linking it, and replaced by `path` if its
+    /// contents no longer match `sha256`, so a tampered object never spreads to new installations.
+    pub fn link(&self, path: &Path, sha256: &str, mode: u32) -> Result<(), Error> {
+        let object = self.object_path(sha256, mode);
+
+        if object.is_file() && !self.is_intact(&object, sha256)? {
+            tracing::warn!(
+                "Replacing object '{}', as its contents do not match its SHA-256",
+                object.display()
+            );
+            std::fs::remove_file(&object).map_err(|e| Error::Write(object.clone(), e))?;
+        }
+
+        if object.is_file() {
+            // Link under a temporary name first, so that `path` is atomically replaced.
+            let mut temporary = path.as_os_str().to_owned();
+            temporary.push(".criticalup-link");
+            let temporary = PathBuf::from(temporary);
+
+            std::fs::hard_link(&object, &temporary)
+                .map_err(|e| Error::Write(temporary.clone(), e))?;
+            std::fs::rename(&temporary, path).map_err(|e| {
+                let _ = std::fs::remove_file(&temporary);
+                Error::Write(path.into(), e)
+            })?;
+        } else {
+            let parent = object
+                .parent()
+                .expect("objects are stored in a subdirectory");
+            std::fs::create_dir_all(parent).map_err(|e| Error::Create(parent.into(), e))?;
+            match std::fs::hard_link(path, &object) {
+                Ok(()) => {}
+                // Another installation stored the same object in the meantime.
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(Error::Write(object, e)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Paths of all the objects in the store.
+    pub fn objects(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut objects = Vec::new();
+        let shards = match std::fs::read_dir(&self.dir) {
+            Ok(shards) => shards,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(objects),
+            Err(e) => return Err(Error::Read(self.dir.clone(), e)),
+        };
+        for shard in shards {
+            let shard = shard.map_err(|e| Error::Read(self.dir.clone(), e))?.path();
+            for object in std::fs::read_dir(&shard).map_err(|e| Error::Read(shard.clone(), e))? {
+                objects.push(object.map_err(|e| Error::Read(shard.clone(), e))?.path());
+            }
+        }
+        Ok(objects)
+    }
+
+    fn is_intact(&self, object: &Path, sha256: &str) -> Result<bool, Error> {
+        let contents = std::fs::read(object).map_err(|e| Error::Read(object.into(), e))?;
+        Ok(Self::hash(&contents) == sha256)
+    }
+
+    fn object_path(&self, sha256: &str, mode: u32) -> PathBuf {
+        // Files with the same contents but a different mode can't share an inode. Only the
+        // permission bits are part of the key, as callers may or may not include the file type.
+        let mode = mode & 0o7777;
+        self.dir
+            .join(&sha256[..2])
+            .join(format!("{sha256}-{mode:o}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestEnvironment;
+
+    #[tokio::test]
+    async fn test_link_shares_identical_files() {
+        let test_env = TestEnvironment::with().prepare().await;
+        let store = ObjectStore::new(test_env.config());
+        let dir = tempfile::tempdir().unwrap();
+
+        let first = dir.path().join("first");
+        let second = dir.path().join("second");
+        std::fs::write(&first, "hello").unwrap();
+        std::fs::write(&second, "hello").unwrap();
+        let sha256 = ObjectStore::hash(b"hello");
+
+        store.link(&first, &sha256, 0o644).unwrap();
+        store.link(&second, &sha256, 0o644).unwrap();
+
+        assert_eq!(1, store.objects().unwrap().len());
+        assert_eq!("hello", std::fs::read_to_string(&second).unwrap());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let first = std::fs::metadata(&first).unwrap();
+            let second = std::fs::metadata(&second).unwrap();
+            assert_eq!(first.ino(), second.ino());
+            assert_eq!(3, first.nlink());
+        }
+
+        // A different mode is stored as a different object.
+        let third = dir.path().join("third");
+        std::fs::write(&third, "hello").unwrap();
+        store.link(&third, &sha256, 0o755).unwrap();
+        assert_eq!(2, store.objects().unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn test_link_replaces_tampered_object() {
+        let test_env = TestEnvironment::with().prepare().await;
+        let store = ObjectStore::new(test_env.config());
+        let dir = tempfile::tempdir().unwrap();
+        let sha256 = ObjectStore::hash(b"hello");
+
+        let first = dir.path().join("first");
+        std::fs::write(&first, "hello").unwrap();
+        store.link(&first, &sha256, 0o644).unwrap();
+
+        // Replace the stored object with different contents under the same name.
+        let object = store.object_path(&sha256, 0o644);
+        std::fs::remove_file(&object).unwrap();
+        std::fs::write(&object, "tampered").unwrap();
+
+        let second = dir.path().join("second");
+        std::fs::write(&second, "hello").unwrap();
+        store.link(&second, &sha256, 0o644).unwrap();
+
+        assert_eq!(1, store.objects().unwrap().len());
+        assert_eq!("hello", std::fs::read_to_string(&second).unwrap());
+        assert_eq!("hello", std::fs::read_to_string(&object).unwrap());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let object = std::fs::metadata(&object).unwrap();
+            let second = std::fs::metadata(&second).unwrap();
+            assert_eq!(object.ino(), second.ino());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_objects_of_missing_store() {
+        let test_env = TestEnvironment::with().prepare().await;
+        assert!(ObjectStore::new(test_env.config())
+            .objects()
+            .unwrap()
+            .is_empty());
+    }
+}
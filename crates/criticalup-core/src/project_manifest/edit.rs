@@ -0,0 +1,242 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::errors::{Error, ProjectManifestEditingError};
+use crate::project_manifest::{parse, ProjectManifest, LATEST_PROJECT_MANIFEST_VERSION};
+use std::path::{Path, PathBuf};
+use toml_edit::{Array, DocumentMut, Item, TableLike};
+
+/// Editor changing the `criticalup.toml` project manifest in place, preserving the formatting and
+/// comments of everything it does not touch.
+pub struct ProjectManifestEditor {
+    path: PathBuf,
+    document: DocumentMut,
+}
+
+impl ProjectManifestEditor {
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| Error::Read(path.to_path_buf(), e))?;
+        let document = contents
+            .parse::<DocumentMut>()
+            .map_err(|e| editing_failed(path, ProjectManifestEditingError::FailedToParse(e)))?;
+
+        Ok(ProjectManifestEditor {
+            path: path.to_path_buf(),
+            document,
+        })
+    }
+
+    /// Name of the product to edit: `requested` if provided, otherwise the first product of the
+    /// manifest.
+    pub fn product_name(&self, requested: Option<&str>) -> Result<String, Error> {
+        let products = self.document.get("products").and_then(Item::as_table_like);
+        match requested {
+            Some(name) if products.is_some_and(|p| p.contains_key(name)) => Ok(name.into()),
+            Some(name) => {
+                Err(self.error(ProjectManifestEditingError::ProductNotFound(name.into())))
+            }
+            None => products
+                .and_then(|p| p.iter().next().map(|(name, _)| name.to_string()))
+                .ok_or_else(|| self.error(ProjectManifestEditingError::NoProducts)),
+        }
+    }
+
+    /// Upgrades the manifest to the latest `manifest-version`, needed before using settings that
+    /// older versions do not accept. Every older manifest is also a valid manifest of the latest
+    /// version, so nothing else needs to change.
+    pub fn upgrade_version(&mut self) {
+        let current = self
+            .document
+            .get("manifest-version")
+            .and_then(Item::as_integer);
+        if current.is_none_or(|v| v < i64::from(LATEST_PROJECT_MANIFEST_VERSION)) {
+            self.document["manifest-version"] =
+                toml_edit::value(i64::from(LATEST_PROJECT_MANIFEST_VERSION));
+        }
+    }
+
+    /// Values of the `key` list of `product`, as written in the manifest (before substitutions).
+    pub fn list(&self, product: &str, key: &str) -> Result<Vec<String>, Error> {
+        let Some(item) = self.product(product)?.get(key) else {
+            return Ok(Vec::new());
+        };
+        let array = item.as_array().ok_or_else(|| {
+            self.error(ProjectManifestEditingError::UnexpectedFieldType(key.into()))
+        })?;
+        Ok(array
+            .iter()
+            .filter_map(|v| v.as_str().map(String::from))
+            .collect())
+    }
+
+    /// Appends `value` to the `key` list of `product`, creating the list if needed. Returns
+    /// whether the value was added, or `false` if it was already present.
+    pub fn add_to_list(&mut self, product: &str, key: &str, value: &str) -> Result<bool, Error> {
+        let array = self.array_mut(product, key)?;
+        if array.iter().any(|v| v.as_str() == Some(value)) {
+            return Ok(false);
+        }
+        array.push(value);
+        Ok(true)
+    }
+
+    /// Removes `value` from the `key` list of `product`, dropping the list once it is empty.
+    /// Returns whether the value was removed, or `false` if it was not present.
+    pub fn remove_from_list(
+        &mut self,
+        product: &str,
+        key: &str,
+        value: &str,
+    ) -> Result<bool, Error> {
+        if self.product(product)?.get(key).is_none() {
+            return Ok(false);
+        }
+        let array = self.array_mut(product, key)?;
+        let before = array.len();
+        array.retain(|v| v.as_str() != Some(value));
+        let removed = array.len() != before;
+
+        if array.is_empty() {
+            if let Some(table) = self.document["products"][product].as_table_like_mut() {
+                table.remove(key);
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Parses the edited manifest, as [`ProjectManifest::load`] would after saving it.
+    pub fn manifest(&self) -> Result<ProjectManifest, Error> {
+        parse(self.document.to_string().as_bytes()).map_err(|kind| {
+            Error::ProjectManifestLoadingFailed {
+                path: self.path.clone(),
+                kind: Box::new(kind),
+            }
+        })
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        std::fs::write(&self.path, self.document.to_string())
+            .map_err(|e| Error::Write(self.path.clone(), e))
+    }
+
+    fn product(&self, product: &str) -> Result<&dyn TableLike, Error> {
+        self.document
+            .get("products")
+            .and_then(|p| p.get(product))
+            .and_then(Item::as_table_like)
+            .ok_or_else(|| self.error(ProjectManifestEditingError::ProductNotFound(product.into())))
+    }
+
+    fn array_mut(&mut self, product: &str, key: &str) -> Result<&mut Array, Error> {
+        self.product(product)?;
+        let path = self.path.clone();
+        let table = self.document["products"][product]
+            .as_table_like_mut()
+            .expect("checked above");
+        let item = table.entry(key).or_insert(toml_edit::value(Array::new()));
+        item.as_array_mut().ok_or_else(|| {
+            editing_failed(
+                &path,
+                ProjectManifestEditingError::UnexpectedFieldType(key.into()),
+            )
+        })
+    }
+
+    fn error(&self, kind: ProjectManifestEditingError) -> Error {
+        editing_failed(&self.path, kind)
+    }
+}
+
+fn editing_failed(path: &Path, kind: ProjectManifestEditingError) -> Error {
+    Error::ProjectManifestEditingFailed {
+        path: path.to_path_buf(),
+        kind,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(contents: &str) -> (tempfile::TempDir, ProjectManifestEditor) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("criticalup.toml");
+        std::fs::write(&path, contents).unwrap();
+        let editor = ProjectManifestEditor::open(&path).unwrap();
+        (dir, editor)
+    }
+
+    #[test]
+    fn test_edit_preserves_formatting() {
+        let (dir, mut editor) = editor(
+            "# Our toolchain\nmanifest-version = 1\n\n[products.ferrocene]\n\
+            release = \"stable-25.02.0\" # pinned\npackages = [\"rustc\"]\n",
+        );
+
+        let product = editor.product_name(None).unwrap();
+        assert_eq!("ferrocene", product);
+
+        editor.upgrade_version();
+        assert!(editor
+            .add_to_list(&product, "targets", "aarch64-unknown-none")
+            .unwrap());
+        assert!(!editor
+            .add_to_list(&product, "targets", "aarch64-unknown-none")
+            .unwrap());
+        assert!(editor
+            .add_to_list(&product, "packages", "rust-src")
+            .unwrap());
+        assert!(editor
+            .remove_from_list(&product, "packages", "rustc")
+            .unwrap());
+        assert!(!editor
+            .remove_from_list(&product, "packages", "rustc")
+            .unwrap());
+        editor.save().unwrap();
+
+        let manifest = editor.manifest().unwrap();
+        assert_eq!(
+            &[
+                "rust-src".to_string(),
+                "rust-std-aarch64-unknown-none".into()
+            ][..],
+            manifest.products()[0].packages()
+        );
+
+        assert_eq!(
+            "# Our toolchain\nmanifest-version = 2\n\n[products.ferrocene]\n\
+            release = \"stable-25.02.0\" # pinned\npackages = [\"rust-src\"]\n\
+            targets = [\"aarch64-unknown-none\"]\n",
+            std::fs::read_to_string(dir.path().join("criticalup.toml")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_list() {
+        let (_dir, editor) = editor(
+            "manifest-version = 2\n[products.ferrocene]\nrelease = \"foo\"\n\
+            packages = [\"rustc\", \"rust-std-${targets}\"]\n",
+        );
+
+        assert_eq!(
+            vec!["rustc".to_string(), "rust-std-${targets}".into()],
+            editor.list("ferrocene", "packages").unwrap()
+        );
+        assert!(editor.list("ferrocene", "targets").unwrap().is_empty());
+        assert!(matches!(
+            editor.list("missing", "packages").unwrap_err(),
+            Error::ProjectManifestEditingFailed {
+                kind: ProjectManifestEditingError::ProductNotFound(name),
+                ..
+            } if name == "missing"
+        ));
+        assert!(matches!(
+            editor.list("ferrocene", "release").unwrap_err(),
+            Error::ProjectManifestEditingFailed {
+                kind: ProjectManifestEditingError::UnexpectedFieldType(key),
+                ..
+            } if key == "release"
+        ));
+    }
+}
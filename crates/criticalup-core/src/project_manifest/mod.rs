@@ -1,13 +1,20 @@
 // SPDX-FileCopyrightText: The Ferrocene Developers
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+mod edit;
+pub mod rust_toolchain;
 mod substitutions;
 pub mod v1;
+pub mod v2;
 
 use crate::errors::ProjectManifestLoadingError::MultipleProductsNotSupportedInProjectManifest;
 use crate::errors::{Error, ProjectManifestLoadingError};
-use crate::project_manifest::substitutions::apply_substitutions;
+use crate::project_manifest::substitutions::{
+    apply_substitutions, expand_substitutions, SubstitutionContext,
+};
 use crate::utils::Sha256Hasher;
+use criticaltrust::manifests::ReleaseArtifactFormat;
+pub use edit::ProjectManifestEditor;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fmt::Display;
@@ -17,9 +24,17 @@ use std::path::{Path, PathBuf};
 
 const DEFAULT_PROJECT_MANIFEST_NAME: &str = "criticalup.toml";
 const DEFAULT_PROJECT_MANIFEST_VERSION: u32 = 1;
+const LATEST_PROJECT_MANIFEST_VERSION: u32 = 2;
+const TARGET_PACKAGE_PREFIX: &str = "rust-std-";
+
+/// Name of the package providing the standard library for `target`.
+pub fn target_package(target: &str) -> String {
+    format!("{TARGET_PACKAGE_PREFIX}{target}")
+}
 
 #[derive(Debug, PartialEq, Eq, Hash, Serialize)]
 pub struct ProjectManifest {
+    auth_profile: Option<String>,
     products: Vec<ProjectManifestProduct>,
 }
 
@@ -48,6 +63,14 @@ impl ProjectManifest {
         })
     }
 
+    /// Parses the contents of a project manifest which is not written to `path` yet.
+    pub fn from_contents(path: &Path, contents: &str) -> Result<Self, Error> {
+        parse(contents.as_bytes()).map_err(|kind| Error::ProjectManifestLoadingFailed {
+            path: path.into(),
+            kind: Box::new(kind),
+        })
+    }
+
     /// Find the project manifest and parse it.
     ///
     /// This function tries to load the manifest for a given path. If the path is not provided
@@ -72,6 +95,12 @@ impl ProjectManifest {
         &self.products
     }
 
+    /// Name of the authentication profile the project wants to use when talking to the download
+    /// server, if any.
+    pub fn auth_profile(&self) -> Option<&str> {
+        self.auth_profile.as_deref()
+    }
+
     /// Generates a directory for each product under the specified `root`.
     ///
     /// If the directory already exists, then just skips the creation.
@@ -118,11 +147,25 @@ impl DerefMut for Packages {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Serialize)]
+#[derive(Debug, PartialEq, Eq, Serialize)]
 pub struct ProjectManifestProduct {
     name: String,
     release: String,
     packages: Packages,
+    targets: Vec<String>,
+    download_server: Option<String>,
+    format: Option<ReleaseArtifactFormat>,
+    auth_profile: Option<String>,
+}
+
+impl Hash for ProjectManifestProduct {
+    /// Only the fields affecting the contents of the installation are hashed, so that where the
+    /// artifacts are downloaded from (and how) does not change the `InstallationId`.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.release.hash(state);
+        self.packages.hash(state);
+    }
 }
 
 impl ProjectManifestProduct {
@@ -138,6 +181,28 @@ impl ProjectManifestProduct {
         &self.packages
     }
 
+    /// URL of the download server to fetch this product from, if it differs from the default one.
+    pub fn download_server(&self) -> Option<&str> {
+        self.download_server.as_deref()
+    }
+
+    /// Compilation targets of this product, whose `rust-std-*` packages are part of
+    /// [`ProjectManifestProduct::packages`].
+    pub fn targets(&self) -> &[String] {
+        &self.targets
+    }
+
+    /// Format of the artifacts to download for this product, if a specific one was requested.
+    pub fn format(&self) -> Option<ReleaseArtifactFormat> {
+        self.format
+    }
+
+    /// Name of the authentication profile to use for this product, falling back to the one of the
+    /// whole project.
+    pub fn auth_profile(&self) -> Option<&str> {
+        self.auth_profile.as_deref()
+    }
+
     pub fn installation_id(&self) -> InstallationId {
         // For now this generates the ID using hash of the product object.
         let mut hasher = Sha256Hasher::new();
@@ -198,33 +263,81 @@ struct VersionDetector {
 }
 
 fn load_inner(path: &Path) -> Result<ProjectManifest, ProjectManifestLoadingError> {
-    let mut products = Vec::new();
-
     let contents = std::fs::read(path).map_err(ProjectManifestLoadingError::FailedToRead)?;
+    parse(&contents)
+}
+
+fn parse(contents: &[u8]) -> Result<ProjectManifest, ProjectManifestLoadingError> {
+    let mut products = Vec::new();
+    let auth_profile;
 
     // We first deserialize only the `manifest_version` field, which must be present in all
     // past and future versions, and then based on the version we properly deserialize.
     let version: VersionDetector =
-        toml_edit::de::from_slice(&contents).map_err(ProjectManifestLoadingError::FailedToParse)?;
+        toml_edit::de::from_slice(contents).map_err(ProjectManifestLoadingError::FailedToParse)?;
     match version.manifest_version {
-        DEFAULT_PROJECT_MANIFEST_VERSION => {
-            let manifest: v1::ProjectManifest = toml_edit::de::from_slice(&contents)
+        1 => {
+            let manifest: v1::ProjectManifest = toml_edit::de::from_slice(contents)
                 .map_err(ProjectManifestLoadingError::FailedToParse)?;
+            auth_profile = None;
 
+            // The packages of version 1 are kept exactly as before the other versions existed
+            // (including duplicates), as they determine the `InstallationId`.
+            let context = SubstitutionContext::v1();
             for (name, product) in manifest.products.into_iter() {
                 let mut packages = Packages(
                     product
                         .packages
                         .iter()
-                        .map(|p| apply_substitutions(p))
+                        .map(|p| apply_substitutions(p, &context))
                         .collect::<Result<Vec<_>, ProjectManifestLoadingError>>()?,
                 );
                 packages.sort();
 
                 products.push(ProjectManifestProduct {
-                    name,
-                    release: apply_substitutions(&product.release)?,
                     packages,
+                    release: apply_substitutions(&product.release, &context)?,
+                    name,
+                    targets: vec![],
+                    download_server: None,
+                    format: None,
+                    auth_profile: None,
+                });
+            }
+        }
+        LATEST_PROJECT_MANIFEST_VERSION => {
+            let manifest: v2::ProjectManifest = toml_edit::de::from_slice(contents)
+                .map_err(ProjectManifestLoadingError::FailedToParse)?;
+            auth_profile = manifest.profile;
+
+            for (name, product) in manifest.products.into_iter() {
+                if product.format == Some(ReleaseArtifactFormat::Unknown) {
+                    return Err(ProjectManifestLoadingError::UnsupportedArtifactFormat {
+                        product_name: name,
+                    });
+                }
+
+                let targets = product
+                    .targets
+                    .iter()
+                    .map(|t| apply_substitutions(t, &SubstitutionContext::default()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let context = SubstitutionContext::new(&targets);
+
+                let release = apply_substitutions(&product.release, &context)?;
+                products.push(ProjectManifestProduct {
+                    packages: substituted_packages(&product.packages, &targets, &context)?,
+                    release: match product.channel {
+                        Some(channel) => {
+                            format!("{}-{release}", apply_substitutions(&channel, &context)?)
+                        }
+                        None => release,
+                    },
+                    name,
+                    targets,
+                    download_server: product.download_server,
+                    format: product.format,
+                    auth_profile: product.profile.or_else(|| auth_profile.clone()),
                 });
             }
         }
@@ -262,7 +375,32 @@ fn load_inner(path: &Path) -> Result<ProjectManifest, ProjectManifestLoadingErro
         }
     }
 
-    Ok(ProjectManifest { products })
+    Ok(ProjectManifest {
+        auth_profile,
+        products,
+    })
+}
+
+/// Substitutes the variables in the packages of a product, adding the `rust-std-*` package of
+/// each of its `targets`.
+fn substituted_packages(
+    packages: &[String],
+    targets: &[String],
+    context: &SubstitutionContext<'_>,
+) -> Result<Packages, ProjectManifestLoadingError> {
+    let mut packages = Packages(
+        packages
+            .iter()
+            .map(|p| expand_substitutions(p, context))
+            .collect::<Result<Vec<_>, ProjectManifestLoadingError>>()?
+            .into_iter()
+            .flatten()
+            .chain(targets.iter().map(|target| target_package(target)))
+            .collect(),
+    );
+    packages.sort();
+    packages.dedup();
+    Ok(packages)
 }
 
 #[cfg(test)]
@@ -402,10 +540,15 @@ mod tests {
         fn assert_sample_parsed(manifest: ProjectManifest) {
             assert_eq!(
                 ProjectManifest {
+                    auth_profile: None,
                     products: vec![ProjectManifestProduct {
                         name: "sample".into(),
                         release: "foo".into(),
                         packages: Packages(vec!["bar".into()]),
+                        targets: vec![],
+                        download_server: None,
+                        format: None,
+                        auth_profile: None,
                     }]
                 },
                 manifest
@@ -459,10 +602,10 @@ mod tests {
                 )
             });
 
-            assert_load_error("manifest-version = 2", |e| {
+            assert_load_error("manifest-version = 3", |e| {
                 matches!(
                     e,
-                    ProjectManifestLoadingError::ManifestVersionTooBig { user_version: 2 }
+                    ProjectManifestLoadingError::ManifestVersionTooBig { user_version: 3 }
                 )
             });
         }
@@ -472,6 +615,7 @@ mod tests {
             assert_load(
                 "manifest-version = 1",
                 ProjectManifest {
+                    auth_profile: None,
                     products: Vec::new(),
                 },
             );
@@ -488,10 +632,15 @@ mod tests {
                     packages = ["bar", "baz"]
                 "#,
                 ProjectManifest {
+                    auth_profile: None,
                     products: vec![ProjectManifestProduct {
                         name: "sample".into(),
                         release: "foo".into(),
                         packages: Packages(vec!["bar".into(), "baz".into()]),
+                        targets: vec![],
+                        download_server: None,
+                        format: None,
+                        auth_profile: None,
                     }],
                 },
             );
@@ -514,22 +663,46 @@ mod tests {
                     packages = ["b", "a"]
                 "#,
                 ProjectManifest {
+                    auth_profile: None,
                     products: vec![
                         ProjectManifestProduct {
                             name: "demo".into(),
                             release: "@foo/latest".into(),
                             packages: Packages(vec!["a".into(), "b".into()]),
+                            targets: vec![],
+                            download_server: None,
+                            format: None,
+                            auth_profile: None,
                         },
                         ProjectManifestProduct {
                             name: "sample".into(),
                             release: "foo".into(),
                             packages: Packages(vec!["bar".into(), "baz".into()]),
+                            targets: vec![],
+                            download_server: None,
+                            format: None,
+                            auth_profile: None,
                         },
                     ],
                 },
             );
         }
 
+        #[test]
+        fn test_v1_rejects_profile() {
+            assert_load_error(
+                r#"
+                    manifest-version = 1
+                    profile = "acme"
+
+                    [products.sample]
+                    release = "foo"
+                    packages = ["bar"]
+                "#,
+                |e| matches!(e, ProjectManifestLoadingError::FailedToParse(_)),
+            );
+        }
+
         #[test]
         fn test_v1_multiple_products_not_supported() {
             let root = tempfile::tempdir().unwrap();
@@ -563,15 +736,68 @@ mod tests {
                     packages = ["foo-${rustc-host}"]
                 "#,
                 ProjectManifest {
+                    auth_profile: None,
                     products: vec![ProjectManifestProduct {
                         name: "sample".into(),
                         release: env!("TARGET").into(),
                         packages: Packages(vec![concat!("foo-", env!("TARGET")).into()]),
+                        targets: vec![],
+                        download_server: None,
+                        format: None,
+                        auth_profile: None,
+                    }],
+                },
+            );
+        }
+
+        #[test]
+        fn test_v1_keeps_duplicate_packages() {
+            assert_load(
+                r#"
+                    manifest-version = 1
+
+                    [products.sample]
+                    release = "foo"
+                    packages = ["bar", "baz", "bar"]
+                "#,
+                ProjectManifest {
+                    auth_profile: None,
+                    products: vec![ProjectManifestProduct {
+                        name: "sample".into(),
+                        release: "foo".into(),
+                        packages: Packages(vec!["bar".into(), "bar".into(), "baz".into()]),
+                        targets: vec![],
+                        download_server: None,
+                        format: None,
+                        auth_profile: None,
                     }],
                 },
             );
         }
 
+        #[test]
+        fn test_v1_rejects_v2_substitutions() {
+            for variable in ["target", "targets", "env:HOME"] {
+                assert_load_error(
+                    &format!(
+                        r#"
+                            manifest-version = 1
+
+                            [products.sample]
+                            release = "foo"
+                            packages = ["bar-${{{variable}}}"]
+                        "#
+                    ),
+                    |e| {
+                        matches!(
+                            e,
+                            ProjectManifestLoadingError::UnknownVariableInSubstitution(v) if v == variable
+                        )
+                    },
+                );
+            }
+        }
+
         #[test]
         fn test_v1_missing_required_fields() {
             assert_load_error(
@@ -630,6 +856,238 @@ mod tests {
             );
         }
 
+        #[test]
+        fn test_v2_without_product_settings() {
+            assert_load(
+                r#"
+                    manifest-version = 2
+
+                    [products.sample]
+                    release = "foo"
+                    packages = ["bar", "baz"]
+                "#,
+                ProjectManifest {
+                    auth_profile: None,
+                    products: vec![ProjectManifestProduct {
+                        name: "sample".into(),
+                        release: "foo".into(),
+                        packages: Packages(vec!["bar".into(), "baz".into()]),
+                        targets: vec![],
+                        download_server: None,
+                        format: None,
+                        auth_profile: None,
+                    }],
+                },
+            );
+        }
+
+        #[test]
+        fn test_v2_targets_substitutions() {
+            assert_load(
+                r#"
+                    manifest-version = 2
+
+                    [products.sample]
+                    release = "foo"
+                    targets = ["thumbv7em-none-eabihf", "aarch64-unknown-none"]
+                    packages = ["rustc-${rustc-host}", "rust-std-${targets}"]
+                "#,
+                ProjectManifest {
+                    auth_profile: None,
+                    products: vec![ProjectManifestProduct {
+                        name: "sample".into(),
+                        release: "foo".into(),
+                        packages: Packages(vec![
+                            "rust-std-aarch64-unknown-none".into(),
+                            "rust-std-thumbv7em-none-eabihf".into(),
+                            concat!("rustc-", env!("TARGET")).into(),
+                        ]),
+                        targets: vec![
+                            "thumbv7em-none-eabihf".into(),
+                            "aarch64-unknown-none".into(),
+                        ],
+                        download_server: None,
+                        format: None,
+                        auth_profile: None,
+                    }],
+                },
+            );
+
+            assert_load_error(
+                r#"
+                    manifest-version = 2
+
+                    [products.sample]
+                    release = "${targets}"
+                    targets = ["aarch64-unknown-none"]
+                    packages = ["bar"]
+                "#,
+                |e| {
+                    matches!(
+                        e,
+                        ProjectManifestLoadingError::ListVariableNotAllowedInSubstitution(_)
+                    )
+                },
+            );
+
+            assert_load_error(
+                r#"
+                    manifest-version = 2
+
+                    [products.sample]
+                    release = "foo"
+                    targets = ["thumbv7em-none-eabihf", "aarch64-unknown-none"]
+                    packages = ["docs-${target}"]
+                "#,
+                |e| {
+                    matches!(
+                        e,
+                        ProjectManifestLoadingError::MultipleTargetsForVariableInSubstitution(_, 2)
+                    )
+                },
+            );
+        }
+
+        #[test]
+        fn test_v2_targets_add_packages() {
+            assert_load(
+                r#"
+                    manifest-version = 2
+
+                    [products.sample]
+                    release = "foo"
+                    targets = ["aarch64-unknown-none", "thumbv7em-none-eabihf"]
+                    packages = ["rustc", "rust-std-aarch64-unknown-none"]
+                "#,
+                ProjectManifest {
+                    auth_profile: None,
+                    products: vec![ProjectManifestProduct {
+                        name: "sample".into(),
+                        release: "foo".into(),
+                        packages: Packages(vec![
+                            "rust-std-aarch64-unknown-none".into(),
+                            "rust-std-thumbv7em-none-eabihf".into(),
+                            "rustc".into(),
+                        ]),
+                        targets: vec![
+                            "aarch64-unknown-none".into(),
+                            "thumbv7em-none-eabihf".into(),
+                        ],
+                        download_server: None,
+                        format: None,
+                        auth_profile: None,
+                    }],
+                },
+            );
+        }
+
+        #[test]
+        fn test_v2_product_settings() {
+            assert_load(
+                r#"
+                    manifest-version = 2
+                    profile = "default-profile"
+
+                    [products.sample]
+                    release = "25.02.0"
+                    channel = "stable"
+                    packages = ["bar"]
+                    download-server = "https://mirror.example.com"
+                    format = "tar.zst"
+                    profile = "acme"
+                "#,
+                ProjectManifest {
+                    auth_profile: Some("default-profile".into()),
+                    products: vec![ProjectManifestProduct {
+                        name: "sample".into(),
+                        release: "stable-25.02.0".into(),
+                        packages: Packages(vec!["bar".into()]),
+                        targets: vec![],
+                        download_server: Some("https://mirror.example.com".into()),
+                        format: Some(ReleaseArtifactFormat::TarZst),
+                        auth_profile: Some("acme".into()),
+                    }],
+                },
+            );
+        }
+
+        #[test]
+        fn test_v2_profile_falls_back_to_project() {
+            assert_load(
+                r#"
+                    manifest-version = 2
+                    profile = "default-profile"
+
+                    [products.sample]
+                    release = "foo"
+                    packages = ["bar"]
+                "#,
+                ProjectManifest {
+                    auth_profile: Some("default-profile".into()),
+                    products: vec![ProjectManifestProduct {
+                        name: "sample".into(),
+                        release: "foo".into(),
+                        packages: Packages(vec!["bar".into()]),
+                        targets: vec![],
+                        download_server: None,
+                        format: None,
+                        auth_profile: Some("default-profile".into()),
+                    }],
+                },
+            );
+        }
+
+        #[test]
+        fn test_v2_unsupported_format() {
+            assert_load_error(
+                r#"
+                    manifest-version = 2
+
+                    [products.sample]
+                    release = "foo"
+                    packages = ["bar"]
+                    format = "zip"
+                "#,
+                |e| {
+                    matches!(
+                        e,
+                        ProjectManifestLoadingError::UnsupportedArtifactFormat { product_name }
+                            if product_name == "sample"
+                    )
+                },
+            );
+        }
+
+        #[test]
+        fn test_v2_extra_unknown_fields() {
+            assert_load_error(
+                r#"
+                    manifest-version = 2
+
+                    [products.sample]
+                    release = "foo"
+                    packages = ["bar"]
+                    foo = 1
+                "#,
+                |e| matches!(e, ProjectManifestLoadingError::FailedToParse(_)),
+            );
+        }
+
+        #[test]
+        fn test_v1_rejects_v2_product_settings() {
+            assert_load_error(
+                r#"
+                    manifest-version = 1
+
+                    [products.sample]
+                    release = "foo"
+                    packages = ["bar"]
+                    download-server = "https://mirror.example.com"
+                "#,
+                |e| matches!(e, ProjectManifestLoadingError::FailedToParse(_)),
+            );
+        }
+
         #[track_caller]
         fn assert_load(contents: &str, expected: ProjectManifest) {
             let root = tempfile::tempdir().unwrap();
@@ -684,6 +1142,10 @@ mod tests {
                 name: "dir_name_tester".to_string(),
                 release: "1.523231341324".to_string(),
                 packages: Packages(vec![]),
+                targets: vec![],
+                download_server: None,
+                format: None,
+                auth_profile: None,
             };
             assert_eq!(
                 InstallationId(
@@ -696,6 +1158,10 @@ mod tests {
                 name: "dir_name_tester".to_string(),
                 release: "1.523231341324".to_string(),
                 packages: Packages(vec!["package 2".to_string(), "package 1".to_string()]),
+                targets: vec![],
+                download_server: None,
+                format: None,
+                auth_profile: None,
             };
             assert_eq!(
                 InstallationId(
@@ -705,6 +1171,25 @@ mod tests {
             );
         }
 
+        #[test]
+        fn test_installation_id_ignores_download_settings() {
+            let product = ProjectManifestProduct {
+                name: "dir_name_tester".to_string(),
+                release: "1.523231341324".to_string(),
+                packages: Packages(vec![]),
+                targets: vec![],
+                download_server: Some("https://mirror.example.com".into()),
+                format: Some(criticaltrust::manifests::ReleaseArtifactFormat::TarZst),
+                auth_profile: Some("acme".into()),
+            };
+            assert_eq!(
+                InstallationId(
+                    "88ae6c4f87f8b450cef620983f00ac440a0b2dd6c2b7a1f04185b917d7a51c84".into()
+                ),
+                product.installation_id(),
+            );
+        }
+
         #[tokio::test]
         async fn test_create_success() {
             let root = tempfile::tempdir().unwrap();
@@ -713,6 +1198,10 @@ mod tests {
                 name: "product1".into(),
                 release: "@foo/latest".into(),
                 packages: Packages(vec!["b".into(), "a".into()]),
+                targets: vec![],
+                download_server: None,
+                format: None,
+                auth_profile: None,
             };
 
             let product1_id = product1.installation_id();
@@ -721,11 +1210,16 @@ mod tests {
                 name: "product2".into(),
                 release: "foo".into(),
                 packages: Packages(vec!["bar".into(), "baz".into()]),
+                targets: vec![],
+                download_server: None,
+                format: None,
+                auth_profile: None,
             };
 
             let product2_id = product2.installation_id();
 
             let test_manifest = crate::project_manifest::ProjectManifest {
+                auth_profile: None,
                 products: vec![product1, product2],
             };
 
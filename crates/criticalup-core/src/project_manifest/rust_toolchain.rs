@@ -0,0 +1,349 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Conversion between the `rust-toolchain.toml` files used by rustup and the project manifest.
+
+use crate::errors::{Error, RustToolchainFileError};
+use crate::project_manifest::v2;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use toml_edit::{Array, DocumentMut, Item};
+
+/// Names of the toolchain file rustup looks for, in order of precedence.
+const FILE_NAMES: [&str; 2] = ["rust-toolchain", "rust-toolchain.toml"];
+
+/// Ferrocene package providing each rustup component.
+const COMPONENT_PACKAGES: &[(&str, &str)] = &[
+    ("rustc", "rustc-${rustc-host}"),
+    ("cargo", "cargo-${rustc-host}"),
+    ("rust-std", "rust-std-${rustc-host}"),
+    ("clippy", "clippy-${rustc-host}"),
+    ("rustfmt", "rustfmt-${rustc-host}"),
+    ("rust-src", "rust-src"),
+    ("rust-analyzer", "rust-analyzer-${rustc-host}"),
+    ("llvm-tools", "llvm-tools-${rustc-host}"),
+    ("rust-docs", "ferrocene-docs"),
+];
+
+/// Components installed by each rustup profile, before the explicitly listed ones.
+const MINIMAL_PROFILE: &[&str] = &["rustc", "cargo", "rust-std"];
+const DEFAULT_PROFILE: &[&str] = &[
+    "rustc",
+    "cargo",
+    "rust-std",
+    "rust-docs",
+    "clippy",
+    "rustfmt",
+];
+const COMPLETE_PROFILE: &[&str] = &[
+    "rustc",
+    "cargo",
+    "rust-std",
+    "clippy",
+    "rustfmt",
+    "rust-src",
+    "rust-analyzer",
+    "llvm-tools",
+    "rust-docs",
+];
+
+/// The `[toolchain]` section of a `rust-toolchain.toml` file.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+pub struct RustToolchain {
+    pub channel: Option<String>,
+    #[serde(default)]
+    pub components: Vec<String>,
+    #[serde(default)]
+    pub targets: Vec<String>,
+    pub profile: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RustToolchainFile {
+    toolchain: RustToolchain,
+}
+
+/// Finds the toolchain file rustup would use in `dir`.
+pub fn find(dir: &Path) -> Option<PathBuf> {
+    FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+impl RustToolchain {
+    /// Loads a toolchain file, in either the TOML format or the legacy format only containing the
+    /// name of the channel.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| Error::Read(path.to_path_buf(), e))?;
+
+        let trimmed = contents.trim();
+        if !trimmed.is_empty() && !trimmed.contains(['\n', '[', '=']) {
+            return Ok(RustToolchain {
+                channel: Some(trimmed.into()),
+                ..RustToolchain::default()
+            });
+        }
+
+        let file: RustToolchainFile = toml_edit::de::from_str(&contents)
+            .map_err(|e| failed(path, RustToolchainFileError::FailedToParse(e)))?;
+        Ok(file.toolchain)
+    }
+
+    /// Selects the rustup toolchain `channel` and nothing else, as rustup can't install
+    /// components or targets into toolchains linked with `criticalup link create`.
+    pub fn linked(channel: &str) -> Self {
+        RustToolchain {
+            channel: Some(channel.into()),
+            ..RustToolchain::default()
+        }
+    }
+
+    /// Builds the project manifest installing the Ferrocene packages matching this toolchain.
+    /// The release is taken from the channel unless `release` is provided. Also returns the
+    /// components no Ferrocene package corresponds to.
+    pub fn to_manifest(
+        &self,
+        path: &Path,
+        release: Option<String>,
+    ) -> Result<(v2::ProjectManifest, Vec<String>), Error> {
+        let release = match (release, &self.channel) {
+            (Some(release), _) => release,
+            (None, Some(channel)) if is_ferrocene_release(channel) => channel.clone(),
+            (None, Some(channel)) => {
+                return Err(failed(
+                    path,
+                    RustToolchainFileError::ChannelNotARelease(channel.clone()),
+                ))
+            }
+            (None, None) => return Err(failed(path, RustToolchainFileError::MissingChannel)),
+        };
+
+        let profile = match self.profile.as_deref() {
+            Some("minimal") => MINIMAL_PROFILE,
+            Some("complete") => COMPLETE_PROFILE,
+            _ => DEFAULT_PROFILE,
+        };
+        let mut packages = Vec::new();
+        let mut unknown = Vec::new();
+        let components = profile
+            .iter()
+            .copied()
+            .chain(self.components.iter().map(String::as_str));
+        for component in components {
+            // Preview components were renamed once stabilized.
+            let component = component.strip_suffix("-preview").unwrap_or(component);
+            match COMPONENT_PACKAGES
+                .iter()
+                .find(|(name, _)| *name == component)
+            {
+                Some((_, package)) if !packages.contains(&package.to_string()) => {
+                    packages.push(package.to_string())
+                }
+                Some(_) => {}
+                None => unknown.push(component.to_string()),
+            }
+        }
+
+        let manifest = v2::manifest(release, packages, self.targets.clone());
+        Ok((manifest, unknown))
+    }
+
+    /// Writes this toolchain to `path`, replacing the toolchain settings of an existing file but
+    /// keeping its other contents, like comments.
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let existing = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(Error::Read(path.to_path_buf(), err)),
+        };
+        // Files in the legacy format are replaced by the TOML format.
+        let mut document = if existing.contains('[') {
+            existing
+                .parse::<DocumentMut>()
+                .map_err(|e| failed(path, RustToolchainFileError::FailedToEdit(e)))?
+        } else {
+            DocumentMut::new()
+        };
+
+        let toolchain = document
+            .entry("toolchain")
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .ok_or_else(|| failed(path, RustToolchainFileError::UnexpectedToolchainType))?;
+        let list = |values: &[String]| Item::Value(values.iter().collect::<Array>().into());
+        match &self.channel {
+            Some(channel) => toolchain.insert("channel", toml_edit::value(channel)),
+            None => toolchain.remove("channel"),
+        };
+        for (key, values) in [("components", &self.components), ("targets", &self.targets)] {
+            if values.is_empty() {
+                toolchain.remove(key);
+            } else {
+                toolchain.insert(key, list(values));
+            }
+        }
+        match &self.profile {
+            Some(profile) => toolchain.insert("profile", toml_edit::value(profile)),
+            None => toolchain.remove("profile"),
+        };
+
+        std::fs::write(path, document.to_string()).map_err(|e| Error::Write(path.to_path_buf(), e))
+    }
+}
+
+/// Whether the rustup channel names a Ferrocene release, like `stable-25.02.0` or `25.02.0`,
+/// rather than a Rust release like `stable` or `1.85.0`. Nightly channels like
+/// `nightly-2024-02-28` name Rust nightlies, so they are never taken as a Ferrocene release.
+fn is_ferrocene_release(channel: &str) -> bool {
+    let version = match channel.split_once('-') {
+        Some(("stable" | "beta", version)) => version,
+        Some(_) => return false,
+        None => channel,
+    };
+    // Ferrocene versions start with the two digits of the year, Rust versions with `1.`.
+    let year = version.split(['.', '-']).next().unwrap_or_default();
+    year.len() >= 2 && year.chars().all(|c| c.is_ascii_digit())
+}
+
+fn failed(path: &Path, kind: RustToolchainFileError) -> Error {
+    Error::RustToolchainFileFailed {
+        path: path.to_path_buf(),
+        kind,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project_manifest::{
+        DEFAULT_PROJECT_MANIFEST_VERSION, LATEST_PROJECT_MANIFEST_VERSION,
+    };
+
+    #[test]
+    fn test_is_ferrocene_release() {
+        for release in ["stable-25.02.0", "beta-25.05", "25.02.0"] {
+            assert!(is_ferrocene_release(release), "{release}");
+        }
+        for channel in [
+            "stable",
+            "nightly",
+            "nightly-2024-02-28",
+            "1.85.0",
+            "stable-1.85.0",
+            "ferrocene",
+        ] {
+            assert!(!is_ferrocene_release(channel), "{channel}");
+        }
+    }
+
+    #[test]
+    fn test_to_manifest() {
+        let toolchain = RustToolchain {
+            channel: Some("stable-25.02.0".into()),
+            components: vec![
+                "rust-src".into(),
+                "llvm-tools-preview".into(),
+                "miri".into(),
+            ],
+            targets: vec!["aarch64-unknown-none".into()],
+            profile: Some("minimal".into()),
+        };
+        let (manifest, unknown) = toolchain.to_manifest(Path::new("x"), None).unwrap();
+        let product = &manifest.products["ferrocene"];
+
+        assert_eq!(LATEST_PROJECT_MANIFEST_VERSION, manifest.manifest_version);
+        assert_eq!("stable-25.02.0", product.release);
+        assert_eq!(
+            vec![
+                "rustc-${rustc-host}",
+                "cargo-${rustc-host}",
+                "rust-std-${rustc-host}",
+                "rust-src",
+                "llvm-tools-${rustc-host}"
+            ],
+            product.packages
+        );
+        assert_eq!(vec!["aarch64-unknown-none"], product.targets);
+        assert_eq!(vec!["miri"], unknown);
+    }
+
+    #[test]
+    fn test_to_manifest_requires_a_release() {
+        let toolchain = RustToolchain {
+            channel: Some("1.85.0".into()),
+            ..RustToolchain::default()
+        };
+        assert!(matches!(
+            toolchain.to_manifest(Path::new("x"), None),
+            Err(Error::RustToolchainFileFailed {
+                kind: RustToolchainFileError::ChannelNotARelease(_),
+                ..
+            })
+        ));
+
+        let (manifest, _) = toolchain
+            .to_manifest(Path::new("x"), Some("25.02.0".into()))
+            .unwrap();
+        assert_eq!("25.02.0", manifest.products["ferrocene"].release);
+        assert_eq!(DEFAULT_PROJECT_MANIFEST_VERSION, manifest.manifest_version);
+    }
+
+    #[test]
+    fn test_write_linked_toolchain() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rust-toolchain.toml");
+        std::fs::write(
+            &path,
+            "# Pinned toolchain\n[toolchain]\nchannel = \"stable\"\nprofile = \"minimal\"\n\
+            components = [\"clippy\"]\ntargets = [\"aarch64-unknown-none\"]\n",
+        )
+        .unwrap();
+
+        RustToolchain::linked("ferrocene").write(&path).unwrap();
+
+        assert_eq!(
+            "# Pinned toolchain\n[toolchain]\nchannel = \"ferrocene\"\n",
+            std::fs::read_to_string(&path).unwrap()
+        );
+        assert_eq!(
+            RustToolchain::linked("ferrocene"),
+            RustToolchain::load(&path).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_to_manifest_default_profile() {
+        let toolchain = RustToolchain {
+            channel: Some("25.02.0".into()),
+            ..RustToolchain::default()
+        };
+        let (manifest, unknown) = toolchain.to_manifest(Path::new("x"), None).unwrap();
+        assert_eq!(
+            vec![
+                "rustc-${rustc-host}",
+                "cargo-${rustc-host}",
+                "rust-std-${rustc-host}",
+                "ferrocene-docs",
+                "clippy-${rustc-host}",
+                "rustfmt-${rustc-host}"
+            ],
+            manifest.products["ferrocene"].packages
+        );
+        assert!(unknown.is_empty());
+    }
+
+    #[test]
+    fn test_load_legacy_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rust-toolchain");
+        std::fs::write(&path, "stable-25.02.0\n").unwrap();
+
+        assert_eq!(Some(path.clone()), find(dir.path()));
+        assert_eq!(
+            Some("stable-25.02.0".into()),
+            RustToolchain::load(&path).unwrap().channel
+        );
+    }
+}
@@ -6,12 +6,77 @@ use crate::errors::ProjectManifestLoadingError;
 const VARIABLE_START: &str = "${";
 const VARIABLE_END: &str = "}";
 
+const ENV_VARIABLE_PREFIX: &str = "env:";
+const LIST_VARIABLE: &str = "targets";
+
 enum ParseState {
     Raw,
     Variable,
 }
 
-pub(super) fn apply_substitutions(mut input: &str) -> Result<String, ProjectManifestLoadingError> {
+/// Values the substitution variables of a product can refer to.
+#[derive(Default)]
+pub(super) struct SubstitutionContext<'a> {
+    /// Compilation targets configured for the product, used by `${target}` and `${targets}`.
+    pub(super) targets: &'a [String],
+    /// The target `${targets}` currently expands to, only set while expanding a list.
+    current_target: Option<&'a str>,
+    /// Only allow `${rustc-host}`, as manifest version 1 did before the other variables existed.
+    v1: bool,
+}
+
+impl<'a> SubstitutionContext<'a> {
+    pub(super) fn new(targets: &'a [String]) -> Self {
+        SubstitutionContext {
+            targets,
+            current_target: None,
+            v1: false,
+        }
+    }
+
+    /// Context of manifest version 1, which only supports `${rustc-host}`.
+    pub(super) fn v1() -> Self {
+        SubstitutionContext {
+            v1: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// Substitutes the variables in `input`, expanding it into one string per configured target if
+/// the `${targets}` list variable is present.
+pub(super) fn expand_substitutions(
+    input: &str,
+    context: &SubstitutionContext<'_>,
+) -> Result<Vec<String>, ProjectManifestLoadingError> {
+    let list_variable = format!("{VARIABLE_START}{LIST_VARIABLE}{VARIABLE_END}");
+    if !input.contains(&list_variable) {
+        return Ok(vec![apply_substitutions(input, context)?]);
+    }
+
+    if context.targets.is_empty() {
+        return Err(
+            ProjectManifestLoadingError::NoTargetsForVariableInSubstitution(LIST_VARIABLE.into()),
+        );
+    }
+    context
+        .targets
+        .iter()
+        .map(|target| {
+            let context = SubstitutionContext {
+                targets: context.targets,
+                current_target: Some(target),
+                v1: context.v1,
+            };
+            apply_substitutions(input, &context)
+        })
+        .collect()
+}
+
+pub(super) fn apply_substitutions(
+    mut input: &str,
+    context: &SubstitutionContext<'_>,
+) -> Result<String, ProjectManifestLoadingError> {
     let mut state = ParseState::Raw;
     let mut result = String::new();
 
@@ -31,7 +96,7 @@ pub(super) fn apply_substitutions(mut input: &str) -> Result<String, ProjectMani
             }
             ParseState::Variable => {
                 if let Some(end) = input.find(VARIABLE_END) {
-                    result.push_str(&apply_substitution(&input[..end])?);
+                    result.push_str(&apply_substitution(&input[..end], context)?);
 
                     input = &input[(end + VARIABLE_END.len())..];
                     state = ParseState::Raw;
@@ -44,12 +109,41 @@ pub(super) fn apply_substitutions(mut input: &str) -> Result<String, ProjectMani
     }
 }
 
-fn apply_substitution(variable: &str) -> Result<String, ProjectManifestLoadingError> {
+fn apply_substitution(
+    variable: &str,
+    context: &SubstitutionContext<'_>,
+) -> Result<String, ProjectManifestLoadingError> {
     match variable {
         "rustc-host" => Ok(env!("TARGET").into()),
-        other => Err(ProjectManifestLoadingError::UnknownVariableInSubstitution(
+        other if context.v1 => Err(ProjectManifestLoadingError::UnknownVariableInSubstitution(
             other.into(),
         )),
+        "target" => match context.targets {
+            [target] => Ok(target.clone()),
+            [] => Err(
+                ProjectManifestLoadingError::NoTargetsForVariableInSubstitution(variable.into()),
+            ),
+            targets => Err(
+                ProjectManifestLoadingError::MultipleTargetsForVariableInSubstitution(
+                    variable.into(),
+                    targets.len(),
+                ),
+            ),
+        },
+        LIST_VARIABLE => match context.current_target {
+            Some(target) => Ok(target.into()),
+            None => Err(
+                ProjectManifestLoadingError::ListVariableNotAllowedInSubstitution(variable.into()),
+            ),
+        },
+        other => match other.strip_prefix(ENV_VARIABLE_PREFIX) {
+            Some(name) => std::env::var(name).map_err(|_| {
+                ProjectManifestLoadingError::UnsetEnvironmentVariableInSubstitution(name.into())
+            }),
+            None => Err(ProjectManifestLoadingError::UnknownVariableInSubstitution(
+                other.into(),
+            )),
+        },
     }
 }
 
@@ -59,49 +153,127 @@ mod tests {
 
     #[test]
     fn test_apply_substitutions() {
-        assert_eq!("hello world", apply_substitutions("hello world").unwrap());
+        assert_eq!(
+            "hello world",
+            apply_substitutions("hello world", &SubstitutionContext::default()).unwrap()
+        );
         assert_eq!(
             env!("TARGET"),
-            apply_substitutions("${rustc-host}").unwrap()
+            apply_substitutions("${rustc-host}", &SubstitutionContext::default()).unwrap()
         );
         assert_eq!(
             concat!("hello ", env!("TARGET")),
-            apply_substitutions("hello ${rustc-host}").unwrap()
+            apply_substitutions("hello ${rustc-host}", &SubstitutionContext::default()).unwrap()
         );
         assert_eq!(
             concat!("hello ", env!("TARGET"), "!"),
-            apply_substitutions("hello ${rustc-host}!").unwrap()
+            apply_substitutions("hello ${rustc-host}!", &SubstitutionContext::default()).unwrap()
         );
         assert_eq!(
             concat!("hello ", env!("TARGET"), "}"),
-            apply_substitutions("hello ${rustc-host}}").unwrap()
+            apply_substitutions("hello ${rustc-host}}", &SubstitutionContext::default()).unwrap()
         );
 
         assert!(matches!(
-            apply_substitutions("hello ${").unwrap_err(),
+            apply_substitutions("hello ${", &SubstitutionContext::default()).unwrap_err(),
             ProjectManifestLoadingError::UnterminatedVariableInSubstitution
         ));
         assert!(matches!(
-            apply_substitutions("hello ${missing-var}!").unwrap_err(),
+            apply_substitutions("hello ${missing-var}!", &SubstitutionContext::default()).unwrap_err(),
             ProjectManifestLoadingError::UnknownVariableInSubstitution(s) if s == "missing-var"
         ));
         assert!(matches!(
-            apply_substitutions("hello ${}!").unwrap_err(),
+            apply_substitutions("hello ${}!", &SubstitutionContext::default()).unwrap_err(),
             ProjectManifestLoadingError::UnknownVariableInSubstitution(s) if s.is_empty()
         ));
     }
 
     #[test]
     fn test_apply_substitution() {
-        assert_eq!(env!("TARGET"), apply_substitution("rustc-host").unwrap());
+        assert_eq!(
+            env!("TARGET"),
+            apply_substitution("rustc-host", &SubstitutionContext::default()).unwrap()
+        );
 
         assert!(matches!(
-            apply_substitution("rustc_host").unwrap_err(),
+            apply_substitution("rustc_host", &SubstitutionContext::default()).unwrap_err(),
             ProjectManifestLoadingError::UnknownVariableInSubstitution(s) if s == "rustc_host"
         ));
         assert!(matches!(
-            apply_substitution("").unwrap_err(),
+            apply_substitution("", &SubstitutionContext::default()).unwrap_err(),
             ProjectManifestLoadingError::UnknownVariableInSubstitution(s) if s.is_empty()
         ));
     }
+
+    #[test]
+    fn test_target_substitutions() {
+        let targets = vec![
+            "aarch64-unknown-none".to_string(),
+            "x86_64-unknown-linux-gnu".into(),
+        ];
+        let context = SubstitutionContext::new(&targets);
+
+        assert_eq!(
+            "rust-std-aarch64-unknown-none",
+            apply_substitutions(
+                "rust-std-${target}",
+                &SubstitutionContext::new(&targets[..1])
+            )
+            .unwrap()
+        );
+        assert!(matches!(
+            apply_substitutions("rust-std-${target}", &context).unwrap_err(),
+            ProjectManifestLoadingError::MultipleTargetsForVariableInSubstitution(s, 2)
+                if s == "target"
+        ));
+        assert_eq!(
+            vec![
+                "rust-std-aarch64-unknown-none".to_string(),
+                "rust-std-x86_64-unknown-linux-gnu".into()
+            ],
+            expand_substitutions("rust-std-${targets}", &context).unwrap()
+        );
+        assert_eq!(
+            vec![concat!("rustc-", env!("TARGET")).to_string()],
+            expand_substitutions("rustc-${rustc-host}", &context).unwrap()
+        );
+
+        assert!(matches!(
+            apply_substitutions("rust-std-${targets}", &context).unwrap_err(),
+            ProjectManifestLoadingError::ListVariableNotAllowedInSubstitution(s) if s == "targets"
+        ));
+        assert!(matches!(
+            apply_substitutions("rust-std-${target}", &SubstitutionContext::default()).unwrap_err(),
+            ProjectManifestLoadingError::NoTargetsForVariableInSubstitution(s) if s == "target"
+        ));
+        assert!(matches!(
+            expand_substitutions("rust-std-${targets}", &SubstitutionContext::default())
+                .unwrap_err(),
+            ProjectManifestLoadingError::NoTargetsForVariableInSubstitution(s) if s == "targets"
+        ));
+    }
+
+    #[test]
+    fn test_env_substitutions() {
+        std::env::set_var("CRITICALUP_TEST_SUBSTITUTION", "from-env");
+        std::env::remove_var("CRITICALUP_TEST_SUBSTITUTION_UNSET");
+
+        assert_eq!(
+            "release-from-env",
+            apply_substitutions(
+                "release-${env:CRITICALUP_TEST_SUBSTITUTION}",
+                &SubstitutionContext::default()
+            )
+            .unwrap()
+        );
+        assert!(matches!(
+            apply_substitutions(
+                "${env:CRITICALUP_TEST_SUBSTITUTION_UNSET}",
+                &SubstitutionContext::default()
+            )
+            .unwrap_err(),
+            ProjectManifestLoadingError::UnsetEnvironmentVariableInSubstitution(s)
+                if s == "CRITICALUP_TEST_SUBSTITUTION_UNSET"
+        ));
+    }
 }
@@ -19,21 +19,3 @@ pub(super) struct ProjectManifestProduct {
     pub(super) release: String,
     pub(super) packages: Vec<String>,
 }
-
-pub fn sample_manifest(release: String) -> ProjectManifest {
-    let packages = vec![
-        "cargo-${rustc-host}".into(),
-        "rustc-${rustc-host}".into(),
-        "clippy-${rustc-host}".into(),
-        "rust-std-${rustc-host}".into(),
-        "rustfmt-${rustc-host}".into(),
-        "rust-src".into(),
-    ];
-
-    let product = ProjectManifestProduct { release, packages };
-
-    ProjectManifest {
-        manifest_version: 1,
-        products: HashMap::from([("ferrocene".to_string(), product)]),
-    }
-}
@@ -0,0 +1,60 @@
+// SPDX-FileCopyrightText: The Ferrocene Developers
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use criticaltrust::manifests::ReleaseArtifactFormat;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::{DEFAULT_PROJECT_MANIFEST_VERSION, LATEST_PROJECT_MANIFEST_VERSION};
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ProjectManifest {
+    pub(super) manifest_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) profile: Option<String>,
+    #[serde(default)]
+    pub(super) products: HashMap<String, ProjectManifestProduct>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(super) struct ProjectManifestProduct {
+    pub(super) release: String,
+    pub(super) packages: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(super) targets: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) download_server: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) format: Option<ReleaseArtifactFormat>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) channel: Option<String>,
+}
+
+/// Builds a manifest with a single `ferrocene` product. The manifest only uses the latest
+/// `manifest-version` when it needs to, for the `targets`.
+pub fn manifest(release: String, packages: Vec<String>, targets: Vec<String>) -> ProjectManifest {
+    let manifest_version = if targets.is_empty() {
+        DEFAULT_PROJECT_MANIFEST_VERSION
+    } else {
+        LATEST_PROJECT_MANIFEST_VERSION
+    };
+    let product = ProjectManifestProduct {
+        release,
+        packages,
+        targets,
+        download_server: None,
+        format: None,
+        profile: None,
+        channel: None,
+    };
+
+    ProjectManifest {
+        manifest_version,
+        profile: None,
+        products: HashMap::from([("ferrocene".to_string(), product)]),
+    }
+}
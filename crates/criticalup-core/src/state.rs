@@ -16,7 +16,7 @@ use crate::config::Config;
 use crate::errors::Error::InstallationDoesNotExist;
 use crate::errors::WriteFileError;
 use crate::errors::{self, Error};
-use crate::project_manifest::InstallationId;
+use crate::project_manifest::{InstallationId, ProjectManifest};
 use crate::utils::open_file_for_write;
 
 const CURRENT_FORMAT_VERSION: u32 = 1;
@@ -57,6 +57,60 @@ impl State {
         self.inner.borrow_mut().repr.authentication_token = token;
     }
 
+    /// Returns the authentication token stored in the named profile.
+    pub fn authentication_profile(&self, profile: &str) -> Option<AuthenticationToken> {
+        self.inner
+            .borrow()
+            .repr
+            .authentication_profiles
+            .get(profile)
+            .cloned()
+    }
+
+    /// Stores the authentication token in the named profile, or removes the profile if `None` is
+    /// passed. The default (unnamed) token is managed by `Self::set_authentication_token`.
+    pub fn set_authentication_profile(&self, profile: &str, token: Option<AuthenticationToken>) {
+        let profiles = &mut self.inner.borrow_mut().repr.authentication_profiles;
+        match token {
+            Some(token) => {
+                profiles.insert(profile.to_string(), token);
+            }
+            None => {
+                profiles.remove(profile);
+            }
+        }
+    }
+
+    /// Gets the names of all the authentication profiles, in alphabetical order.
+    pub fn authentication_profile_names(&self) -> Vec<String> {
+        self.inner
+            .borrow()
+            .repr
+            .authentication_profiles
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the rustup toolchains created by `criticalup link create --name`, by name.
+    pub fn links(&self) -> BTreeMap<String, StateLink> {
+        self.inner.borrow().repr.links.clone()
+    }
+
+    /// Records the rustup toolchain `name` linking to the installation of a project, or forgets it
+    /// if `None` is passed.
+    pub fn set_link(&self, name: &str, link: Option<StateLink>) {
+        let links = &mut self.inner.borrow_mut().repr.links;
+        match link {
+            Some(link) => {
+                links.insert(name.to_string(), link);
+            }
+            None => {
+                links.remove(name);
+            }
+        }
+    }
+
     /// Adds or selectively installation in the State for a given `InstallationId`,
     /// a given Manifest path and verified packages.
     ///
@@ -161,6 +215,51 @@ impl State {
         Ok(inner.remove_manifest_from_all_installations(&manifest))
     }
 
+    /// Returns the references to project manifests that no longer exist, or whose products no
+    /// longer hash to the installation referencing them, without changing the state.
+    ///
+    /// Project manifests that fail to load are not stale, as they might only be temporarily broken
+    /// (for example when an environment variable used in a substitution is not set).
+    pub fn stale_manifests(&self) -> Vec<StaleManifest> {
+        let inner = self.inner.borrow();
+        let mut stale = Vec::new();
+
+        for (installation_id, installation) in inner.repr.installations.iter() {
+            for manifest in &installation.manifests {
+                if let Some(reason) = stale_manifest_reason(installation_id, manifest) {
+                    stale.push(StaleManifest {
+                        installation: installation_id.clone(),
+                        manifest: manifest.clone(),
+                        reason,
+                    });
+                }
+            }
+        }
+
+        stale
+    }
+
+    /// Removes the references returned by [`State::stale_manifests`], and returns them: the project
+    /// manifests which were deleted, and the ones edited so that none of their products match the
+    /// installation anymore. Manifests which could not be checked or loaded are kept.
+    pub fn prune_stale_manifests(&self) -> Vec<StaleManifest> {
+        let stale = self.stale_manifests();
+
+        let mut inner = self.inner.borrow_mut();
+        for StaleManifest {
+            installation,
+            manifest,
+            ..
+        } in &stale
+        {
+            if let Some(installation) = inner.repr.installations.get_mut(installation) {
+                installation.manifests.remove(manifest);
+            }
+        }
+
+        stale
+    }
+
     /// Remove an installation from the `State` for a given `InstallationId`.
     pub fn remove_installation(&self, installation_id: &InstallationId) {
         self.inner
@@ -315,8 +414,12 @@ impl StateInner {
 struct StateRepr {
     version: u32,
     authentication_token: Option<AuthenticationToken>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    authentication_profiles: BTreeMap<String, AuthenticationToken>,
     #[serde(default)]
     installations: BTreeMap<InstallationId, StateInstallation>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    links: BTreeMap<String, StateLink>,
 }
 
 impl Default for StateRepr {
@@ -324,7 +427,9 @@ impl Default for StateRepr {
         Self {
             version: CURRENT_FORMAT_VERSION,
             authentication_token: None,
+            authentication_profiles: BTreeMap::new(),
             installations: BTreeMap::new(),
+            links: BTreeMap::new(),
         }
     }
 }
@@ -344,6 +449,67 @@ impl StateInstallation {
     }
 }
 
+/// A rustup toolchain linked directly to the installation of a project.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StateLink {
+    /// Canonical path of the project manifest the toolchain follows.
+    pub manifest: PathBuf,
+    /// Installation directory the toolchain currently links to.
+    pub path: PathBuf,
+}
+
+fn stale_manifest_reason(
+    installation_id: &InstallationId,
+    manifest: &Path,
+) -> Option<StaleManifestReason> {
+    // Only manifests known to be deleted are missing, not ones temporarily unreadable. Manifests
+    // which still exist are only stale if they load and no longer match the installation.
+    match manifest.try_exists() {
+        Ok(false) => Some(StaleManifestReason::Missing),
+        Ok(true) => match ProjectManifest::load(manifest) {
+            Ok(loaded)
+                if !loaded
+                    .products()
+                    .iter()
+                    .any(|product| product.installation_id() == *installation_id) =>
+            {
+                Some(StaleManifestReason::Changed)
+            }
+            Ok(_) => None,
+            Err(err) => {
+                tracing::debug!(
+                    "Keeping project manifest {} that failed to load: {err}",
+                    manifest.display()
+                );
+                None
+            }
+        },
+        Err(err) => {
+            tracing::debug!(
+                "Keeping project manifest {} that could not be checked: {err}",
+                manifest.display()
+            );
+            None
+        }
+    }
+}
+
+/// Reference to a project manifest removed by [`State::prune_stale_manifests`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleManifest {
+    pub installation: InstallationId,
+    pub manifest: PathBuf,
+    pub reason: StaleManifestReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaleManifestReason {
+    /// The project manifest was moved or deleted.
+    Missing,
+    /// The project manifest was edited, and none of its products match the installation anymore.
+    Changed,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 #[cfg_attr(test, derive(PartialEq, Eq))]
 pub struct AuthenticationToken(String);
@@ -426,7 +592,9 @@ mod tests {
             serde_json::to_vec_pretty(&StateRepr {
                 version: CURRENT_FORMAT_VERSION,
                 authentication_token: Some(AuthenticationToken("hello".into())),
+                authentication_profiles: BTreeMap::new(),
                 installations: BTreeMap::new(),
+                links: BTreeMap::new(),
             })
             .unwrap(),
         )
@@ -758,6 +926,72 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_set_authentication_profile() {
+        let test_env = TestEnvironment::with().state().prepare().await;
+        let state = test_env.state();
+        state.set_authentication_token(None);
+
+        assert!(state.authentication_profile_names().is_empty());
+        assert_eq!(None, state.authentication_profile("acme"));
+
+        state.set_authentication_profile("acme", Some(AuthenticationToken("acme token".into())));
+        state.set_authentication_profile("beta", Some(AuthenticationToken("beta token".into())));
+        assert_eq!(
+            Some(AuthenticationToken("acme token".into())),
+            state.authentication_profile("acme")
+        );
+        assert_eq!(
+            vec!["acme".to_string(), "beta".to_string()],
+            state.authentication_profile_names()
+        );
+
+        // Named profiles do not affect the default token.
+        assert_eq!(None, state.authentication_token().await);
+
+        state.set_authentication_profile("acme", None);
+        assert_eq!(None, state.authentication_profile("acme"));
+        assert_eq!(
+            vec!["beta".to_string()],
+            state.authentication_profile_names()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_persist_state_with_authentication_profiles() {
+        let test_env = TestEnvironment::with().state().prepare().await;
+        test_env.state().set_authentication_token(None);
+
+        let token = AuthenticationToken("acme token".into());
+        test_env
+            .state()
+            .set_authentication_profile("acme", Some(token.clone()));
+        test_env.state().persist().await.unwrap();
+
+        let new_state = State::load(test_env.config()).await.unwrap();
+        assert_eq!(Some(token), new_state.authentication_profile("acme"));
+        assert_eq!(None, new_state.authentication_token().await);
+    }
+
+    #[tokio::test]
+    async fn test_persist_state_with_links() {
+        let test_env = TestEnvironment::with().state().prepare().await;
+        let link = StateLink {
+            manifest: "/project/criticalup.toml".into(),
+            path: "/toolchains/abc".into(),
+        };
+        test_env.state().set_link("project", Some(link.clone()));
+        test_env.state().set_link("other", Some(link.clone()));
+        test_env.state().set_link("other", None);
+        test_env.state().persist().await.unwrap();
+
+        let new_state = State::load(test_env.config()).await.unwrap();
+        assert_eq!(
+            BTreeMap::from([("project".to_string(), link)]),
+            new_state.links()
+        );
+    }
+
     #[tokio::test]
     async fn test_persist_state() {
         let test_env = TestEnvironment::with().state().prepare().await;
@@ -902,7 +1136,9 @@ mod tests {
             StateRepr {
                 version: 1,
                 authentication_token: None,
+                authentication_profiles: BTreeMap::new(),
                 installations: BTreeMap::new(),
+                links: BTreeMap::new(),
             },
             StateRepr::default()
         );
@@ -984,4 +1220,87 @@ mod tests {
             unused_installations
         )
     }
+
+    #[tokio::test]
+    async fn prune_stale_manifests() {
+        let test_env = TestEnvironment::with().state().prepare().await;
+        let root = test_env.root();
+        let state = test_env.state();
+        let manifest = "manifest-version = 1\n\n\
+            [products.ferrocene]\n\
+            release = \"25.02.0\"\n\
+            packages = [\"rustc\"]\n";
+
+        let mut manifests = Vec::new();
+        for project in ["kept", "missing", "changed", "broken", "unreadable"] {
+            let dir = root.join(project);
+            tokio::fs::create_dir_all(&dir).await.unwrap();
+            let path = dir.join("criticalup.toml");
+            tokio::fs::write(&path, manifest).await.unwrap();
+            manifests.push(path);
+        }
+        let installation_id =
+            ProjectManifest::load(&manifests[0]).unwrap().products()[0].installation_id();
+        tokio::fs::create_dir_all(
+            test_env
+                .config()
+                .paths
+                .installation_dir
+                .join(&installation_id.0),
+        )
+        .await
+        .unwrap();
+        state
+            .add_installation(&installation_id, &[], &manifests[0], test_env.config())
+            .unwrap();
+        for path in &manifests[1..] {
+            state
+                .update_installation_manifests(&installation_id, path)
+                .unwrap();
+        }
+        let canonical: Vec<_> = manifests
+            .iter()
+            .map(|m| m.canonicalize().unwrap())
+            .collect();
+
+        tokio::fs::remove_dir_all(root.join("missing"))
+            .await
+            .unwrap();
+        tokio::fs::write(&manifests[2], manifest.replace("rustc", "cargo"))
+            .await
+            .unwrap();
+        tokio::fs::write(&manifests[3], "not toml").await.unwrap();
+        // The manifest can't be checked, as its parent directory is now a file.
+        tokio::fs::remove_dir_all(root.join("unreadable"))
+            .await
+            .unwrap();
+        tokio::fs::write(root.join("unreadable"), "").await.unwrap();
+
+        assert_eq!(
+            vec![
+                StaleManifest {
+                    installation: installation_id.clone(),
+                    manifest: canonical[2].clone(),
+                    reason: StaleManifestReason::Changed,
+                },
+                StaleManifest {
+                    installation: installation_id.clone(),
+                    manifest: canonical[1].clone(),
+                    reason: StaleManifestReason::Missing,
+                },
+            ],
+            state.stale_manifests()
+        );
+        assert_eq!(state.stale_manifests(), state.prune_stale_manifests());
+        // Broken and unreadable manifests are kept.
+        assert_eq!(
+            &BTreeSet::from([
+                canonical[0].clone(),
+                canonical[3].clone(),
+                canonical[4].clone()
+            ]),
+            state.installations()[&installation_id].manifests()
+        );
+        assert!(state.prune_stale_manifests().is_empty());
+    }
 }
@@ -6,10 +6,12 @@ use crate::download_server_client::{Connectivity, DownloadServerClient};
 use crate::state::{AuthenticationToken, State};
 use axum::http::{Request, Response};
 use criticaltrust::keys::{EphemeralKeyPair, KeyAlgorithm, KeyPair, KeyRole, PublicKey};
+use criticaltrust::manifests::RootRotation;
 use criticaltrust::signatures::SignedPayload;
 use mock_download_server::MockServer;
 use std::path::Path;
 use tempfile::TempDir;
+use time::{Duration, OffsetDateTime};
 use tokio::sync::MappedMutexGuard;
 
 pub(crate) const SAMPLE_AUTH_TOKEN: &str = "criticalup_token_foo";
@@ -33,6 +35,7 @@ impl TestEnvironment {
             state: false,
             download_server: false,
             keys: false,
+            root_rotation: false,
             root_in_subdir: None,
         }
     }
@@ -90,6 +93,7 @@ pub(crate) struct TestEnvironmentBuilder {
     state: bool,
     download_server: bool,
     keys: bool,
+    root_rotation: bool,
     root_in_subdir: Option<String>,
 }
 
@@ -109,6 +113,12 @@ impl TestEnvironmentBuilder {
         self.state().keys()
     }
 
+    /// Serve a rotation from the trust root to the alternate trust root.
+    pub(crate) fn root_rotation(mut self) -> Self {
+        self.root_rotation = true;
+        self.download_server()
+    }
+
     pub(crate) fn root_in_subdir(mut self, subdir: &str) -> Self {
         self.root_in_subdir = Some(subdir.into());
         self
@@ -150,7 +160,14 @@ impl TestEnvironmentBuilder {
 
         let mock_server = if self.download_server {
             let keys = keys.as_ref().unwrap();
-            let server = start_mock_server(keys.signed_public_keys().await, &keys.revocation).await;
+            let rotation = if self.root_rotation {
+                Some(keys.root_rotation().await)
+            } else {
+                None
+            };
+            let server =
+                start_mock_server(keys.signed_public_keys().await, &keys.revocation, rotation)
+                    .await;
             config.whitelabel.download_server_url = server.url();
             Some(server)
         } else {
@@ -239,18 +256,33 @@ impl TestKeys {
         result.push(sign(&self.packages, &[&self.root]).await);
         result.push(sign(&self.releases, &[&self.root]).await);
         result.push(sign(&self.redirects, &[&self.root]).await);
-        result.push(sign(&self.revocation, &[&self.root]).await);
 
         result.push(sign(&self.alternate_root, &[&self.alternate_trust_root]).await);
         result.push(sign(&self.alternate_packages, &[&self.alternate_root]).await);
 
+        // Also signed by the alternate root, to keep verifying the revocation information after
+        // rotating the trust root. Keys are loaded in order, so it has to come after it.
+        result.push(sign(&self.revocation, &[&self.root, &self.alternate_root]).await);
+
         result
     }
+
+    /// Rotation from the trust root to the alternate trust root, already in effect.
+    pub(crate) async fn root_rotation(&self) -> SignedPayload<RootRotation> {
+        let mut payload = SignedPayload::new(&RootRotation {
+            root: self.alternate_trust_root.public().clone(),
+            effective_at: OffsetDateTime::now_utc() - Duration::days(1),
+        })
+        .unwrap();
+        payload.add_signature(&self.trust_root).await.unwrap();
+        payload
+    }
 }
 
 async fn start_mock_server(
     keys: Vec<SignedPayload<PublicKey>>,
     revocation_key: &EphemeralKeyPair,
+    root_rotation: Option<SignedPayload<RootRotation>>,
 ) -> MockServer {
     use mock_download_server::AuthenticationToken;
 
@@ -268,6 +300,9 @@ async fn start_mock_server(
         builder = builder.add_key(key);
     }
 
+    if let Some(rotation) = root_rotation {
+        builder = builder.add_root_rotation(rotation);
+    }
     builder = builder.add_revocation_info(revocation_key).await;
 
     builder.start().await
@@ -4,16 +4,17 @@
 use std::str::FromStr;
 use std::sync::Arc;
 
-use crate::{AuthenticationToken, Data};
+use crate::{AuthenticationToken, Data, TokenRejection};
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use axum::response::{IntoResponse, Response};
 use axum::Json;
 use axum_extra::headers::authorization::Bearer;
 use axum_extra::headers::{self, IfNoneMatch};
 use axum_extra::{headers::Authorization, TypedHeader};
 use criticaltrust::manifests::ManifestVersion;
 use md5::Digest;
+use serde_json::json;
 use tokio::sync::Mutex;
 
 pub(crate) async fn handle_v1_package(
@@ -95,9 +96,9 @@ pub(crate) async fn handle_package(
 pub(crate) async fn handle_v1_tokens_current(
     State(data): State<Arc<Mutex<Data>>>,
     TypedHeader(bearer): TypedHeader<Authorization<Bearer>>,
-) -> Result<Json<AuthenticationToken>, StatusCode> {
+) -> Result<Json<AuthenticationToken>, Response> {
     let data = data.lock().await;
-    let token = authorize(&data, bearer)?;
+    let token = authorize(&data, bearer).map_err(unauthorized)?;
     Ok(Json(token.clone()))
 }
 
@@ -107,31 +108,46 @@ pub(crate) async fn handle_v1_keys(State(data): State<Arc<Mutex<Data>>>) -> impl
         version: ManifestVersion,
         keys: data.keys.clone(),
         revoked_signatures: data.revoked_signatures.clone(),
+        root_rotations: data.root_rotations.clone(),
     })
 }
 
 pub(crate) async fn handle_v1_release(
     State(data): State<Arc<Mutex<Data>>>,
     Path((product, release)): Path<(String, String)>,
-) -> impl IntoResponse {
+) -> Response {
     let data = data.lock().await;
-    let rm = data
+    match data
         .release_manifests
         .get(&(product.to_string(), release.to_string()))
-        .expect("Did not get a release manifest")
-        .to_owned();
-    Json(rm)
+    {
+        Some(rm) => Json(rm.to_owned()).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
 }
 
 fn authorize(
     data: &Data,
     bearer: Authorization<Bearer>,
-) -> Result<&AuthenticationToken, StatusCode> {
+) -> Result<&AuthenticationToken, Option<TokenRejection>> {
     let token = bearer.token();
 
-    if let Some(token) = data.tokens.get(token) {
+    if let Some(reason) = data.rejected_tokens.get(token) {
+        Err(Some(*reason))
+    } else if let Some(token) = data.tokens.get(token) {
         Ok(token)
     } else {
-        Err(StatusCode::FORBIDDEN)
+        Err(None)
+    }
+}
+
+fn unauthorized(reason: Option<TokenRejection>) -> Response {
+    match reason {
+        Some(reason) => (
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": reason.as_error_code() })),
+        )
+            .into_response(),
+        None => StatusCode::FORBIDDEN.into_response(),
     }
 }
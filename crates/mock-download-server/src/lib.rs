@@ -12,7 +12,7 @@ use axum::response::Redirect;
 use axum::routing::get;
 use axum::Router;
 use criticaltrust::keys::{EphemeralKeyPair, PublicKey};
-use criticaltrust::manifests::ReleaseManifest;
+use criticaltrust::manifests::{ReleaseManifest, RootRotation};
 use criticaltrust::revocation_info::RevocationInfo;
 use criticaltrust::signatures::SignedPayload;
 use handlers::{
@@ -36,11 +36,31 @@ pub struct AuthenticationToken {
     pub expires_at: Option<Cow<'static, str>>,
 }
 
+/// Reason returned by the server when it explicitly rejects an authentication token.
+#[derive(Clone, Copy, Debug)]
+pub enum TokenRejection {
+    Expired,
+    Revoked,
+    Malformed,
+}
+
+impl TokenRejection {
+    pub(crate) fn as_error_code(&self) -> &'static str {
+        match self {
+            TokenRejection::Expired => "token-expired",
+            TokenRejection::Revoked => "token-revoked",
+            TokenRejection::Malformed => "token-malformed",
+        }
+    }
+}
+
 pub struct Data {
     pub keypairs: HashMap<String, EphemeralKeyPair>,
     pub tokens: HashMap<String, AuthenticationToken>,
+    pub rejected_tokens: HashMap<String, TokenRejection>,
     pub keys: Vec<SignedPayload<PublicKey>>,
     pub revoked_signatures: SignedPayload<RevocationInfo>,
+    pub root_rotations: Vec<SignedPayload<RootRotation>>,
     pub release_manifests: HashMap<(String, String), ReleaseManifest>,
     pub release_packages: HashMap<(String, String, String), Vec<u8>>,
     pub history: Vec<(Request<Body>, Response<Body>)>,
@@ -57,12 +77,14 @@ impl Builder {
             data: Data {
                 keypairs: HashMap::new(),
                 tokens: HashMap::new(),
+                rejected_tokens: HashMap::new(),
                 keys: Vec::new(),
                 revoked_signatures: SignedPayload::new(&RevocationInfo::new(
                     Vec::new(),
                     OffsetDateTime::now_utc() + EXPIRATION_EXTENSION_IN_DAYS,
                 ))
                 .unwrap(),
+                root_rotations: Vec::new(),
                 release_manifests: HashMap::new(),
                 release_packages: HashMap::new(),
                 history: Vec::new(),
@@ -81,11 +103,21 @@ impl Builder {
         self
     }
 
+    pub fn add_rejected_token(mut self, token: &str, reason: TokenRejection) -> Self {
+        self.data.rejected_tokens.insert(token.into(), reason);
+        self
+    }
+
     pub fn add_key(mut self, key: SignedPayload<PublicKey>) -> Self {
         self.data.keys.push(key);
         self
     }
 
+    pub fn add_root_rotation(mut self, rotation: SignedPayload<RootRotation>) -> Self {
+        self.data.root_rotations.push(rotation);
+        self
+    }
+
     pub async fn add_revocation_info(mut self, revocation_key: &EphemeralKeyPair) -> Self {
         self.data
             .revoked_signatures